@@ -0,0 +1,33 @@
+-- ingredient stock of a game, in the units of drink recipes (cl); used stock is recorded per
+-- turn in turn_ingredient_usage
+CREATE TABLE ingredient_stock
+(
+    game_id       INTEGER NOT NULL REFERENCES games (game_id) ON DELETE CASCADE,
+    ingredient_id INTEGER NOT NULL REFERENCES ingredients (ingredient_id) ON DELETE CASCADE,
+    opening       FLOAT   NOT NULL CHECK (opening >= 0),
+    -- the IE is warned when the remaining stock falls to this level, or runs out if null
+    low_stock     FLOAT CHECK (low_stock >= 0),
+    PRIMARY KEY (game_id, ingredient_id)
+);
+
+-- stock brought in during a game
+CREATE TABLE ingredient_restocks
+(
+    restock_id    SERIAL PRIMARY KEY,
+    game_id       INTEGER     NOT NULL,
+    ingredient_id INTEGER     NOT NULL,
+    quantity      FLOAT       NOT NULL CHECK (quantity > 0),
+    uid           INTEGER REFERENCES users (uid) ON DELETE SET NULL,
+    created_at    TIMESTAMPTZ NOT NULL DEFAULT now(),
+    FOREIGN KEY (game_id, ingredient_id) REFERENCES ingredient_stock (game_id, ingredient_id) ON DELETE CASCADE
+);
+
+-- ingredients used by the drinks of a turn, recorded from the recipes of the drinks when they
+-- are mixed (or delivered without mixing) and cleared if they go back to the queue
+CREATE TABLE turn_ingredient_usage
+(
+    turn_id       INTEGER NOT NULL REFERENCES turns (turn_id) ON DELETE CASCADE,
+    ingredient_id INTEGER NOT NULL REFERENCES ingredients (ingredient_id) ON DELETE CASCADE,
+    quantity      FLOAT   NOT NULL,
+    PRIMARY KEY (turn_id, ingredient_id)
+);
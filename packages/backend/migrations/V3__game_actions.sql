@@ -0,0 +1,16 @@
+-- append-only log of referee actions within a game
+-- before/after hold the state of the teams the action touched (see database/actions.rs)
+CREATE TABLE IF NOT EXISTS game_actions
+(
+    action_id  SERIAL PRIMARY KEY,
+    game_id    INTEGER     NOT NULL REFERENCES games (game_id) ON DELETE CASCADE,
+    uid        INTEGER REFERENCES users (uid) ON DELETE SET NULL,
+    action     TEXT        NOT NULL,
+    -- the action this one reverts: an undo reverts an action, a redo reverts an undo
+    reverts    INTEGER REFERENCES game_actions (action_id),
+    before     JSONB       NOT NULL,
+    after      JSONB       NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX idx_game_actions_game_id ON game_actions (game_id);
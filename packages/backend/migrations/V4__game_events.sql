@@ -0,0 +1,13 @@
+-- ordered stream of what happened in each game, replayed by engine/replay.rs
+CREATE TABLE IF NOT EXISTS game_events
+(
+    event_id   SERIAL PRIMARY KEY,
+    game_id    INTEGER     NOT NULL REFERENCES games (game_id) ON DELETE CASCADE,
+    -- the logged action that produced the event; events of undone actions are skipped on replay
+    action_id  INTEGER REFERENCES game_actions (action_id) ON DELETE SET NULL,
+    event      JSONB       NOT NULL,
+    -- same as the NOW() of the change, since both happen in one transaction
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX idx_game_events_game_id ON game_events (game_id);
@@ -0,0 +1,8 @@
+-- boards are frozen once a game has started on them, so that editing a board can never change
+-- how the history of its games is interpreted; clone the board to make changes
+ALTER TABLE boards
+    ADD COLUMN frozen BOOLEAN NOT NULL DEFAULT FALSE;
+
+UPDATE boards
+SET frozen = TRUE
+WHERE EXISTS (SELECT 1 FROM games WHERE games.board_id = boards.board_id AND games.started);
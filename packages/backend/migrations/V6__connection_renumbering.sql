@@ -0,0 +1,12 @@
+-- renumbering a board place carries its connections along, like its place_drinks
+ALTER TABLE place_connections
+    DROP CONSTRAINT place_connections_board_id_origin_fkey,
+    DROP CONSTRAINT place_connections_board_id_target_fkey,
+    ADD CONSTRAINT place_connections_board_id_origin_fkey
+        FOREIGN KEY (board_id, origin)
+            REFERENCES board_places (board_id, place_number)
+            ON DELETE CASCADE ON UPDATE CASCADE,
+    ADD CONSTRAINT place_connections_board_id_target_fkey
+        FOREIGN KEY (board_id, target)
+            REFERENCES board_places (board_id, place_number)
+            ON DELETE CASCADE ON UPDATE CASCADE;
@@ -0,0 +1,16 @@
+-- per-game drinking limits, in standard drinks per team; a null limit is not enforced
+CREATE TYPE LIMITACTION AS ENUM ('Flag', 'Substitute', 'RequireOverride');
+
+CREATE TABLE drinking_limits
+(
+    game_id             INTEGER PRIMARY KEY REFERENCES games (game_id) ON DELETE CASCADE,
+    per_hour            FLOAT,
+    per_game            FLOAT,
+    per_turn            FLOAT,
+    action              LIMITACTION NOT NULL DEFAULT 'Flag',
+    substitute_drink_id INTEGER REFERENCES drinks (drink_id) ON DELETE SET NULL
+);
+
+-- set when a turn was confirmed over a drinking limit of its game
+ALTER TABLE turns
+    ADD COLUMN over_limit BOOLEAN NOT NULL DEFAULT FALSE;
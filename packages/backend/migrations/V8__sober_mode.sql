@@ -0,0 +1,11 @@
+-- non-alcoholic equivalent of a drink, served instead of it to teams in sober mode
+ALTER TABLE drinks
+    ADD COLUMN non_alcoholic_id INTEGER REFERENCES drinks (drink_id) ON DELETE SET NULL;
+
+-- whether the drinks of a team are swapped for their non-alcoholic equivalents
+ALTER TABLE teams
+    ADD COLUMN sober BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- the drink that a turn drink replaced, if it was substituted
+ALTER TABLE turn_drinks
+    ADD COLUMN substituted_for INTEGER REFERENCES drinks (drink_id) ON DELETE SET NULL;
@@ -0,0 +1,20 @@
+-- the players of a team
+CREATE TABLE players
+(
+    player_id   SERIAL PRIMARY KEY,
+    team_id     INTEGER NOT NULL REFERENCES teams (team_id) ON DELETE CASCADE,
+    player_name TEXT    NOT NULL,
+    -- sober players are not served alcoholic drinks
+    sober       BOOLEAN NOT NULL DEFAULT FALSE
+);
+
+-- how many servings of a turn drink each player drank; changing the drinks of a turn clears them
+CREATE TABLE turn_drink_players
+(
+    turn_id   INTEGER NOT NULL,
+    drink_id  INTEGER NOT NULL,
+    player_id INTEGER NOT NULL REFERENCES players (player_id) ON DELETE CASCADE,
+    n         INTEGER NOT NULL CHECK (n > 0),
+    PRIMARY KEY (turn_id, drink_id, player_id),
+    FOREIGN KEY (drink_id, turn_id) REFERENCES turn_drinks (drink_id, turn_id) ON DELETE CASCADE
+);
@@ -0,0 +1,12 @@
+pub mod utils;
+
+use self::utils::*;
+use crate::utils::state::AppState;
+use axum::routing::{delete, get};
+use axum::Router;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(connections_get).put(connections_put))
+        .route("/{origin}/{target}", delete(connection_delete))
+}
@@ -0,0 +1,39 @@
+use crate::database::boards::{delete_connection, get_connections, upsert_connections};
+use crate::utils::errors::wrap_json;
+use crate::utils::ids::BoardId;
+use crate::utils::state::{AppError, AppState};
+use crate::utils::types::BoardConnections;
+use axum::extract::{Path, State};
+use axum::Json;
+use deadpool_postgres::Client;
+
+pub async fn connections_get(
+    Path(board_id): Path<BoardId>,
+    state: State<AppState>,
+) -> Result<Json<BoardConnections>, AppError> {
+    let client: Client = state.db.get().await?;
+    wrap_json(get_connections(&client, board_id).await)
+}
+
+/// Creates or updates the given connections in one transaction, returning all connections
+/// of the board.
+pub async fn connections_put(
+    Path(board_id): Path<BoardId>,
+    state: State<AppState>,
+    Json(body): Json<BoardConnections>,
+) -> Result<Json<BoardConnections>, AppError> {
+    let mut client: Client = state.db.get().await?;
+    let tx = client.transaction().await?;
+    upsert_connections(&tx, board_id, &body.connections).await?;
+    let connections = get_connections(&tx, board_id).await?;
+    tx.commit().await?;
+    Ok(Json(connections))
+}
+
+pub async fn connection_delete(
+    Path((board_id, origin, target)): Path<(BoardId, i32, i32)>,
+    state: State<AppState>,
+) -> Result<Json<u64>, AppError> {
+    let client: Client = state.db.get().await?;
+    wrap_json(delete_connection(&client, board_id, origin, target).await)
+}
@@ -1,16 +1,26 @@
 use crate::api::v1::boards::utils::boards_post;
 use crate::utils::state::AppState;
-use axum::routing::get;
+use axum::routing::{get, post};
 use axum::Router;
 
 pub mod utils;
-use self::utils::{boards_get, boards_get_id};
+use self::utils::{
+    boards_analysis, boards_clone, boards_export, boards_get, boards_get_id, boards_import,
+    boards_validate,
+};
 
+pub mod connections;
 pub mod places;
 
 pub fn router() -> Router<AppState> {
     Router::new()
         .nest("/places", places::router())
+        .nest("/{id}/connections", connections::router())
         .route("/{id}", get(boards_get_id))
+        .route("/{id}/validate", get(boards_validate))
+        .route("/{id}/export", get(boards_export))
+        .route("/{id}/analysis", get(boards_analysis))
+        .route("/{id}/clone", post(boards_clone))
+        .route("/import", post(boards_import))
         .route("/", get(boards_get).post(boards_post))
 }
@@ -1,6 +1,6 @@
 use crate::api::v1::boards::utils::board_place_post;
 use crate::utils::state::AppState;
-use axum::routing::{get, patch};
+use axum::routing::{get, patch, put};
 use axum::Router;
 
 pub mod utils;
@@ -13,5 +13,10 @@ pub fn router() -> Router<AppState> {
         .route("/", get(places_get).post(places_post))
         .route("/{board_id}/coordinate", patch(coordinate_patch))
         .route("/{id}", get(board_places_get).post(board_place_post))
+        .route("/place/{place_id}", put(place_put).delete(place_delete))
+        .route(
+            "/{board_id}/{place_number}",
+            patch(board_place_patch).delete(board_place_delete),
+        )
         .nest("/drinks", drinks::router())
 }
@@ -1,8 +1,13 @@
-use crate::database::boards::{add_place, get_board_places, get_places, update_coordinates};
+use crate::database::boards::{
+    add_place, delete_board_place, delete_place, get_board_places, get_places, update_board_place,
+    update_coordinates, update_place,
+};
 use crate::utils::errors::wrap_json;
-use crate::utils::ids::BoardId;
+use crate::utils::ids::{BoardId, PlaceId};
 use crate::utils::state::{AppError, AppState};
-use crate::utils::types::{BoardPlace, BoardPlaces, Place, Places};
+use crate::utils::types::{
+    BoardEditResult, BoardPlace, BoardPlaceUpdate, BoardPlaces, Place, Places,
+};
 use axum::extract::{Path, State};
 use axum::Json as AxumJson;
 use deadpool_postgres::Client;
@@ -36,3 +41,49 @@ pub async fn coordinate_patch(
     let client: Client = state.db.get().await?;
     wrap_json(update_coordinates(&client, board_id, &place).await)
 }
+
+pub async fn place_put(
+    Path(place_id): Path<PlaceId>,
+    state: State<AppState>,
+    AxumJson(place): AxumJson<Place>,
+) -> Result<AxumJson<BoardEditResult>, AppError> {
+    let mut client: Client = state.db.get().await?;
+    let tx = client.transaction().await?;
+    let result = update_place(&tx, place_id, place).await?;
+    tx.commit().await?;
+    Ok(AxumJson(result))
+}
+
+pub async fn place_delete(
+    Path(place_id): Path<PlaceId>,
+    state: State<AppState>,
+) -> Result<AxumJson<BoardEditResult>, AppError> {
+    let mut client: Client = state.db.get().await?;
+    let tx = client.transaction().await?;
+    let result = delete_place(&tx, place_id).await?;
+    tx.commit().await?;
+    Ok(AxumJson(result))
+}
+
+pub async fn board_place_patch(
+    Path((board_id, place_number)): Path<(BoardId, i32)>,
+    state: State<AppState>,
+    AxumJson(update): AxumJson<BoardPlaceUpdate>,
+) -> Result<AxumJson<BoardEditResult>, AppError> {
+    let mut client: Client = state.db.get().await?;
+    let tx = client.transaction().await?;
+    let result = update_board_place(&tx, board_id, place_number, update).await?;
+    tx.commit().await?;
+    Ok(AxumJson(result))
+}
+
+pub async fn board_place_delete(
+    Path((board_id, place_number)): Path<(BoardId, i32)>,
+    state: State<AppState>,
+) -> Result<AxumJson<BoardEditResult>, AppError> {
+    let mut client: Client = state.db.get().await?;
+    let tx = client.transaction().await?;
+    let result = delete_board_place(&tx, board_id, place_number).await?;
+    tx.commit().await?;
+    Ok(AxumJson(result))
+}
@@ -1,9 +1,18 @@
-use crate::database::boards::{add_board_place, get_board, get_boards, post_board};
+use crate::database::board_transfer::{clone_board, export_board, import_board};
+use crate::database::boards::{
+    add_board_place, get_board, get_board_places, get_boards, post_board,
+};
+use crate::database::drinks::get_drinks;
+use crate::engine::analysis::analyse_board;
+use crate::engine::validation::validate_board;
 use crate::utils::errors::wrap_json;
 use crate::utils::ids::BoardId;
 use crate::utils::state::{AppError, AppState};
-use crate::utils::types::{Board, BoardPlace, Boards};
-use axum::extract::{Path, State};
+use crate::utils::types::{
+    Board, BoardAnalysis, BoardExport, BoardImportQuery, BoardImportResult, BoardPlace,
+    BoardValidation, Boards, CloneBoardBody, DiagnosticSeverity,
+};
+use axum::extract::{Path, Query, State};
 use axum::Json;
 use deadpool_postgres::Client;
 
@@ -20,6 +29,67 @@ pub async fn boards_get_id(
     wrap_json(get_board(&client, board_id).await)
 }
 
+pub async fn boards_validate(
+    Path(board_id): Path<BoardId>,
+    state: State<AppState>,
+) -> Result<Json<BoardValidation>, AppError> {
+    let client: Client = state.db.get().await?;
+    let board_places = get_board_places(&client, board_id).await?;
+    let catalog = get_drinks(&client).await?;
+    let diagnostics = validate_board(&board_places, &catalog);
+    Ok(Json(BoardValidation {
+        board_id,
+        valid: !diagnostics
+            .iter()
+            .any(|d| d.severity == DiagnosticSeverity::Error),
+        diagnostics,
+    }))
+}
+
+pub async fn boards_analysis(
+    Path(board_id): Path<BoardId>,
+    state: State<AppState>,
+) -> Result<Json<BoardAnalysis>, AppError> {
+    let client: Client = state.db.get().await?;
+    let board_places = get_board_places(&client, board_id).await?;
+    if board_places.board.id != board_id {
+        return Err(AppError::NotFound(format!("Board {} not found", board_id)));
+    }
+    wrap_json(analyse_board(&board_places))
+}
+
+pub async fn boards_export(
+    Path(board_id): Path<BoardId>,
+    state: State<AppState>,
+) -> Result<Json<BoardExport>, AppError> {
+    let client: Client = state.db.get().await?;
+    wrap_json(export_board(&client, board_id).await)
+}
+
+pub async fn boards_import(
+    state: State<AppState>,
+    Query(query): Query<BoardImportQuery>,
+    Json(export): Json<BoardExport>,
+) -> Result<Json<BoardImportResult>, AppError> {
+    let mut client: Client = state.db.get().await?;
+    let tx = client.transaction().await?;
+    let result = import_board(&tx, &export, query.on_conflict.unwrap_or_default()).await?;
+    tx.commit().await?;
+    Ok(Json(result))
+}
+
+pub async fn boards_clone(
+    Path(board_id): Path<BoardId>,
+    state: State<AppState>,
+    Json(body): Json<CloneBoardBody>,
+) -> Result<Json<Board>, AppError> {
+    let mut client: Client = state.db.get().await?;
+    let tx = client.transaction().await?;
+    let board = clone_board(&tx, board_id, &body.name).await?;
+    tx.commit().await?;
+    Ok(Json(board))
+}
+
 pub async fn boards_post(
     state: State<AppState>,
     Json(board): Json<Board>,
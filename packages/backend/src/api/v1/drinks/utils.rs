@@ -1,4 +1,6 @@
-use crate::database::drinks::{delete_drink, get_drinks_ingredients, post_drink, update_drink};
+use crate::database::drinks::{
+    delete_drink, get_drink_alcohol, get_drinks, get_drinks_ingredients, post_drink, update_drink,
+};
 use crate::utils::errors::wrap_json;
 use crate::utils::ids::DrinkId;
 use crate::utils::remove_ingredients;
@@ -23,6 +25,7 @@ pub async fn drinks_post(
 ) -> Result<Json<u64>, AppError> {
     tracing::info!("{} {}", drink.name, drink.id);
     let client: Client = state.db.get().await?;
+    check_non_alcoholic(&client, &drink).await?;
     wrap_json(post_drink(&client, drink).await)
 }
 
@@ -43,5 +46,40 @@ pub async fn drink_patch(
     Json(drink): Json<Drink>,
 ) -> Result<Json<u64>, AppError> {
     let client: Client = state.db.get().await?;
+    check_non_alcoholic(&client, &drink).await?;
     wrap_json(update_drink(&client, drink).await)
 }
+
+/// Checks that the non-alcoholic equivalent of a drink exists and has no alcohol.
+async fn check_non_alcoholic(client: &Client, drink: &Drink) -> Result<(), AppError> {
+    let Some(non_alcoholic) = drink.non_alcoholic else {
+        return Ok(());
+    };
+    if non_alcoholic == drink.id {
+        return Err(AppError::Validation(
+            "A drink cannot be its own non-alcoholic equivalent".to_string(),
+        ));
+    }
+    if !get_drinks(client)
+        .await?
+        .drinks
+        .iter()
+        .any(|d| d.id == non_alcoholic)
+    {
+        return Err(AppError::NotFound(format!(
+            "Drink {} not found",
+            non_alcoholic
+        )));
+    }
+    if get_drink_alcohol(client)
+        .await?
+        .get(&non_alcoholic)
+        .is_some_and(|&grams| grams > 0.0)
+    {
+        return Err(AppError::Validation(format!(
+            "Non-alcoholic equivalent {} contains alcohol",
+            non_alcoholic
+        )));
+    }
+    Ok(())
+}
@@ -1,13 +1,16 @@
 use crate::database::games::{get_full_game_data, get_game_list};
 use crate::utils::ids::GameId;
 use crate::utils::state::{AppError, AppState};
-use crate::utils::types::{Game, GameData};
+use crate::utils::types::{Game, GameData, SessionInfo};
 use axum::extract::State;
 use axum::Json;
 use deadpool_postgres::Client;
 
+/// Requires a session even though it is a GET: the data carries the team hashes that key the
+/// public team views.
 pub async fn games_get(
     state: State<AppState>,
+    _session: SessionInfo,
     Json(game_id): Json<GameId>,
 ) -> Result<Json<GameData>, AppError> {
     let client: Client = state.db.get().await?;
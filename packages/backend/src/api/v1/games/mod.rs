@@ -14,4 +14,21 @@ pub fn router() -> Router<AppState> {
             "/{game_id}/teams/{team_id}",
             patch(update_team).delete(delete_team),
         )
+        .route("/{game_id}/actions", get(get_actions))
+        .route("/{game_id}/actions/{action_id}/undo", post(undo_action))
+        .route("/{game_id}/undo", post(undo_actions))
+        .route("/{game_id}/redo", post(redo_actions))
+        .route("/{game_id}/events", get(get_events))
+        .route("/{game_id}/replay", get(replay_game))
+        .route("/{game_id}/stats", get(get_stats))
+        .route("/{game_id}/limits", get(get_limits).put(put_limits))
+        .route(
+            "/{game_id}/drink-queue",
+            get(get_drink_queue).put(set_queue_prep_status),
+        )
+        .route(
+            "/{game_id}/inventory",
+            get(get_game_inventory).put(put_opening_stock),
+        )
+        .route("/{game_id}/inventory/restock", post(restock_ingredient))
 }
@@ -1,17 +1,42 @@
-use crate::api::v1::turns::utils::broadcast_game_update;
+use crate::api::v1::turns::utils::{broadcast_game_update, warn_low_stock};
+use crate::database::actions::{
+    get_game_actions, revert_action, undone_action_ids, ActionRecorder,
+};
+use crate::database::boards::get_board_places;
+use crate::database::drinks::{get_drink_alcohol, get_drinks, get_ingredients, get_recipes};
+use crate::database::events::get_game_events;
 use crate::database::games::{
-    get_full_game_data, get_games, post_game, start_game as db_start_game,
+    get_full_game_data, get_game_by_id, get_games, lock_game, post_game,
+    start_game as db_start_game,
+};
+use crate::database::inventory::{
+    add_restock, get_inventory, get_used_ingredients, set_opening_stock,
 };
+use crate::database::limits::{get_drinking_limits, set_drinking_limits};
 use crate::database::team::{
     create_team as db_create_team, delete_team as db_delete_team,
     update_team_name as db_update_team_name,
 };
+use crate::database::turns::{get_turn_teams, lock_turn, set_drink_prep_status};
+use crate::engine::alcohol::add_alcohol_intake;
+use crate::engine::events::{GameEvent, GameEvents};
+use crate::engine::inventory::{starts_using_stock, validate_opening_stock};
+use crate::engine::lifecycle::{check_transition, TurnAction};
+use crate::engine::limits::validate_limits;
+use crate::engine::replay::replay;
+use crate::engine::stats::game_stats;
+use crate::engine::views::{drink_queue, queued_drinks};
 use crate::utils::errors::wrap_json;
-use crate::utils::ids::{GameId, TeamId};
+use crate::utils::ids::{ActionId, GameId, IngredientId, TeamId};
 use crate::utils::state::{AppError, AppState};
-use crate::utils::types::{FirstTurnPost, Game, Games, PostGame, TeamNameUpdate};
+use crate::utils::types::{
+    BatchPrepStatusBody, DrinkQueue, DrinkingLimits, FirstTurnPost, Game, GameActions, GameData,
+    GameInventory, GameStats, Games, OpeningStockBody, PostGame, RestockBody, SessionInfo,
+    TeamNameUpdate, UndoBody,
+};
 use axum::extract::{Path, State};
-use axum::Json;
+use axum::{Extension, Json};
+use chrono::Utc;
 use deadpool_postgres::Client;
 
 pub async fn games_get(state: State<AppState>) -> Result<Json<Games>, AppError> {
@@ -31,13 +56,22 @@ pub async fn games_post(
 pub async fn start_game(
     State(state): State<AppState>,
     Path(game_id): Path<GameId>,
+    Extension(session): Extension<SessionInfo>,
     Json(mut data): Json<FirstTurnPost>,
 ) -> Result<(), AppError> {
     data.game_id = game_id;
-    let client = state.db.get().await?;
-    let game = db_start_game(&client, data).await?;
-    let game_data = get_full_game_data(&client, game.id).await?;
-    broadcast_game_update(&state.io, game.id, &game_data).await;
+    let mut client = state.db.get().await?;
+    let tx = client.transaction().await?;
+    let mut recorder = ActionRecorder::begin_game(&tx, game_id).await?;
+    let drinks = data.drinks.clone();
+    let (game, first_turns) = db_start_game(&tx, data).await?;
+    recorder.event(GameEvent::GameStarted {
+        drinks,
+        first_turns,
+    });
+    recorder.finish(&tx, session.uid, "start_game").await?;
+    tx.commit().await?;
+    broadcast_game_update(&state, game.id).await;
     Ok(())
 }
 
@@ -45,12 +79,18 @@ pub async fn start_game(
 pub async fn create_team(
     State(state): State<AppState>,
     Path(game_id): Path<GameId>,
+    Extension(session): Extension<SessionInfo>,
     Json(data): Json<TeamNameUpdate>,
 ) -> Result<(), AppError> {
-    let client = state.db.get().await?;
-    let team = db_create_team(&client, game_id, data).await?;
-    let game_data = get_full_game_data(&client, team.game_id).await?;
-    broadcast_game_update(&state.io, team.game_id, &game_data).await;
+    let mut client = state.db.get().await?;
+    let tx = client.transaction().await?;
+    let mut recorder = ActionRecorder::begin(&tx, game_id, &[]).await?;
+    let team = db_create_team(&tx, game_id, data).await?;
+    recorder.add_new_team(team.team_id);
+    recorder.event(GameEvent::TeamCreated { team: team.clone() });
+    recorder.finish(&tx, session.uid, "create_team").await?;
+    tx.commit().await?;
+    broadcast_game_update(&state, team.game_id).await;
     Ok(())
 }
 
@@ -58,12 +98,20 @@ pub async fn create_team(
 pub async fn update_team(
     State(state): State<AppState>,
     Path((game_id, team_id)): Path<(GameId, TeamId)>,
+    Extension(session): Extension<SessionInfo>,
     Json(data): Json<TeamNameUpdate>,
 ) -> Result<(), AppError> {
-    let client = state.db.get().await?;
-    db_update_team_name(&client, team_id, data.team_name).await?;
-    let game_data = get_full_game_data(&client, game_id).await?;
-    broadcast_game_update(&state.io, game_id, &game_data).await;
+    let mut client = state.db.get().await?;
+    let tx = client.transaction().await?;
+    let mut recorder = ActionRecorder::begin(&tx, game_id, &[team_id]).await?;
+    let team = db_update_team_name(&tx, team_id, data.team_name).await?;
+    recorder.event(GameEvent::TeamRenamed {
+        team_id,
+        team_name: team.team_name,
+    });
+    recorder.finish(&tx, session.uid, "update_team").await?;
+    tx.commit().await?;
+    broadcast_game_update(&state, game_id).await;
     Ok(())
 }
 
@@ -71,10 +119,361 @@ pub async fn update_team(
 pub async fn delete_team(
     State(state): State<AppState>,
     Path((game_id, team_id)): Path<(GameId, TeamId)>,
+    Extension(session): Extension<SessionInfo>,
+) -> Result<(), AppError> {
+    let mut client = state.db.get().await?;
+    let tx = client.transaction().await?;
+    let mut recorder = ActionRecorder::begin(&tx, game_id, &[team_id]).await?;
+    db_delete_team(&tx, team_id).await?;
+    recorder.event(GameEvent::TeamDeleted { team_id });
+    recorder.finish(&tx, session.uid, "delete_team").await?;
+    tx.commit().await?;
+    broadcast_game_update(&state, game_id).await;
+    Ok(())
+}
+
+/// GET /games/{game_id}/actions - The referee action log of a game, oldest first.
+pub async fn get_actions(
+    State(state): State<AppState>,
+    Path(game_id): Path<GameId>,
+) -> Result<Json<GameActions>, AppError> {
+    let client = state.db.get().await?;
+    let actions = get_game_actions(&client, game_id).await?;
+    wrap_json(Ok(GameActions {
+        actions: actions.into_iter().map(|a| a.action).collect(),
+    }))
+}
+
+/// POST /games/{game_id}/undo - Undo the latest `count` actions that are not undone yet.
+pub async fn undo_actions(
+    State(state): State<AppState>,
+    Path(game_id): Path<GameId>,
+    Extension(session): Extension<SessionInfo>,
+    Json(data): Json<UndoBody>,
+) -> Result<(), AppError> {
+    revert_latest(&state, game_id, &session, data, false).await
+}
+
+/// POST /games/{game_id}/redo - Redo the latest `count` undone actions.
+pub async fn redo_actions(
+    State(state): State<AppState>,
+    Path(game_id): Path<GameId>,
+    Extension(session): Extension<SessionInfo>,
+    Json(data): Json<UndoBody>,
+) -> Result<(), AppError> {
+    revert_latest(&state, game_id, &session, data, true).await
+}
+
+/// Reverts the latest actions (or the latest undos, when redoing) of a game, newest first.
+async fn revert_latest(
+    state: &AppState,
+    game_id: GameId,
+    session: &SessionInfo,
+    data: UndoBody,
+    redo: bool,
 ) -> Result<(), AppError> {
+    let count = data.count.unwrap_or(1);
+    if count < 1 {
+        return Err(AppError::Validation("Count must be at least 1".to_string()));
+    }
+
+    let mut client = state.db.get().await?;
+    let tx = client.transaction().await?;
+    lock_game(&tx, game_id).await?;
+    let actions = get_game_actions(&tx, game_id).await?;
+    let targets: Vec<_> = actions
+        .iter()
+        .rev()
+        .filter(|a| !a.action.undone)
+        .filter(|a| {
+            if redo {
+                a.action.action == "undo"
+            } else {
+                a.action.reverts.is_none()
+            }
+        })
+        .take(count as usize)
+        .collect();
+
+    if targets.len() < count as usize {
+        return Err(AppError::Conflict(format!(
+            "Only {} action(s) can be {}",
+            targets.len(),
+            if redo { "redone" } else { "undone" }
+        )));
+    }
+    for target in targets {
+        revert_action(&tx, target, session.uid).await?;
+    }
+    tx.commit().await?;
+
+    broadcast_game_update(state, game_id).await;
+    Ok(())
+}
+
+/// POST /games/{game_id}/actions/{action_id}/undo - Undo a specific action.
+/// Undoing an undo redoes the original action.
+pub async fn undo_action(
+    State(state): State<AppState>,
+    Path((game_id, action_id)): Path<(GameId, ActionId)>,
+    Extension(session): Extension<SessionInfo>,
+) -> Result<(), AppError> {
+    let mut client = state.db.get().await?;
+    let tx = client.transaction().await?;
+    lock_game(&tx, game_id).await?;
+    let actions = get_game_actions(&tx, game_id).await?;
+    let target = actions
+        .iter()
+        .find(|a| a.action.action_id == action_id)
+        .ok_or_else(|| AppError::NotFound(format!("Action {} not found", action_id)))?;
+    revert_action(&tx, target, session.uid).await?;
+    tx.commit().await?;
+
+    broadcast_game_update(&state, game_id).await;
+    Ok(())
+}
+
+/// GET /games/{game_id}/events - The event stream of a game, oldest first.
+/// Requires a session, as the events of created teams carry their hashes.
+pub async fn get_events(
+    State(state): State<AppState>,
+    Path(game_id): Path<GameId>,
+    _session: SessionInfo,
+) -> Result<Json<GameEvents>, AppError> {
+    let client = state.db.get().await?;
+    let actions = get_game_actions(&client, game_id).await?;
+    let events = get_game_events(&client, game_id, &undone_action_ids(&actions)).await?;
+    wrap_json(Ok(GameEvents { events }))
+}
+
+/// GET /games/{game_id}/replay - Rebuilds the game data from the event stream of the game,
+/// using the current rules of its board. Compare with /game_data to audit a game.
+/// Requires a session, as the data carries the team hashes.
+pub async fn replay_game(
+    State(state): State<AppState>,
+    Path(game_id): Path<GameId>,
+    _session: SessionInfo,
+) -> Result<Json<GameData>, AppError> {
+    let client = state.db.get().await?;
+    let game = get_game_by_id(&client, game_id).await?;
+    let board_places = get_board_places(&client, game.board.id).await?;
+    let catalog = get_drinks(&client).await?;
+    let actions = get_game_actions(&client, game_id).await?;
+    let events = get_game_events(&client, game_id, &undone_action_ids(&actions)).await?;
+    let mut game_data = replay(&game, &board_places, &catalog, &events)?;
+    add_alcohol_intake(&mut game_data, &get_drink_alcohol(&client).await?);
+    wrap_json(Ok(game_data))
+}
+
+/// GET /games/{game_id}/stats - Per-team statistics and the current ranking of the game.
+pub async fn get_stats(
+    State(state): State<AppState>,
+    Path(game_id): Path<GameId>,
+) -> Result<Json<GameStats>, AppError> {
     let client = state.db.get().await?;
-    db_delete_team(&client, team_id).await?;
     let game_data = get_full_game_data(&client, game_id).await?;
-    broadcast_game_update(&state.io, game_id, &game_data).await;
+    if game_data.game.id != game_id {
+        return Err(AppError::NotFound(format!("Game {} not found", game_id)));
+    }
+    let board_places = get_board_places(&client, game_data.game.board.id).await?;
+    wrap_json(Ok(game_stats(&game_data, &board_places, Utc::now())))
+}
+
+/// GET /games/{game_id}/drink-queue - The drinks waiting to be mixed or delivered, with their
+/// recipes, the ingredients still needed and how long each team has been waiting.
+pub async fn get_drink_queue(
+    State(state): State<AppState>,
+    Path(game_id): Path<GameId>,
+) -> Result<Json<DrinkQueue>, AppError> {
+    let client = state.db.get().await?;
+    let game_data = get_full_game_data(&client, game_id).await?;
+    if game_data.game.id != game_id {
+        return Err(AppError::NotFound(format!("Game {} not found", game_id)));
+    }
+    let recipes = get_recipes(&client, &queued_drinks(&game_data)).await?;
+    wrap_json(Ok(drink_queue(&game_data, &recipes, Utc::now())))
+}
+
+/// PUT /games/{game_id}/drink-queue - Move several turns of the game to the same drink
+/// preparation status at once, e.g. start mixing three turns. Nothing changes if any of the
+/// turns cannot be moved.
+pub async fn set_queue_prep_status(
+    State(state): State<AppState>,
+    Path(game_id): Path<GameId>,
+    Extension(session): Extension<SessionInfo>,
+    Json(body): Json<BatchPrepStatusBody>,
+) -> Result<(), AppError> {
+    let mut turn_ids = body.turn_ids.clone();
+    turn_ids.sort();
+    if turn_ids.is_empty() {
+        return Err(AppError::Validation("No turns given".to_string()));
+    }
+    if let Some(w) = turn_ids.windows(2).find(|w| w[0] == w[1]) {
+        return Err(AppError::Validation(format!(
+            "Turn {} given more than once",
+            w[0]
+        )));
+    }
+
+    let mut client = state.db.get().await?;
+    let tx = client.transaction().await?;
+    let team_ids = get_turn_teams(&tx, game_id, &turn_ids).await?;
+    let mut recorder = ActionRecorder::begin(&tx, game_id, &team_ids).await?;
+    let mut using_stock = Vec::new();
+    for &turn_id in &turn_ids {
+        let turn = lock_turn(&tx, turn_id).await?;
+        check_transition(&turn, TurnAction::SetPrepStatus(body.status))?;
+        if starts_using_stock(&turn, body.status) {
+            using_stock.push(turn_id);
+        }
+        set_drink_prep_status(&tx, turn_id, body.status).await?;
+        recorder.event(GameEvent::PrepStatusChanged {
+            turn_id,
+            status: body.status,
+        });
+    }
+    let used = get_used_ingredients(&tx, &using_stock).await?;
+    recorder
+        .finish(&tx, session.uid, "set_drink_prep_status")
+        .await?;
+    tx.commit().await?;
+    broadcast_game_update(&state, game_id).await;
+    if !used.is_empty() {
+        if let Err(e) = warn_low_stock(&state, game_id, &used).await {
+            tracing::error!("Failed to check ingredient stock: {e}");
+        }
+    }
     Ok(())
 }
+
+/// GET /games/{game_id}/inventory - The ingredient stock of the game: opening stock,
+/// restocks, what the mixed drinks have used and what remains.
+pub async fn get_game_inventory(
+    State(state): State<AppState>,
+    Path(game_id): Path<GameId>,
+) -> Result<Json<GameInventory>, AppError> {
+    let client = state.db.get().await?;
+    let game = get_game_by_id(&client, game_id).await?;
+    if game.id != game_id {
+        return Err(AppError::NotFound(format!("Game {} not found", game_id)));
+    }
+    wrap_json(get_inventory(&client, game_id).await)
+}
+
+/// PUT /games/{game_id}/inventory - Set the opening stock and low stock level of ingredients
+/// of the game.
+pub async fn put_opening_stock(
+    State(state): State<AppState>,
+    Path(game_id): Path<GameId>,
+    Extension(session): Extension<SessionInfo>,
+    Json(body): Json<OpeningStockBody>,
+) -> Result<Json<GameInventory>, AppError> {
+    let mut client = state.db.get().await?;
+    let game = get_game_by_id(&client, game_id).await?;
+    if game.id != game_id {
+        return Err(AppError::NotFound(format!("Game {} not found", game_id)));
+    }
+    let ingredients = get_ingredients(&client).await?;
+    validate_opening_stock(&body.stock, &ingredients.ingredients)?;
+    let tx = client.transaction().await?;
+    let mut recorder = ActionRecorder::begin_stock(&tx, game_id).await?;
+    for stock in &body.stock {
+        set_opening_stock(&tx, game_id, stock).await?;
+    }
+    recorder.event(GameEvent::OpeningStockSet {
+        stock: body.stock.clone(),
+    });
+    recorder
+        .finish(&tx, session.uid, "set_opening_stock")
+        .await?;
+    tx.commit().await?;
+    broadcast_game_update(&state, game_id).await;
+    let ingredient_ids: Vec<IngredientId> = body.stock.iter().map(|s| s.ingredient_id).collect();
+    if let Err(e) = warn_low_stock(&state, game_id, &ingredient_ids).await {
+        tracing::error!("Failed to check ingredient stock: {e}");
+    }
+    wrap_json(get_inventory(&client, game_id).await)
+}
+
+/// POST /games/{game_id}/inventory/restock - Record stock brought in during the game.
+pub async fn restock_ingredient(
+    State(state): State<AppState>,
+    Path(game_id): Path<GameId>,
+    Extension(session): Extension<SessionInfo>,
+    Json(body): Json<RestockBody>,
+) -> Result<Json<GameInventory>, AppError> {
+    if !body.quantity.is_finite() || body.quantity <= 0.0 {
+        return Err(AppError::Validation(format!(
+            "Restocked quantity must be positive, got {}",
+            body.quantity
+        )));
+    }
+    let mut client = state.db.get().await?;
+    let game = get_game_by_id(&client, game_id).await?;
+    if game.id != game_id {
+        return Err(AppError::NotFound(format!("Game {} not found", game_id)));
+    }
+    let ingredients = get_ingredients(&client).await?;
+    if !ingredients
+        .ingredients
+        .iter()
+        .any(|i| i.id == body.ingredient_id)
+    {
+        return Err(AppError::NotFound(format!(
+            "Ingredient {} not found",
+            body.ingredient_id
+        )));
+    }
+    let tx = client.transaction().await?;
+    let mut recorder = ActionRecorder::begin_stock(&tx, game_id).await?;
+    let restock = add_restock(&tx, game_id, body.ingredient_id, body.quantity, session.uid).await?;
+    recorder.event(GameEvent::Restocked { restock });
+    recorder.finish(&tx, session.uid, "restock").await?;
+    tx.commit().await?;
+    broadcast_game_update(&state, game_id).await;
+    // A restock that leaves the ingredient low still warrants a warning
+    if let Err(e) = warn_low_stock(&state, game_id, &[body.ingredient_id]).await {
+        tracing::error!("Failed to check ingredient stock: {e}");
+    }
+    wrap_json(get_inventory(&client, game_id).await)
+}
+
+/// GET /games/{game_id}/limits - The drinking limits of the game.
+pub async fn get_limits(
+    State(state): State<AppState>,
+    Path(game_id): Path<GameId>,
+) -> Result<Json<DrinkingLimits>, AppError> {
+    let client = state.db.get().await?;
+    wrap_json(get_drinking_limits(&client, game_id).await)
+}
+
+/// PUT /games/{game_id}/limits - Replace the drinking limits of the game. They apply to
+/// turns confirmed from now on.
+pub async fn put_limits(
+    State(state): State<AppState>,
+    Path(game_id): Path<GameId>,
+    Extension(session): Extension<SessionInfo>,
+    Json(limits): Json<DrinkingLimits>,
+) -> Result<Json<DrinkingLimits>, AppError> {
+    let mut client = state.db.get().await?;
+    let game = get_game_by_id(&client, game_id).await?;
+    if game.id != game_id {
+        return Err(AppError::NotFound(format!("Game {} not found", game_id)));
+    }
+    if let Some(drink_id) = limits.substitute_drink {
+        let catalog = get_drinks(&client).await?;
+        if !catalog.drinks.iter().any(|d| d.id == drink_id) {
+            return Err(AppError::NotFound(format!("Drink {} not found", drink_id)));
+        }
+    }
+    validate_limits(&limits, &get_drink_alcohol(&client).await?)?;
+    let tx = client.transaction().await?;
+    let mut recorder = ActionRecorder::begin_limits(&tx, game_id).await?;
+    set_drinking_limits(&tx, game_id, &limits).await?;
+    recorder.event(GameEvent::LimitsSet {
+        limits: limits.clone(),
+    });
+    recorder.finish(&tx, session.uid, "set_limits").await?;
+    tx.commit().await?;
+    wrap_json(Ok(limits))
+}
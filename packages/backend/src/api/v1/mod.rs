@@ -6,6 +6,7 @@ pub mod drinks;
 pub mod game_data;
 pub mod games;
 pub mod ingredients;
+pub mod public;
 pub mod teams;
 pub mod turns;
 pub mod users;
@@ -17,6 +18,7 @@ pub fn router() -> Router<AppState> {
         .nest("/boards", boards::router())
         .nest("/games", games::router())
         .nest("/game_data", game_data::router())
+        .nest("/public", public::router())
         .nest("/teams", teams::router())
         .nest("/turns", turns::router())
         .nest("/users", users::router())
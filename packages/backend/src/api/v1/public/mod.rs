@@ -0,0 +1,11 @@
+use crate::utils::state::AppState;
+use axum::routing::get;
+use axum::Router;
+
+pub mod utils;
+use self::utils::*;
+
+/// Read-only endpoints for players and spectators, who have no referee credentials.
+pub fn router() -> Router<AppState> {
+    Router::new().route("/teams/{team_hash}", get(team_view_get))
+}
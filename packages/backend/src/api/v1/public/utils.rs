@@ -0,0 +1,20 @@
+use crate::database::games::get_full_game_data;
+use crate::database::team::get_team_by_hash;
+use crate::engine::views::team_view;
+use crate::utils::state::{AppError, AppState};
+use crate::utils::types::TeamView;
+use axum::extract::{Path, State};
+use axum::Json;
+
+/// GET /public/teams/{team_hash} - The view of a team of its own game.
+pub async fn team_view_get(
+    State(state): State<AppState>,
+    Path(team_hash): Path<String>,
+) -> Result<Json<TeamView>, AppError> {
+    let client = state.db.get().await?;
+    let team = get_team_by_hash(&client, &team_hash).await?;
+    let game_data = get_full_game_data(&client, team.game_id).await?;
+    team_view(&game_data, team.team_id)
+        .map(Json)
+        .ok_or_else(|| AppError::NotFound("Team not found".to_string()))
+}
@@ -1,5 +1,5 @@
 use crate::utils::state::AppState;
-use axum::routing::{post, put};
+use axum::routing::{get, post, put};
 use axum::Router;
 
 pub mod utils;
@@ -11,6 +11,12 @@ pub fn router() -> Router<AppState> {
             "/{team_id}/moral-victory-eligible",
             put(set_moral_victory_eligible),
         )
+        .route("/{team_id}/sober", put(set_sober))
+        .route("/{team_id}/players", get(players_get).post(players_post))
+        .route(
+            "/{team_id}/players/{player_id}",
+            put(player_put).delete(player_delete),
+        )
         .route("/{team_id}/end-turn", post(end_turn))
         .route("/{team_id}/teleport", post(teleport_team))
 }
@@ -1,14 +1,16 @@
 use crate::api::v1::turns::utils::broadcast_game_update;
-use crate::database::games::get_full_game_data;
-use crate::database::team::get_team_by_id;
-use crate::database::team::set_team_moral_victory_eligible;
+use crate::database::actions::ActionRecorder;
+use crate::database::players::{create_player, delete_player, get_players, update_player};
+use crate::database::team::lock_team;
+use crate::database::team::{set_team_moral_victory_eligible, set_team_sober};
 use crate::database::turns::{end_active_turns, teleport_team as db_teleport_team};
+use crate::engine::events::GameEvent;
 use crate::utils::errors::wrap_json;
-use crate::utils::ids::TeamId;
+use crate::utils::ids::{PlayerId, TeamId};
 use crate::utils::state::{AppError, AppState};
-use crate::utils::types::{TeleportTeamBody, Turn};
+use crate::utils::types::{Player, PlayerUpdate, Players, SessionInfo, TeleportTeamBody, Turn};
 use axum::extract::{Path, State};
-use axum::Json;
+use axum::{Extension, Json};
 use serde::Deserialize;
 
 /// Request body for PUT /teams/{team_id}/moral-victory-eligible
@@ -21,13 +23,126 @@ pub struct SetMoralVictoryEligibleBody {
 pub async fn set_moral_victory_eligible(
     State(state): State<AppState>,
     Path(team_id): Path<TeamId>,
+    Extension(session): Extension<SessionInfo>,
     Json(body): Json<SetMoralVictoryEligibleBody>,
 ) -> Result<(), AppError> {
+    let mut client = state.db.get().await?;
+    let tx = client.transaction().await?;
+    let team = lock_team(&tx, team_id).await?;
+    let mut recorder = ActionRecorder::begin(&tx, team.game_id, &[team_id]).await?;
+    set_team_moral_victory_eligible(&tx, team_id, body.moral_victory_eligible).await?;
+    recorder.event(GameEvent::MoralVictoryEligibilitySet {
+        team_id,
+        moral_victory_eligible: body.moral_victory_eligible,
+    });
+    recorder
+        .finish(&tx, session.uid, "set_moral_victory_eligible")
+        .await?;
+    tx.commit().await?;
+    broadcast_game_update(&state, team.game_id).await;
+    Ok(())
+}
+
+/// Request body for PUT /teams/{team_id}/sober
+#[derive(Deserialize)]
+pub struct SetSoberBody {
+    pub sober: bool,
+}
+
+/// PUT /teams/{team_id}/sober - Set sober mode. Turns started afterwards get the
+/// non-alcoholic equivalents of their drinks.
+pub async fn set_sober(
+    State(state): State<AppState>,
+    Path(team_id): Path<TeamId>,
+    Extension(session): Extension<SessionInfo>,
+    Json(body): Json<SetSoberBody>,
+) -> Result<(), AppError> {
+    let mut client = state.db.get().await?;
+    let tx = client.transaction().await?;
+    let team = lock_team(&tx, team_id).await?;
+    let mut recorder = ActionRecorder::begin(&tx, team.game_id, &[team_id]).await?;
+    set_team_sober(&tx, team_id, body.sober).await?;
+    recorder.event(GameEvent::SoberModeSet {
+        team_id,
+        sober: body.sober,
+    });
+    recorder.finish(&tx, session.uid, "set_sober").await?;
+    tx.commit().await?;
+    broadcast_game_update(&state, team.game_id).await;
+    Ok(())
+}
+
+/// GET /teams/{team_id}/players - List the players of a team.
+pub async fn players_get(
+    State(state): State<AppState>,
+    Path(team_id): Path<TeamId>,
+) -> Result<Json<Players>, AppError> {
     let client = state.db.get().await?;
-    let team = get_team_by_id(&client, team_id).await?;
-    set_team_moral_victory_eligible(&client, team_id, body.moral_victory_eligible).await?;
-    let game_data = get_full_game_data(&client, team.game_id).await?;
-    broadcast_game_update(&state.io, team.game_id, &game_data).await;
+    wrap_json(
+        get_players(&client, team_id)
+            .await
+            .map(|players| Players { players }),
+    )
+}
+
+/// POST /teams/{team_id}/players - Add a player to a team.
+pub async fn players_post(
+    State(state): State<AppState>,
+    Path(team_id): Path<TeamId>,
+    Extension(session): Extension<SessionInfo>,
+    Json(data): Json<PlayerUpdate>,
+) -> Result<Json<Player>, AppError> {
+    let mut client = state.db.get().await?;
+    let tx = client.transaction().await?;
+    let team = lock_team(&tx, team_id).await?;
+    let mut recorder = ActionRecorder::begin(&tx, team.game_id, &[team_id]).await?;
+    let player = create_player(&tx, team_id, data).await?;
+    recorder.event(GameEvent::PlayerAdded {
+        player: player.clone(),
+    });
+    recorder.finish(&tx, session.uid, "add_player").await?;
+    tx.commit().await?;
+    broadcast_game_update(&state, team.game_id).await;
+    Ok(Json(player))
+}
+
+/// PUT /teams/{team_id}/players/{player_id} - Rename a player or set them sober.
+pub async fn player_put(
+    State(state): State<AppState>,
+    Path((team_id, player_id)): Path<(TeamId, PlayerId)>,
+    Extension(session): Extension<SessionInfo>,
+    Json(data): Json<PlayerUpdate>,
+) -> Result<Json<Player>, AppError> {
+    let mut client = state.db.get().await?;
+    let tx = client.transaction().await?;
+    let team = lock_team(&tx, team_id).await?;
+    let mut recorder = ActionRecorder::begin(&tx, team.game_id, &[team_id]).await?;
+    let player = update_player(&tx, team_id, player_id, data).await?;
+    recorder.event(GameEvent::PlayerUpdated {
+        player: player.clone(),
+    });
+    recorder.finish(&tx, session.uid, "update_player").await?;
+    tx.commit().await?;
+    broadcast_game_update(&state, team.game_id).await;
+    Ok(Json(player))
+}
+
+/// DELETE /teams/{team_id}/players/{player_id} - Remove a player and the drinks attributed
+/// to them.
+pub async fn player_delete(
+    State(state): State<AppState>,
+    Path((team_id, player_id)): Path<(TeamId, PlayerId)>,
+    Extension(session): Extension<SessionInfo>,
+) -> Result<(), AppError> {
+    let mut client = state.db.get().await?;
+    let tx = client.transaction().await?;
+    let team = lock_team(&tx, team_id).await?;
+    let mut recorder = ActionRecorder::begin(&tx, team.game_id, &[team_id]).await?;
+    delete_player(&tx, team_id, player_id).await?;
+    recorder.event(GameEvent::PlayerRemoved { team_id, player_id });
+    recorder.finish(&tx, session.uid, "delete_player").await?;
+    tx.commit().await?;
+    broadcast_game_update(&state, team.game_id).await;
     Ok(())
 }
 
@@ -35,12 +150,17 @@ pub async fn set_moral_victory_eligible(
 pub async fn end_turn(
     State(state): State<AppState>,
     Path(team_id): Path<TeamId>,
+    Extension(session): Extension<SessionInfo>,
 ) -> Result<(), AppError> {
-    let client = state.db.get().await?;
-    let team = get_team_by_id(&client, team_id).await?;
-    end_active_turns(&client, team.game_id, team_id).await?;
-    let game_data = get_full_game_data(&client, team.game_id).await?;
-    broadcast_game_update(&state.io, team.game_id, &game_data).await;
+    let mut client = state.db.get().await?;
+    let tx = client.transaction().await?;
+    let team = lock_team(&tx, team_id).await?;
+    let mut recorder = ActionRecorder::begin(&tx, team.game_id, &[team_id]).await?;
+    end_active_turns(&tx, team.game_id, team_id).await?;
+    recorder.event(GameEvent::TurnEnded { team_id });
+    recorder.finish(&tx, session.uid, "end_turn").await?;
+    tx.commit().await?;
+    broadcast_game_update(&state, team.game_id).await;
     Ok(())
 }
 
@@ -49,12 +169,21 @@ pub async fn end_turn(
 pub async fn teleport_team(
     State(state): State<AppState>,
     Path(team_id): Path<TeamId>,
+    Extension(session): Extension<SessionInfo>,
     Json(data): Json<TeleportTeamBody>,
 ) -> Result<Json<Turn>, AppError> {
-    let client = state.db.get().await?;
-    let team = get_team_by_id(&client, team_id).await?;
-    let turn = db_teleport_team(&client, team.game_id, team_id, data.location).await?;
-    let game_data = get_full_game_data(&client, team.game_id).await?;
-    broadcast_game_update(&state.io, team.game_id, &game_data).await;
+    let mut client = state.db.get().await?;
+    let tx = client.transaction().await?;
+    let team = lock_team(&tx, team_id).await?;
+    let mut recorder = ActionRecorder::begin(&tx, team.game_id, &[team_id]).await?;
+    let turn = db_teleport_team(&tx, team.game_id, team_id, data.location).await?;
+    recorder.event(GameEvent::Teleported {
+        team_id,
+        turn_id: turn.turn_id,
+        location: data.location,
+    });
+    recorder.finish(&tx, session.uid, "teleport_team").await?;
+    tx.commit().await?;
+    broadcast_game_update(&state, team.game_id).await;
     wrap_json(Ok(turn))
 }
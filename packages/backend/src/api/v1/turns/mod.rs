@@ -13,5 +13,6 @@ pub fn router() -> Router<AppState> {
         .route("/{turn_id}", delete(cancel_turn))
         .route("/{turn_id}/penalty", post(confirm_penalty))
         .route("/{turn_id}/drinks", put(edit_turn_drinks))
+        .route("/{turn_id}/players", put(attribute_drinks))
         .route("/{turn_id}/prep-status", put(set_drink_prep_status))
 }
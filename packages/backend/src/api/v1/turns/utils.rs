@@ -1,217 +1,478 @@
-use std::cmp::min;
-
-use crate::database::boards::{get_board_place, get_board_places, move_forwards};
+use crate::api::websocket::team::team_room;
+use crate::api::websocket::utils::{role_payload, role_room, ROLE_CHANNELS};
+use crate::database::actions::{latest_action_id, ActionRecorder};
+use crate::database::boards::{get_board_place, get_board_places};
+use crate::database::drinks::{get_drink_alcohol, get_drinks};
 use crate::database::games::{
-    check_dice, check_opt_dice, count_place_visits, end_game, get_full_game_data, get_game_by_id,
-    get_team_latest_turn,
+    check_dice, check_opt_dice, end_game, get_full_game_data, get_game_by_id,
 };
+use crate::database::inventory::{get_inventory, get_used_ingredients};
+use crate::database::limits::get_drinking_limits;
+use crate::database::players::{get_players, set_turn_drink_players};
+use crate::database::team::lock_team;
 use crate::database::turns::{
-    cancel_turn as db_cancel_turn, end_turn as db_end_turn, get_turn_with_drinks,
-    set_drink_prep_status as db_set_drink_prep_status, set_end_place, set_turn_confirmed,
-    set_turn_double_tampere, set_turn_drinks, start_turn as db_start_turn, update_turn_dice,
+    cancel_turn as db_cancel_turn, end_turn as db_end_turn, get_game_turns, get_team_turns,
+    lock_turn, set_drink_prep_status as db_set_drink_prep_status, set_end_place,
+    set_turn_confirmed, set_turn_double_tampere, set_turn_drinks, set_turn_over_limit,
+    start_turn as db_start_turn, update_turn_dice,
+};
+use crate::engine::alcohol::{alcohol_intake, ethanol_grams};
+use crate::engine::events::GameEvent;
+use crate::engine::inventory::{low_stock_alert, starts_using_stock};
+use crate::engine::lifecycle::{check_can_start, check_transition, TurnAction};
+use crate::engine::limits::{check_limits, substitute_drinks};
+use crate::engine::players::validate_attribution;
+use crate::engine::turns::{
+    confirm_outcome, penalty_outcome, resolve_turn, sync_drinks_on_table, ConfirmOutcome, Dice,
+    TurnComputeResult,
 };
+use crate::engine::views::{spectator_view, team_view};
 use crate::utils::errors::wrap_json;
-use crate::utils::ids::{GameId, TurnId};
+use crate::utils::ids::{ActionId, GameId, IngredientId, TeamId, TurnId};
 use crate::utils::state::{AppError, AppState};
 use crate::utils::types::{
-    BoardPlace, ChangeDiceBody, ConfirmTurnBody, DrinkPrepStatus, GameData, PostStartTurn,
-    SetDrinkPrepStatusBody, TeamLatestTurn, Turn, TurnDrinks,
+    AttributeDrinksBody, ChangeDiceBody, ConfirmTurnBody, DrinkPrepStatus, GameData, LimitAction,
+    LimitAlert, PostStartTurn, SessionInfo, SetDrinkPrepStatusBody, Turn, TurnDrinks, UserType,
 };
 use axum::extract::{Path, State};
-use axum::Json;
-use deadpool_postgres::Client;
+use axum::{Extension, Json};
+use chrono::Utc;
+use deadpool_postgres::GenericClient;
 use socketioxide::SocketIo;
 
-/// Broadcasts game data to all clients subscribed to a game room.
-pub async fn broadcast_game_update(io: &SocketIo, game_id: GameId, data: &GameData) {
+/// Broadcasts the changes of a game to the referees following it, the drink queue and turn
+/// log to the IE and secretary channels of the game, the spectator view of it
+/// to the spectators of the game, and to every team following the game its own view of it.
+pub async fn broadcast_game_update(state: &AppState, game_id: GameId) {
+    match read_game_version(state, game_id).await {
+        Ok((action, data)) => broadcast_game_data(state, game_id, action, &data).await,
+        Err(e) => tracing::error!("Failed to read game {game_id} for its update: {e}"),
+    }
+}
+
+/// Reads the data of a game with the latest action it includes.
+async fn read_game_version(
+    state: &AppState,
+    game_id: GameId,
+) -> Result<(Option<ActionId>, GameData), AppError> {
+    let client = state.db.get().await?;
+    // Read first, so that the data includes at least this action
+    let action = latest_action_id(&client, game_id).await?;
+    Ok((action, get_full_game_data(&client, game_id).await?))
+}
+
+async fn broadcast_game_data(
+    state: &AppState,
+    game_id: GameId,
+    action: Option<ActionId>,
+    data: &GameData,
+) {
+    let io = &state.io;
     let room = format!("game:{}", game_id.0);
     if let Some(ns) = io.of("/referee") {
-        if let Err(e) = ns.to(room).emit("game-update", data).await {
-            tracing::error!("Failed to broadcast game update: {e}");
+        let followed = || !ns.clone().to(room.clone()).sockets().is_empty();
+        for delta in state.versions.advance(game_id, action, data, followed) {
+            if let Err(e) = ns
+                .clone()
+                .to(room.clone())
+                .emit(delta.event_name(), &delta)
+                .await
+            {
+                tracing::error!("Failed to broadcast game delta: {e}");
+            }
+        }
+    }
+    if let Err(e) = broadcast_role_channels(state, game_id, data).await {
+        tracing::error!("Failed to broadcast role channels: {e}");
+    }
+    if let Some(ns) = io.of("/spectate") {
+        if let Err(e) = ns.to(room).emit("game-update", &spectator_view(data)).await {
+            tracing::error!("Failed to broadcast spectator update: {e}");
+        }
+    }
+    if let Some(ns) = io.of("/team") {
+        for team in &data.teams {
+            let Some(view) = team_view(data, team.team.team_id) else {
+                continue;
+            };
+            if let Err(e) = ns
+                .clone()
+                .to(team_room(team.team.team_id))
+                .emit("team-update", &view)
+                .await
+            {
+                tracing::error!("Failed to broadcast team update: {e}");
+            }
         }
     }
 }
 
-/// Result of computing turn movement and drinks.
-pub struct TurnComputeResult {
-    /// The final place after movement.
-    pub end: BoardPlace,
-    /// The intermediate place before an on_land connection was taken (if applicable).
-    pub via: Option<BoardPlace>,
-    pub turn_drinks: TurnDrinks,
-    /// Whether double tampere applies after this turn.
-    pub double_tampere: bool,
+/// Sends the channel of each role in a game its payload.
+async fn broadcast_role_channels(
+    state: &AppState,
+    game_id: GameId,
+    data: &GameData,
+) -> Result<(), AppError> {
+    let Some(ns) = state.io.of("/referee") else {
+        return Ok(());
+    };
+    let client = state.db.get().await?;
+    for role in ROLE_CHANNELS {
+        let Some(payload) = role_payload(&client, data, role).await? else {
+            continue;
+        };
+        if let Err(e) = ns
+            .clone()
+            .to(role_room(game_id, role))
+            .emit(payload.event_name(), &payload)
+            .await
+        {
+            tracing::error!("Failed to broadcast {}: {e}", payload.event_name());
+        }
+    }
+    Ok(())
 }
 
-/// Computes the destination and drinks for a turn based on dice values.
-/// Does NOT apply side effects (end_game) - those happen on confirm.
-pub async fn compute_turn_result(
-    client: &Client,
+/// Sends a drinking limit alert to the referees following the game.
+pub async fn broadcast_limit_alert(io: &SocketIo, alert: &LimitAlert) {
+    let room = format!("game:{}", alert.game_id.0);
+    if let Some(ns) = io.of("/referee") {
+        if let Err(e) = ns.to(room).emit("limit-alert", alert).await {
+            tracing::error!("Failed to broadcast limit alert: {e}");
+        }
+    }
+}
+
+/// Warns the IE channel of a game if any of the given ingredients is low, e.g. those that the
+/// drinks of a turn used once it started using stock, see [`starts_using_stock`].
+pub async fn warn_low_stock(
+    state: &AppState,
     game_id: GameId,
-    team: &TeamLatestTurn,
-    dice1: i32,
-    dice2: i32,
-    dice3: Option<i32>,
-    dice4: Option<i32>,
-) -> Result<TurnComputeResult, AppError> {
-    let is_double = dice1 == dice2;
-    let double_multiplier = if is_double { 2 } else { 1 };
-    let throw = (dice1 as i8, dice2 as i8);
-
-    let double_tampere_multiplier = if team.double_tampere { 2 } else { 1 };
-
-    let current_place = team.location.clone().ok_or_else(|| {
-        AppError::NotFound(format!(
-            "Team {} has no current location - cannot compute movement",
-            team.team.team_id
-        ))
-    })?;
-    let board_places = get_board_places(client, current_place.board_id).await?;
-
-    let throw: i8 = min(throw.0, throw.1);
-    let backward_throw = dice3.map(|d| (double_multiplier * d) as i8);
-    let (end, via) = move_forwards(&current_place, &board_places, throw, backward_throw)?;
-
-    let visited = count_place_visits(client, game_id, end.place_number).await?;
-
-    let special = end.place.special.as_deref();
-    let extra_multiplier = match (special, dice3, dice4) {
-        // If landing at special=MIN(D2), multiply drinks by the value of the smaller die
-        (Some("MIN(D2)"), Some(dice3), Some(dice4)) => min(dice3, dice4),
-        // If landing at special=N+1, multiply drinks by the number of visits to that square + 1
-        (Some("N+1"), _, _) => visited + 1,
-        _ => 1,
+    ingredient_ids: &[IngredientId],
+) -> Result<(), AppError> {
+    let client = state.db.get().await?;
+    let inventory = get_inventory(&client, game_id).await?;
+    let Some(alert) = low_stock_alert(&inventory, ingredient_ids) else {
+        return Ok(());
     };
+    if let Some(ns) = state.io.of("/referee") {
+        let room = role_room(alert.game_id, &UserType::Ie);
+        if let Err(e) = ns.to(room).emit("low-stock", &alert).await {
+            tracing::error!("Failed to broadcast low stock alert: {e}");
+        }
+    }
+    Ok(())
+}
 
-    let base_multiplier = double_multiplier * double_tampere_multiplier;
+/// Drinks of a turn being confirmed, after the drinking limits of its game were applied.
+pub struct LimitedDrinks {
+    pub drinks: TurnDrinks,
+    pub over_limit: bool,
+    /// sent to the referees once the confirmation has been committed
+    pub alert: Option<LimitAlert>,
+}
 
-    // Collect drinks from the via place (if we moved after the usual dice throw)
-    let mut all_drinks = if let Some(ref via_place) = via {
-        let via_visited = count_place_visits(client, game_id, via_place.place_number).await?;
-        via_place
-            .drinks
-            .to_turn_drinks(via_visited, base_multiplier)
-            .drinks
-    } else {
-        vec![]
-    };
+/// Why confirming a turn failed.
+pub enum ConfirmError {
+    App(AppError),
+    /// The turn exceeds drinking limits that require a referee override
+    OverLimit {
+        alert: LimitAlert,
+        error: AppError,
+    },
+}
+
+impl From<AppError> for ConfirmError {
+    fn from(e: AppError) -> Self {
+        ConfirmError::App(e)
+    }
+}
 
-    // Add drinks from the final destination (with special formula multiplier)
-    let end_drinks = end
-        .drinks
-        .to_turn_drinks(visited, base_multiplier * extra_multiplier)
-        .drinks;
-    for drink in end_drinks {
-        // Merge duplicate drinks (same drink id) by summing n and on_table
-        if let Some(existing) = all_drinks.iter_mut().find(|d| d.drink.id == drink.drink.id) {
-            existing.n += drink.n;
-            existing.on_table += drink.on_table;
-            existing.optional = existing.optional || drink.optional;
-        } else {
-            all_drinks.push(drink);
+impl ConfirmError {
+    /// Alerts the referees of a confirmation refused over a drinking limit, and returns the
+    /// error to respond with. Called once the transaction has been rolled back.
+    async fn report(self, state: &AppState) -> AppError {
+        match self {
+            ConfirmError::App(e) => e,
+            ConfirmError::OverLimit { alert, error } => {
+                broadcast_limit_alert(&state.io, &alert).await;
+                error
+            }
         }
     }
+}
 
-    let turn_drinks = TurnDrinks { drinks: all_drinks };
+/// Checks the drinks of a turn being confirmed against the drinking limits of its game.
+/// Over a limit, the turn is flagged and, depending on the limits, its alcoholic drinks are
+/// substituted or the confirmation fails with a conflict unless `override_limits` is set.
+async fn apply_drinking_limits(
+    client: &impl GenericClient,
+    turn: &Turn,
+    drinks: TurnDrinks,
+    override_limits: bool,
+) -> Result<LimitedDrinks, ConfirmError> {
+    let limits = get_drinking_limits(client, turn.game_id).await?;
+    let alcohol = get_drink_alcohol(client).await?;
+    let history = get_team_turns(client, turn.team_id).await?;
+    let intake = alcohol_intake(&history, &alcohol);
+    let breaches = check_limits(
+        &limits,
+        &intake,
+        ethanol_grams(&drinks, &alcohol),
+        Utc::now(),
+    );
+    if breaches.is_empty() {
+        return Ok(LimitedDrinks {
+            drinks,
+            over_limit: false,
+            alert: None,
+        });
+    }
 
-    // Compute new double_tampere state based on movement
-    let double_tampere = if current_place.area == "normal" && end.area == "tampere" && is_double {
-        true
-    } else if end.area == "normal" {
-        false
-    } else {
-        team.double_tampere
+    let (action, drinks) = match limits.action {
+        LimitAction::RequireOverride if !override_limits => {
+            let exceeded: Vec<String> = breaches
+                .iter()
+                .map(|b| {
+                    format!(
+                        "{} standard drinks per {:?}, limit {}",
+                        b.standard_drinks, b.period, b.limit
+                    )
+                })
+                .collect();
+            let error = AppError::Conflict(format!(
+                "Turn {} exceeds the drinking limits of the game ({}); confirm with override_limits to proceed",
+                turn.turn_id,
+                exceeded.join(", ")
+            ));
+            let alert = LimitAlert {
+                game_id: turn.game_id,
+                team_id: turn.team_id,
+                turn_id: turn.turn_id,
+                action: LimitAction::RequireOverride,
+                blocked: true,
+                breaches,
+            };
+            return Err(ConfirmError::OverLimit { alert, error });
+        }
+        LimitAction::Substitute => {
+            let catalog = get_drinks(client).await?;
+            let fallback = limits
+                .substitute_drink
+                .and_then(|id| catalog.drinks.iter().find(|d| d.id == id));
+            match substitute_drinks(&drinks, &alcohol, &catalog.drinks, fallback) {
+                Some(substituted) => (LimitAction::Substitute, substituted),
+                // Nothing to substitute the drinks with, so the turn can only be flagged
+                None => (LimitAction::Flag, drinks),
+            }
+        }
+        action => (action, drinks),
     };
-
-    Ok(TurnComputeResult {
-        end: end.clone(),
-        via: via.cloned(),
-        turn_drinks,
-        double_tampere,
+    Ok(LimitedDrinks {
+        drinks,
+        over_limit: true,
+        alert: Some(LimitAlert {
+            game_id: turn.game_id,
+            team_id: turn.team_id,
+            turn_id: turn.turn_id,
+            action,
+            blocked: false,
+            breaches,
+        }),
     })
 }
 
+/// Loads the board and turn history of a game and resolves a team's throw with them.
+/// Does NOT apply side effects (end_game) - those happen on confirm.
+pub async fn compute_turn_result(
+    client: &impl GenericClient,
+    game_id: GameId,
+    team_id: TeamId,
+    dice: Dice,
+) -> Result<TurnComputeResult, AppError> {
+    let game = get_game_by_id(client, game_id).await?;
+    let team = lock_team(client, team_id).await?;
+    let board_places = get_board_places(client, game.board.id).await?;
+    let history = get_game_turns(client, game_id).await?;
+    resolve_turn(&board_places, &history, team_id, team.sober, dice)
+}
+
+/// Moves a turn to the place computed from the dice and replaces its drinks accordingly.
+async fn apply_turn_result(
+    client: &impl GenericClient,
+    turn: &Turn,
+    dice: Dice,
+) -> Result<(), AppError> {
+    let result = compute_turn_result(client, turn.game_id, turn.team_id, dice).await?;
+    set_end_place(
+        client,
+        result.end.place_number,
+        result.via.map(|p| p.place_number),
+        turn.turn_id,
+    )
+    .await?;
+    set_turn_drinks(client, turn.turn_id, result.turn_drinks).await?;
+    set_turn_double_tampere(client, turn.turn_id, result.double_tampere).await?;
+    Ok(())
+}
+
 /// Starts a new turn, optionally with dice. Returns the created turn.
+/// The team row is locked so that two turns cannot be started for a team at once.
+/// Fails with a conflict if the team still has a turn in progress.
 pub async fn process_start_turn(
-    client: &Client,
+    client: &impl GenericClient,
     turn_start_data: PostStartTurn,
 ) -> Result<Turn, AppError> {
-    let game_id = turn_start_data.game_id;
-    let team_id = turn_start_data.team_id;
-
     let dice1 = check_opt_dice(turn_start_data.dice1)?;
     let dice2 = check_opt_dice(turn_start_data.dice2)?;
 
-    let team_data = get_team_latest_turn(client, game_id, team_id).await?;
+    lock_team(client, turn_start_data.team_id).await?;
+    let history = get_game_turns(client, turn_start_data.game_id).await?;
+    check_can_start(&history, turn_start_data.team_id, turn_start_data.penalty)?;
     let turn = db_start_turn(client, turn_start_data.clone()).await?;
 
     if let (Some(dice1), Some(dice2)) = (dice1, dice2) {
-        let result =
-            compute_turn_result(client, game_id, &team_data, dice1, dice2, None, None).await?;
-        set_end_place(
-            client,
-            result.end.place_number,
-            result.via.map(|p| p.place_number),
-            turn.turn_id,
-        )
-        .await?;
-        set_turn_drinks(client, turn.turn_id, result.turn_drinks).await?;
-        set_turn_double_tampere(client, turn.turn_id, result.double_tampere).await?;
+        apply_turn_result(client, &turn, Dice::new(dice1, dice2, None, None)).await?;
     }
 
     Ok(turn)
 }
 
-/// Confirms a penalty turn: sets confirmed_at, sets drinks, and applies mixing logic.
-pub async fn process_confirm_penalty(
-    client: &Client,
+/// Sets new dice values for a turn and recomputes its destination and drinks.
+pub async fn process_change_dice(
+    client: &impl GenericClient,
     turn_id: TurnId,
-    mut drinks: TurnDrinks,
+    dice: Dice,
 ) -> Result<Turn, AppError> {
-    let turn = get_turn_with_drinks(client, turn_id).await?;
+    let turn = lock_turn(client, turn_id).await?;
+    check_transition(&turn, TurnAction::ChangeDice)?;
+    let turn = update_turn_dice(
+        client, turn_id, dice.dice1, dice.dice2, dice.dice3, dice.dice4,
+    )
+    .await?;
+    apply_turn_result(client, &turn, dice).await?;
+    Ok(turn)
+}
+
+/// Confirms a turn: applies the drinking limits, stores the final drinks and ends the game
+/// or moves the turn forward in the IE queue depending on the drinks.
+pub async fn process_confirm_turn(
+    client: &impl GenericClient,
+    turn_id: TurnId,
+    drinks: TurnDrinks,
+    override_limits: bool,
+) -> Result<(Turn, ConfirmOutcome, LimitedDrinks), ConfirmError> {
+    let turn = lock_turn(client, turn_id).await?;
+    check_transition(&turn, TurnAction::Confirm)?;
+    let game = get_game_by_id(client, turn.game_id).await?;
+
+    let end_place = match turn.place_number {
+        Some(location) => get_board_place(client, game.board.id, location).await?,
+        None => {
+            return Err(AppError::Validation(
+                "Turn must have location and dice to be confirmed".to_string(),
+            )
+            .into())
+        }
+    };
+    let via_place = match turn.via_number {
+        Some(via_num) => Some(get_board_place(client, game.board.id, via_num).await?),
+        None => None,
+    };
+
+    let limited = apply_drinking_limits(client, &turn, drinks, override_limits).await?;
+    let mut drinks = limited.drinks.clone();
+    sync_drinks_on_table(Some(&end_place), via_place.as_ref(), &mut drinks);
+    set_turn_drinks(client, turn_id, drinks.clone()).await?;
+    set_turn_confirmed(client, turn_id).await?;
+    set_turn_over_limit(client, turn_id, limited.over_limit).await?;
+
+    let outcome = confirm_outcome(&end_place, &drinks);
+    match outcome {
+        ConfirmOutcome::EndGame => {
+            end_game(client, turn.game_id).await?;
+        }
+        ConfirmOutcome::EndTurn => {
+            db_end_turn(client, turn_id).await?;
+        }
+        ConfirmOutcome::Delivered => {
+            db_set_drink_prep_status(client, turn_id, DrinkPrepStatus::Delivered).await?;
+        }
+        ConfirmOutcome::Mixing => {
+            db_set_drink_prep_status(client, turn_id, DrinkPrepStatus::Mixing).await?;
+        }
+        ConfirmOutcome::Queued => {}
+    }
+
+    Ok((turn, outcome, limited))
+}
+
+/// Confirms a penalty turn: applies the drinking limits, sets confirmed_at, sets drinks,
+/// and applies mixing logic.
+pub async fn process_confirm_penalty(
+    client: &impl GenericClient,
+    turn_id: TurnId,
+    drinks: TurnDrinks,
+    override_limits: bool,
+) -> Result<(Turn, LimitedDrinks), ConfirmError> {
+    let turn = lock_turn(client, turn_id).await?;
+    check_transition(&turn, TurnAction::ConfirmPenalty)?;
 
     if !turn.penalty {
-        return Err(AppError::Validation(
-            "Turn is not a penalty turn".to_string(),
-        ));
+        return Err(AppError::Validation("Turn is not a penalty turn".to_string()).into());
     }
 
     if drinks.drinks.is_empty() {
-        return Err(AppError::Validation(
-            "Penalty turn must have drinks assigned".to_string(),
-        ));
+        return Err(
+            AppError::Validation("Penalty turn must have drinks assigned".to_string()).into(),
+        );
     }
 
-    // Ensure no drinks are on_table for penalty turns - they should all go through IE queue
-    for drink in &mut drinks.drinks {
-        drink.on_table = 0;
-    }
+    let limited = apply_drinking_limits(client, &turn, drinks, override_limits).await?;
+    let mut drinks = limited.drinks.clone();
 
-    let needs_mixing = drinks.drinks.iter().any(|d| !d.drink.no_mix_required);
+    // Penalty drinks all go through the IE queue, unless none of them needs mixing
+    let outcome = penalty_outcome(&mut drinks);
 
     set_turn_confirmed(client, turn_id).await?;
     set_turn_drinks(client, turn_id, drinks).await?;
+    set_turn_over_limit(client, turn_id, limited.over_limit).await?;
 
-    if !needs_mixing {
-        // If drinks don't require mixing, skip IE queue
+    if outcome == ConfirmOutcome::Mixing {
         db_set_drink_prep_status(client, turn_id, DrinkPrepStatus::Mixing).await?;
     }
 
-    Ok(turn)
+    Ok((turn, limited))
 }
 
 // REST handlers
+//
+// Each mutating handler runs its database work in one transaction and broadcasts
+// the new game state only after it has been committed. The action is recorded in the
+// game's action log within the same transaction, so that it can be undone, along with
+// the events it adds to the game's event stream.
 
 /// POST /turns - Start a new turn. Returns the created turn.
 pub async fn start_turn(
     State(state): State<AppState>,
+    Extension(session): Extension<SessionInfo>,
     Json(data): Json<PostStartTurn>,
 ) -> Result<Json<Turn>, AppError> {
     let game_id = data.game_id;
-    let client = state.db.get().await?;
-    let turn = process_start_turn(&client, data).await?;
-    let game_data = get_full_game_data(&client, game_id).await?;
-    broadcast_game_update(&state.io, game_id, &game_data).await;
+    let mut client = state.db.get().await?;
+    let tx = client.transaction().await?;
+    let mut recorder = ActionRecorder::begin(&tx, game_id, &[data.team_id]).await?;
+    let turn = process_start_turn(&tx, data.clone()).await?;
+    recorder.event(GameEvent::TurnStarted {
+        turn_id: turn.turn_id,
+        team_id: turn.team_id,
+        penalty: turn.penalty,
+        dice1: data.dice1,
+        dice2: data.dice2,
+    });
+    recorder.finish(&tx, session.uid, "start_turn").await?;
+    tx.commit().await?;
+    broadcast_game_update(&state, game_id).await;
     wrap_json(Ok(turn))
 }
 
@@ -219,87 +480,91 @@ pub async fn start_turn(
 pub async fn change_dice(
     State(state): State<AppState>,
     Path(turn_id): Path<TurnId>,
+    Extension(session): Extension<SessionInfo>,
     Json(data): Json<ChangeDiceBody>,
 ) -> Result<(), AppError> {
-    let client = state.db.get().await?;
     let dice1 = check_dice(data.dice1)?;
     let dice2 = check_dice(data.dice2)?;
     let dice3 = check_opt_dice(data.dice3)?;
     let dice4 = check_opt_dice(data.dice4)?;
 
-    let turn = update_turn_dice(&client, turn_id, dice1, dice2, dice3, dice4).await?;
-    let team_data = get_team_latest_turn(&client, turn.game_id, turn.team_id).await?;
-    let result = compute_turn_result(
-        &client,
-        turn.game_id,
-        &team_data,
+    let mut client = state.db.get().await?;
+    let tx = client.transaction().await?;
+    let mut recorder = ActionRecorder::begin_turn(&tx, turn_id).await?;
+    let turn = process_change_dice(&tx, turn_id, Dice::new(dice1, dice2, dice3, dice4)).await?;
+    recorder.event(GameEvent::DiceChanged {
+        turn_id,
         dice1,
         dice2,
         dice3,
         dice4,
-    )
-    .await?;
-    set_end_place(
-        &client,
-        result.end.place_number,
-        result.via.map(|p| p.place_number),
-        turn.turn_id,
-    )
-    .await?;
-    set_turn_drinks(&client, turn.turn_id, result.turn_drinks).await?;
-    set_turn_double_tampere(&client, turn.turn_id, result.double_tampere).await?;
+    });
+    recorder.finish(&tx, session.uid, "change_dice").await?;
+    tx.commit().await?;
 
-    let game_data = get_full_game_data(&client, turn.game_id).await?;
-    broadcast_game_update(&state.io, turn.game_id, &game_data).await;
+    broadcast_game_update(&state, turn.game_id).await;
     Ok(())
 }
 
-/// Updates the on_table values of the drinks in a turn based on the place's drinks.
-/// Checks both the final place and the via place (if applicable), since drinks can come from either.
-fn sync_drinks_on_table(
-    place: Option<&BoardPlace>,
-    via: Option<&BoardPlace>,
-    drinks: &mut TurnDrinks,
-) {
-    let places: Vec<&BoardPlace> = [place, via].into_iter().flatten().collect();
-
-    for td in drinks.drinks.iter_mut() {
-        // Sum on_table contributions from all places (final + via)
-        let on_table_total: i32 = places
-            .iter()
-            .filter_map(|p| {
-                p.drinks
-                    .drinks
-                    .iter()
-                    .find(|pd| pd.drink.id == td.drink.id && pd.on_table)
-                    .map(|pd| pd.n)
-            })
-            .sum();
-
-        td.on_table = min(td.n, on_table_total);
-    }
-}
-
 /// PUT /turns/{turn_id}/drinks - Update drinks on an already-confirmed turn (used by IE for "IE" special).
 pub async fn edit_turn_drinks(
     State(state): State<AppState>,
     Path(turn_id): Path<TurnId>,
+    Extension(session): Extension<SessionInfo>,
     Json(mut data): Json<ConfirmTurnBody>,
 ) -> Result<(), AppError> {
-    let client = state.db.get().await?;
-    let turn = get_turn_with_drinks(&client, turn_id).await?;
-
-    if turn.confirmed_at.is_none() {
-        return Err(AppError::Validation(
-            "Turn must be confirmed before editing drinks".to_string(),
-        ));
-    }
+    let mut client = state.db.get().await?;
+    let tx = client.transaction().await?;
+    let mut recorder = ActionRecorder::begin_turn(&tx, turn_id).await?;
+    let turn = lock_turn(&tx, turn_id).await?;
+    check_transition(&turn, TurnAction::EditDrinks)?;
+    recorder.event(GameEvent::TurnDrinksEdited {
+        turn_id,
+        drinks: data.drinks.clone(),
+    });
 
     sync_drinks_on_table(turn.place.as_ref(), turn.via.as_ref(), &mut data.drinks);
-    set_turn_drinks(&client, turn_id, data.drinks).await?;
+    set_turn_drinks(&tx, turn_id, data.drinks).await?;
+    recorder
+        .finish(&tx, session.uid, "edit_turn_drinks")
+        .await?;
+    tx.commit().await?;
+
+    broadcast_game_update(&state, turn.game_id).await;
+    Ok(())
+}
+
+/// PUT /turns/{turn_id}/players - Attribute the drinks of a confirmed turn to players of its
+/// team, replacing any earlier attribution.
+pub async fn attribute_drinks(
+    State(state): State<AppState>,
+    Path(turn_id): Path<TurnId>,
+    Extension(session): Extension<SessionInfo>,
+    Json(data): Json<AttributeDrinksBody>,
+) -> Result<(), AppError> {
+    let mut client = state.db.get().await?;
+    let tx = client.transaction().await?;
+    let mut recorder = ActionRecorder::begin_turn(&tx, turn_id).await?;
+    let turn = lock_turn(&tx, turn_id).await?;
+    check_transition(&turn, TurnAction::AttributeDrinks)?;
+    let players = get_players(&tx, turn.team_id).await?;
+    validate_attribution(
+        &turn,
+        &players,
+        &data.drinks,
+        &get_drink_alcohol(&tx).await?,
+    )?;
+    set_turn_drink_players(&tx, turn_id, &data.drinks).await?;
+    recorder.event(GameEvent::DrinksAttributed {
+        turn_id,
+        drinks: data.drinks,
+    });
+    recorder
+        .finish(&tx, session.uid, "attribute_drinks")
+        .await?;
+    tx.commit().await?;
 
-    let game_data = get_full_game_data(&client, turn.game_id).await?;
-    broadcast_game_update(&state.io, turn.game_id, &game_data).await;
+    broadcast_game_update(&state, turn.game_id).await;
     Ok(())
 }
 
@@ -307,59 +572,35 @@ pub async fn edit_turn_drinks(
 pub async fn confirm_turn(
     State(state): State<AppState>,
     Path(turn_id): Path<TurnId>,
+    Extension(session): Extension<SessionInfo>,
     Json(data): Json<ConfirmTurnBody>,
 ) -> Result<(), AppError> {
-    let client = state.db.get().await?;
-    let mut drinks = data.drinks;
-
-    let turn = get_turn_with_drinks(&client, turn_id).await?;
-    let game = get_game_by_id(&client, turn.game_id).await?;
-
-    let end_place = match turn.place_number {
-        Some(location) => get_board_place(&client, game.board.id, location).await?,
-        None => {
-            return Err(AppError::Validation(
-                "Turn must have location and dice to be confirmed".to_string(),
-            ))
+    let mut client = state.db.get().await?;
+    let tx = client.transaction().await?;
+    let mut recorder = ActionRecorder::begin_turn(&tx, turn_id).await?;
+    let confirmed = process_confirm_turn(&tx, turn_id, data.drinks, data.override_limits).await;
+    let (turn, outcome, limited) = match confirmed {
+        Ok(confirmed) => confirmed,
+        Err(e) => {
+            drop(tx);
+            return Err(e.report(&state).await);
         }
     };
-    let via_place = match turn.via_number {
-        Some(via_num) => Some(get_board_place(&client, game.board.id, via_num).await?),
-        None => None,
-    };
-
-    sync_drinks_on_table(Some(&end_place), via_place.as_ref(), &mut drinks);
-    set_turn_drinks(&client, turn_id, drinks.clone()).await?;
-    set_turn_confirmed(&client, turn_id).await?;
-
-    // If the turn ended on a place with end=true, end the game immediately
-    if end_place.end {
-        end_game(&client, turn.game_id).await?;
-    }
-    // Otherwise, end turn immediately if no drinks were awarded
-    else if drinks.drinks.is_empty() {
-        db_end_turn(&client, turn_id).await?;
-    }
-    // Otherwise, store all drinks in this turn
-    else {
-        let has_ie_drinks = drinks.drinks.iter().any(|d| d.on_table < d.n);
-        let needs_mixing = drinks
-            .drinks
-            .iter()
-            .any(|d| d.on_table < d.n && !d.drink.no_mix_required);
-
-        if !has_ie_drinks {
-            // If all drinks are on_table (no IE drinks), mark as delivered immediately
-            db_set_drink_prep_status(&client, turn_id, DrinkPrepStatus::Delivered).await?;
-        } else if !needs_mixing {
-            // Otherwise, if drinks don't require mixing, skip IE queue
-            db_set_drink_prep_status(&client, turn_id, DrinkPrepStatus::Mixing).await?;
-        }
-        // Otherwise, turn goes through IE queue normally (on_table drinks delivered with IE drinks)
+    recorder.event(GameEvent::TurnConfirmed {
+        turn_id,
+        drinks: limited.drinks,
+        over_limit: limited.over_limit,
+    });
+    if outcome == ConfirmOutcome::EndGame {
+        recorder.event(GameEvent::GameEnded);
     }
+    recorder.finish(&tx, session.uid, "confirm_turn").await?;
+    tx.commit().await?;
 
-    let game_data = get_full_game_data(&client, turn.game_id).await?;
-    broadcast_game_update(&state.io, turn.game_id, &game_data).await;
+    broadcast_game_update(&state, turn.game_id).await;
+    if let Some(alert) = limited.alert {
+        broadcast_limit_alert(&state.io, &alert).await;
+    }
     Ok(())
 }
 
@@ -367,12 +608,18 @@ pub async fn confirm_turn(
 pub async fn cancel_turn(
     State(state): State<AppState>,
     Path(turn_id): Path<TurnId>,
+    Extension(session): Extension<SessionInfo>,
 ) -> Result<(), AppError> {
-    let client = state.db.get().await?;
-    let turn = get_turn_with_drinks(&client, turn_id).await?;
-    db_cancel_turn(&client, turn_id).await?;
-    let game_data = get_full_game_data(&client, turn.game_id).await?;
-    broadcast_game_update(&state.io, turn.game_id, &game_data).await;
+    let mut client = state.db.get().await?;
+    let tx = client.transaction().await?;
+    let mut recorder = ActionRecorder::begin_turn(&tx, turn_id).await?;
+    let turn = lock_turn(&tx, turn_id).await?;
+    check_transition(&turn, TurnAction::Cancel)?;
+    db_cancel_turn(&tx, turn_id).await?;
+    recorder.event(GameEvent::TurnCancelled { turn_id });
+    recorder.finish(&tx, session.uid, "cancel_turn").await?;
+    tx.commit().await?;
+    broadcast_game_update(&state, turn.game_id).await;
     Ok(())
 }
 
@@ -380,12 +627,31 @@ pub async fn cancel_turn(
 pub async fn confirm_penalty(
     State(state): State<AppState>,
     Path(turn_id): Path<TurnId>,
+    Extension(session): Extension<SessionInfo>,
     Json(data): Json<ConfirmTurnBody>,
 ) -> Result<(), AppError> {
-    let client = state.db.get().await?;
-    let turn = process_confirm_penalty(&client, turn_id, data.drinks).await?;
-    let game_data = get_full_game_data(&client, turn.game_id).await?;
-    broadcast_game_update(&state.io, turn.game_id, &game_data).await;
+    let mut client = state.db.get().await?;
+    let tx = client.transaction().await?;
+    let mut recorder = ActionRecorder::begin_turn(&tx, turn_id).await?;
+    let confirmed = process_confirm_penalty(&tx, turn_id, data.drinks, data.override_limits).await;
+    let (turn, limited) = match confirmed {
+        Ok(confirmed) => confirmed,
+        Err(e) => {
+            drop(tx);
+            return Err(e.report(&state).await);
+        }
+    };
+    recorder.event(GameEvent::PenaltyConfirmed {
+        turn_id,
+        drinks: limited.drinks,
+        over_limit: limited.over_limit,
+    });
+    recorder.finish(&tx, session.uid, "confirm_penalty").await?;
+    tx.commit().await?;
+    broadcast_game_update(&state, turn.game_id).await;
+    if let Some(alert) = limited.alert {
+        broadcast_limit_alert(&state.io, &alert).await;
+    }
     Ok(())
 }
 
@@ -393,12 +659,34 @@ pub async fn confirm_penalty(
 pub async fn set_drink_prep_status(
     State(state): State<AppState>,
     Path(turn_id): Path<TurnId>,
+    Extension(session): Extension<SessionInfo>,
     Json(data): Json<SetDrinkPrepStatusBody>,
 ) -> Result<(), AppError> {
-    let client = state.db.get().await?;
-    let turn = get_turn_with_drinks(&client, turn_id).await?;
-    db_set_drink_prep_status(&client, turn_id, data.status).await?;
-    let game_data = get_full_game_data(&client, turn.game_id).await?;
-    broadcast_game_update(&state.io, turn.game_id, &game_data).await;
+    let mut client = state.db.get().await?;
+    let tx = client.transaction().await?;
+    let mut recorder = ActionRecorder::begin_turn(&tx, turn_id).await?;
+    let turn = lock_turn(&tx, turn_id).await?;
+    check_transition(&turn, TurnAction::SetPrepStatus(data.status))?;
+    let uses_stock = starts_using_stock(&turn, data.status);
+    db_set_drink_prep_status(&tx, turn_id, data.status).await?;
+    let used = if uses_stock {
+        get_used_ingredients(&tx, &[turn_id]).await?
+    } else {
+        Vec::new()
+    };
+    recorder.event(GameEvent::PrepStatusChanged {
+        turn_id,
+        status: data.status,
+    });
+    recorder
+        .finish(&tx, session.uid, "set_drink_prep_status")
+        .await?;
+    tx.commit().await?;
+    broadcast_game_update(&state, turn.game_id).await;
+    if !used.is_empty() {
+        if let Err(e) = warn_low_stock(&state, turn.game_id, &used).await {
+            tracing::error!("Failed to check ingredient stock: {e}");
+        }
+    }
     Ok(())
 }
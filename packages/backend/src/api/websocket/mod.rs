@@ -1,2 +1,4 @@
 pub mod referee;
+pub mod spectate;
+pub mod team;
 pub mod utils;
@@ -1,20 +1,17 @@
 use crate::api::websocket::utils::{
-    emit_app_error, emit_msg, get_db_client, verify_login_handler, ServerResponse,
+    emit_app_error, emit_msg, get_db_client, role_payload, role_room, verify_login_handler,
+    RoleSubscribeRequest, ServerResponse, SubscribeRequest,
 };
+use crate::database::actions::latest_action_id;
 use crate::database::games::get_full_game_data;
+use crate::database::login::check_session;
 use crate::utils::ids::GameId;
 use crate::utils::socket::check_auth_any;
-use crate::utils::state::SocketState;
+use crate::utils::state::{AppError, SocketState};
 use crate::utils::types::{SocketAuth, UserType};
-use serde::Deserialize;
 use socketioxide::adapter::Adapter;
 use socketioxide::extract::{Data, SocketRef, State};
 
-#[derive(Deserialize)]
-struct SubscribeRequest {
-    game_id: GameId,
-}
-
 /// Allowed user types for websocket connections.
 const ALLOWED_TYPES: &[UserType] = &[
     UserType::Admin,
@@ -25,7 +22,7 @@ const ALLOWED_TYPES: &[UserType] = &[
 
 /// Handler for websocket connections.
 /// Accepts Admin, Referee, Ie, and Secretary users.
-/// Only handles verify-login and subscribe events.
+/// Only handles verify-login, subscribe, resync and subscribe-role events.
 /// All actions are now REST API calls.
 pub async fn referee_on_connect<A: Adapter>(
     s: SocketRef<A>,
@@ -38,18 +35,33 @@ pub async fn referee_on_connect<A: Adapter>(
     s.on(
         "subscribe",
         |s: SocketRef<A>, Data(req): Data<SubscribeRequest>, State(state): State<SocketState>| async move {
-            let room = format!("game:{}", req.game_id.0);
-            s.join(room);
-
-            // Send initial game data immediately
-            let client = match get_db_client(&state).await {
-                Ok(c) => c,
-                Err(e) => return emit_app_error(&s, e),
-            };
-
-            match get_full_game_data(&client, req.game_id).await {
-                Ok(data) => emit_msg(&s, ServerResponse::GameUpdate(data)),
-                Err(e) => emit_app_error(&s, e),
+            s.join(format!("game:{}", req.game_id.0));
+            emit_snapshot(&s, &state, req.game_id).await;
+        },
+    );
+    // Sent by clients that missed a delta
+    s.on(
+        "resync",
+        |s: SocketRef<A>, Data(req): Data<SubscribeRequest>, State(state): State<SocketState>| async move {
+            emit_snapshot(&s, &state, req.game_id).await;
+        },
+    );
+    // Sent by IE and secretary screens following the channel of their role
+    let token = auth.token.clone();
+    s.on(
+        "subscribe-role",
+        move |s: SocketRef<A>,
+              Data(req): Data<RoleSubscribeRequest>,
+              State(state): State<SocketState>| {
+            let token = token.clone();
+            async move {
+                match role_subscription(&state, &token, &req).await {
+                    Ok(payload) => {
+                        s.join(role_room(req.game_id, &req.role));
+                        emit_msg(&s, payload);
+                    }
+                    Err(e) => emit_app_error(&s, e),
+                }
             }
         },
     );
@@ -59,3 +71,69 @@ pub async fn referee_on_connect<A: Adapter>(
         let _ = s.disconnect();
     }
 }
+
+/// Sends the current data of a game to a socket as a versioned snapshot. Changes since the
+/// version the referees last received are first broadcast to them as deltas, so that the
+/// snapshot and the deltas that follow it share one sequence.
+async fn emit_snapshot<A: Adapter>(s: &SocketRef<A>, state: &SocketState, game_id: GameId) {
+    let client = match get_db_client(state).await {
+        Ok(c) => c,
+        Err(e) => return emit_app_error(s, e),
+    };
+
+    let action = match latest_action_id(&client, game_id).await {
+        Ok(action) => action,
+        Err(e) => return emit_app_error(s, e),
+    };
+    let data = match get_full_game_data(&client, game_id).await {
+        Ok(data) if data.game.id == game_id => data,
+        Ok(_) => {
+            return emit_app_error(s, AppError::NotFound(format!("Game {} not found", game_id)))
+        }
+        Err(e) => return emit_app_error(s, e),
+    };
+    let (deltas, version) = state.versions.snapshot(game_id, action, data);
+    for delta in deltas {
+        if let Err(e) = s
+            .within(format!("game:{}", game_id.0))
+            .emit(delta.event_name(), &delta)
+            .await
+        {
+            tracing::error!("Failed to broadcast game delta: {e}");
+        }
+    }
+    emit_msg(s, ServerResponse::GameSnapshot(Box::new(version)));
+}
+
+/// Checks that the user of a socket may follow the channel of a role in a game, returning the
+/// current payload of the channel.
+async fn role_subscription(
+    state: &SocketState,
+    token: &str,
+    req: &RoleSubscribeRequest,
+) -> Result<ServerResponse, AppError> {
+    let client = get_db_client(state).await?;
+    let session = check_session(token, &client).await?;
+    let user_types = &session.user_types.user_types;
+    if !user_types.contains(&req.role) && !user_types.contains(&UserType::Admin) {
+        return Err(AppError::Unauthorized(format!(
+            "Not allowed to follow the {} channel",
+            req.role.as_str()
+        )));
+    }
+    let data = get_full_game_data(&client, req.game_id).await?;
+    if data.game.id != req.game_id {
+        return Err(AppError::NotFound(format!(
+            "Game {} not found",
+            req.game_id
+        )));
+    }
+    role_payload(&client, &data, &req.role)
+        .await?
+        .ok_or_else(|| {
+            AppError::Validation(format!(
+                "{} has no channel of its own, subscribe to the game instead",
+                req.role.as_str()
+            ))
+        })
+}
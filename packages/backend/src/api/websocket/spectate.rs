@@ -0,0 +1,44 @@
+use crate::api::websocket::utils::{
+    emit_app_error, emit_msg, get_db_client, ServerResponse, SubscribeRequest,
+};
+use crate::database::games::get_full_game_data;
+use crate::engine::views::spectator_view;
+use crate::utils::state::{AppError, SocketState};
+use socketioxide::adapter::Adapter;
+use socketioxide::extract::{Data, SocketRef, State};
+
+/// Handler for anonymous spectator connections, e.g. the big screen.
+/// Spectators need no login and follow one game at a time; they only receive the spectator
+/// view of the game, and may subscribe at most once a second.
+pub async fn spectate_on_connect<A: Adapter>(s: SocketRef<A>) {
+    s.on(
+        "subscribe",
+        |s: SocketRef<A>, Data(req): Data<SubscribeRequest>, State(state): State<SocketState>| async move {
+            if !state.spectate_limiter.check(&s.id.to_string()) {
+                return emit_app_error(&s, AppError::RateLimited);
+            }
+            let client = match get_db_client(&state).await {
+                Ok(c) => c,
+                Err(e) => return emit_app_error(&s, e),
+            };
+
+            match get_full_game_data(&client, req.game_id).await {
+                Ok(data) if data.game.id == req.game_id => {
+                    s.leave_all();
+                    s.join(format!("game:{}", req.game_id.0));
+                    emit_msg(&s, ServerResponse::SpectatorUpdate(Box::new(spectator_view(&data))));
+                }
+                Ok(_) => emit_app_error(
+                    &s,
+                    AppError::NotFound(format!("Game {} not found", req.game_id)),
+                ),
+                Err(e) => emit_app_error(&s, e),
+            }
+        },
+    );
+    s.on_disconnect(
+        |s: SocketRef<A>, State(state): State<SocketState>| async move {
+            state.spectate_limiter.forget(&s.id.to_string());
+        },
+    );
+}
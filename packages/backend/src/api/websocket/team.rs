@@ -0,0 +1,48 @@
+use crate::api::websocket::utils::{emit_app_error, emit_msg, get_db_client, ServerResponse};
+use crate::database::games::get_full_game_data;
+use crate::database::team::get_team_by_hash;
+use crate::engine::views::team_view;
+use crate::utils::ids::TeamId;
+use crate::utils::state::{AppError, SocketState};
+use crate::utils::types::TeamSocketAuth;
+use socketioxide::adapter::Adapter;
+use socketioxide::extract::{Data, SocketRef, State};
+
+/// Room of the /team namespace that receives the updates of a team.
+pub fn team_room(team_id: TeamId) -> String {
+    format!("team:{}", team_id.0)
+}
+
+/// Handler for websocket connections of teams following their own game.
+/// The team is identified by its team_hash, so no login is needed; the socket only ever
+/// receives the view of that team.
+pub async fn team_on_connect<A: Adapter>(
+    s: SocketRef<A>,
+    Data(auth): Data<TeamSocketAuth>,
+    State(state): State<SocketState>,
+) {
+    let client = match get_db_client(&state).await {
+        Ok(c) => c,
+        Err(e) => return emit_app_error(&s, e),
+    };
+    let team = match get_team_by_hash(&client, &auth.team_hash).await {
+        Ok(team) => team,
+        Err(e) => {
+            emit_app_error(&s, e);
+            let _ = s.disconnect();
+            return;
+        }
+    };
+    s.join(team_room(team.team_id));
+
+    let view = get_full_game_data(&client, team.game_id)
+        .await
+        .and_then(|data| {
+            team_view(&data, team.team_id)
+                .ok_or_else(|| AppError::NotFound("Team not found".to_string()))
+        });
+    match view {
+        Ok(view) => emit_msg(&s, ServerResponse::TeamUpdate(Box::new(view))),
+        Err(e) => emit_app_error(&s, e),
+    }
+}
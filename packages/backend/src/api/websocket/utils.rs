@@ -1,28 +1,130 @@
+use crate::database::drinks::get_recipes;
+use crate::engine::deltas::{game_changes, GameChange};
+use crate::engine::views::{drink_queue, queued_drinks, turn_log};
+use crate::utils::ids::GameId;
 use crate::utils::socket::check_auth_any;
 use crate::utils::state::{AppError, SocketState};
-use crate::utils::types::{GameData, SocketAuth, UserType};
-use deadpool_postgres::Client;
-use serde::Serialize;
+use crate::utils::types::{
+    DrinkQueue, GameData, GameDelta, SocketAuth, SpectatorGame, TeamDelta, TeamView, TurnDelta,
+    TurnLog, UserType, VersionedGameData,
+};
+use chrono::Utc;
+use deadpool_postgres::{Client, GenericClient};
+use serde::{Deserialize, Serialize};
 use socketioxide::adapter::{Adapter, Emitter};
 use socketioxide::extract::{Data, SocketRef, State};
 use socketioxide_core::adapter::CoreAdapter;
 
+/// Client request to follow a game.
+#[derive(Deserialize)]
+pub struct SubscribeRequest {
+    pub game_id: GameId,
+}
+
+/// Client request to follow the channel of a role in a game.
+#[derive(Deserialize)]
+pub struct RoleSubscribeRequest {
+    pub game_id: GameId,
+    pub role: UserType,
+}
+
 /// Server-to-client websocket responses with typed payloads.
 #[derive(Serialize)]
 #[serde(untagged)]
 pub enum ServerResponse {
     Error(String),
     Verification(bool),
-    GameUpdate(GameData),
+    GameSnapshot(Box<VersionedGameData>),
+    GameUpdated(Box<GameDelta>),
+    TeamUpdated(Box<TeamDelta>),
+    TurnUpdated(Box<TurnDelta>),
+    DrinkQueue(Box<DrinkQueue>),
+    TurnLog(Box<TurnLog>),
+    TeamUpdate(Box<TeamView>),
+    SpectatorUpdate(Box<SpectatorGame>),
 }
 
 impl ServerResponse {
-    fn event_name(&self) -> &'static str {
+    pub fn event_name(&self) -> &'static str {
         match self {
             ServerResponse::Error(_) => "response-error",
             ServerResponse::Verification(_) => "verification-reply",
-            ServerResponse::GameUpdate(_) => "game-update",
+            ServerResponse::GameSnapshot(_) => "game-snapshot",
+            ServerResponse::GameUpdated(_) => "game-updated",
+            ServerResponse::TeamUpdated(_) => "team-updated",
+            ServerResponse::TurnUpdated(_) => "turn-updated",
+            ServerResponse::DrinkQueue(_) => "drink-queue",
+            ServerResponse::TurnLog(_) => "turn-log",
+            ServerResponse::TeamUpdate(_) => "team-update",
+            ServerResponse::SpectatorUpdate(_) => "game-update",
+        }
+    }
+}
+
+/// Moves a game to its new data, returning the deltas from the previous version for the
+/// referees, each numbered with the next sequence number.
+pub fn advance_version(version: &mut VersionedGameData, data: &GameData) -> Vec<ServerResponse> {
+    let game_id = version.game_id;
+    let deltas = game_changes(&version.data, data)
+        .into_iter()
+        .map(|change| {
+            version.seq += 1;
+            let seq = version.seq;
+            match change {
+                GameChange::Game(game) => {
+                    ServerResponse::GameUpdated(Box::new(GameDelta { game_id, seq, game }))
+                }
+                GameChange::Team { team_id, team } => {
+                    ServerResponse::TeamUpdated(Box::new(TeamDelta {
+                        game_id,
+                        seq,
+                        team_id,
+                        team: team.map(|team| *team),
+                    }))
+                }
+                GameChange::Turn {
+                    team_id,
+                    turn_id,
+                    turn,
+                } => ServerResponse::TurnUpdated(Box::new(TurnDelta {
+                    game_id,
+                    seq,
+                    team_id,
+                    turn_id,
+                    turn: turn.map(|turn| *turn),
+                })),
+            }
+        })
+        .collect();
+    version.data = data.clone();
+    deltas
+}
+
+/// Roles with a channel of their own in each game, carrying a payload shaped for their screen.
+pub const ROLE_CHANNELS: &[UserType] = &[UserType::Ie, UserType::Secretary];
+
+/// Room of the channel of a role in a game.
+pub fn role_room(game_id: GameId, role: &UserType) -> String {
+    format!("game:{}:{}", game_id.0, role.as_str().to_lowercase())
+}
+
+/// The payload of the channel of a role, None if the role has no channel of its own.
+pub async fn role_payload(
+    client: &impl GenericClient,
+    data: &GameData,
+    role: &UserType,
+) -> Result<Option<ServerResponse>, AppError> {
+    match role {
+        UserType::Ie => {
+            let recipes = get_recipes(client, &queued_drinks(data)).await?;
+            Ok(Some(ServerResponse::DrinkQueue(Box::new(drink_queue(
+                data,
+                &recipes,
+                Utc::now(),
+            )))))
         }
+        UserType::Secretary => Ok(Some(ServerResponse::TurnLog(Box::new(turn_log(data))))),
+        UserType::Admin | UserType::Referee => Ok(None),
     }
 }
 
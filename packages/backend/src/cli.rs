@@ -0,0 +1,52 @@
+use crate::database::boards::get_board_places;
+use crate::database::utils::make_pool;
+use crate::engine::simulation::{simulate, SimulationConfig};
+use crate::utils::ids::BoardId;
+use anyhow::{anyhow, bail, Context};
+use std::env;
+
+const USAGE: &str = "\
+Usage: Otaniemipeli simulate <board_id> [--games N] [--teams N] [--max-rounds N] [--seed N]
+
+Plays games on a board with random dice and prints a JSON report of game length,
+drinks per team, visits per place and the share of games reaching Tampere.";
+
+/// Runs the `simulate` subcommand with the arguments after it.
+pub async fn run_simulate(args: &[String]) -> anyhow::Result<()> {
+    let mut config = SimulationConfig::default();
+    let mut board_id = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| anyhow!("{} needs a value\n\n{}", arg, USAGE))
+        };
+        match arg.as_str() {
+            "--games" => config.games = value()?.parse()?,
+            "--teams" => config.teams = value()?.parse()?,
+            "--max-rounds" => config.max_rounds = value()?.parse()?,
+            "--seed" => config.seed = Some(value()?.parse()?),
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return Ok(());
+            }
+            _ if board_id.is_none() => board_id = Some(BoardId(arg.parse()?)),
+            _ => bail!("Unexpected argument {}\n\n{}", arg, USAGE),
+        }
+    }
+    let board_id = board_id.ok_or_else(|| anyhow!("No board given\n\n{}", USAGE))?;
+
+    dotenvy::from_filename("../../.env")
+        .or_else(|_| dotenvy::dotenv())
+        .ok();
+    let db_url = env::var("POSTGRES_URL").context("POSTGRES_URL must be set")?;
+    let client = make_pool(&db_url)?.get().await?;
+    let board_places = get_board_places(&client, board_id).await?;
+    if board_places.board.id != board_id {
+        bail!("Board {} not found", board_id);
+    }
+
+    let report = simulate(&board_places, &config)?;
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    Ok(())
+}
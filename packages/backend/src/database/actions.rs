@@ -0,0 +1,859 @@
+use std::collections::HashSet;
+
+use crate::database::events::insert_events;
+use crate::database::inventory::{get_opening_stock, get_restocks, set_opening_stock};
+use crate::database::limits::{get_drinking_limits, set_drinking_limits};
+use crate::database::players::build_player_from_row;
+use crate::database::team::build_team_from_row;
+use crate::engine::events::GameEvent;
+use crate::utils::ids::{
+    ActionId, DrinkId, GameId, IngredientId, PlayerId, RestockId, TeamId, TurnId, UserId,
+};
+use crate::utils::state::AppError;
+use crate::utils::types::{
+    DrinkingLimits, GameAction, OpeningStock, Player, PlayerServing, Restock, Team,
+};
+use chrono::{DateTime, Utc};
+use deadpool_postgres::GenericClient;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio_postgres::types::Json;
+use tokio_postgres::Row;
+
+/// State of a game that referee actions change: the game flags, its start time, the rows of
+/// some of its teams and, for actions that change them, its drinking limits or ingredient
+/// stock.
+/// Stored as JSON in the before/after columns of `game_actions`.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct GameSnapshot {
+    pub started: bool,
+    pub finished: bool,
+    pub start_time: DateTime<Utc>,
+    pub teams: Vec<TeamSnapshot>,
+    /// None if the action does not cover the drinking limits
+    pub limits: Option<DrinkingLimits>,
+    /// None if the action does not cover the ingredient stock
+    pub stock: Option<StockSnapshot>,
+}
+
+/// The opening stock and restocks of a game.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct StockSnapshot {
+    pub opening: Vec<OpeningStock>,
+    pub restocks: Vec<Restock>,
+}
+
+/// A team with its players and all of its turns. `team` is None if the team did not exist.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct TeamSnapshot {
+    pub team_id: TeamId,
+    pub team: Option<Team>,
+    pub players: Vec<Player>,
+    pub turns: Vec<TurnRow>,
+}
+
+/// A row of `turns` with its `turn_drinks`.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct TurnRow {
+    pub turn_id: TurnId,
+    pub team_id: TeamId,
+    pub game_id: GameId,
+    pub start_time: DateTime<Utc>,
+    pub thrown_at: Option<DateTime<Utc>>,
+    pub confirmed_at: Option<DateTime<Utc>>,
+    pub mixing_at: Option<DateTime<Utc>>,
+    pub mixed_at: Option<DateTime<Utc>>,
+    pub delivered_at: Option<DateTime<Utc>>,
+    pub end_time: Option<DateTime<Utc>>,
+    pub dice1: Option<i32>,
+    pub dice2: Option<i32>,
+    pub dice3: Option<i32>,
+    pub dice4: Option<i32>,
+    pub place_number: Option<i32>,
+    pub via_number: Option<i32>,
+    pub penalty: bool,
+    pub double_tampere: Option<bool>,
+    pub over_limit: bool,
+    pub drinks: Vec<TurnDrinkRow>,
+    /// rows of `turn_ingredient_usage`
+    pub usage: Vec<IngredientUsage>,
+}
+
+/// A row of `turn_drinks`.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct TurnDrinkRow {
+    pub drink_id: DrinkId,
+    pub n: i32,
+    pub on_table: i32,
+    pub optional: bool,
+    pub substituted_for: Option<DrinkId>,
+    pub players: Vec<PlayerServing>,
+}
+
+/// A row of `turn_ingredient_usage`.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct IngredientUsage {
+    pub ingredient_id: IngredientId,
+    pub quantity: f64,
+}
+
+impl GameSnapshot {
+    pub fn team_ids(&self) -> Vec<TeamId> {
+        self.teams.iter().map(|t| t.team_id).collect()
+    }
+
+    /// Whether the database is in the state of the snapshot.
+    /// Compared as JSON so that the stored snapshots need no conversion back.
+    fn matches(&self, other: &Value) -> bool {
+        serde_json::to_value(self).is_ok_and(|value| &value == other)
+    }
+}
+
+fn build_turn_row(row: &Row) -> TurnRow {
+    TurnRow {
+        turn_id: row.get("turn_id"),
+        team_id: row.get("team_id"),
+        game_id: row.get("game_id"),
+        start_time: row.get("start_time"),
+        thrown_at: row.get("thrown_at"),
+        confirmed_at: row.get("confirmed_at"),
+        mixing_at: row.get("mixing_at"),
+        mixed_at: row.get("mixed_at"),
+        delivered_at: row.get("delivered_at"),
+        end_time: row.get("end_time"),
+        dice1: row.get("dice1"),
+        dice2: row.get("dice2"),
+        dice3: row.get("dice3"),
+        dice4: row.get("dice4"),
+        place_number: row.get("place_number"),
+        via_number: row.get("via_number"),
+        penalty: row.get("penalty"),
+        double_tampere: row.get("double_tampere"),
+        over_limit: row.get("over_limit"),
+        drinks: Vec::new(),
+        usage: Vec::new(),
+    }
+}
+
+/// Reads the current state of the given teams of a game. Teams that are not in the game are
+/// read as missing.
+pub async fn snapshot_teams(
+    client: &impl GenericClient,
+    game_id: GameId,
+    team_ids: &[TeamId],
+) -> Result<GameSnapshot, AppError> {
+    let game = client
+        .query_opt(
+            "SELECT started, finished, start_time FROM games WHERE game_id = $1",
+            &[&game_id],
+        )
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Game {} not found", game_id)))?;
+
+    let ids: Vec<TeamId> = team_ids.to_vec();
+    let team_rows = client
+        .query(
+            "SELECT * FROM teams WHERE team_id = ANY($1) AND game_id = $2",
+            &[&ids, &game_id],
+        )
+        .await?;
+    let mut turns: Vec<TurnRow> = client
+        .query(
+            "SELECT * FROM turns WHERE team_id = ANY($1) AND game_id = $2 ORDER BY turn_id",
+            &[&ids, &game_id],
+        )
+        .await?
+        .iter()
+        .map(build_turn_row)
+        .collect();
+    let drink_rows = client
+        .query(
+            "SELECT td.turn_id, td.drink_id, td.n, td.on_table, td.optional, td.substituted_for
+             FROM turn_drinks td
+             INNER JOIN turns t ON t.turn_id = td.turn_id
+             WHERE t.team_id = ANY($1) AND t.game_id = $2
+             ORDER BY td.turn_id, td.drink_id",
+            &[&ids, &game_id],
+        )
+        .await?;
+    for row in &drink_rows {
+        let turn_id: TurnId = row.get("turn_id");
+        if let Some(turn) = turns.iter_mut().find(|t| t.turn_id == turn_id) {
+            turn.drinks.push(TurnDrinkRow {
+                drink_id: row.get("drink_id"),
+                n: row.get("n"),
+                on_table: row.get("on_table"),
+                optional: row.get("optional"),
+                substituted_for: row.get("substituted_for"),
+                players: Vec::new(),
+            });
+        }
+    }
+    let serving_rows = client
+        .query(
+            "SELECT tdp.turn_id, tdp.drink_id, tdp.player_id, tdp.n
+             FROM turn_drink_players tdp
+             INNER JOIN turns t ON t.turn_id = tdp.turn_id
+             WHERE t.team_id = ANY($1) AND t.game_id = $2
+             ORDER BY tdp.turn_id, tdp.drink_id, tdp.player_id",
+            &[&ids, &game_id],
+        )
+        .await?;
+    for row in &serving_rows {
+        let turn_id: TurnId = row.get("turn_id");
+        let drink_id: DrinkId = row.get("drink_id");
+        if let Some(drink) = turns
+            .iter_mut()
+            .filter(|t| t.turn_id == turn_id)
+            .flat_map(|t| t.drinks.iter_mut())
+            .find(|d| d.drink_id == drink_id)
+        {
+            drink.players.push(PlayerServing {
+                player_id: row.get("player_id"),
+                n: row.get("n"),
+            });
+        }
+    }
+    let usage_rows = client
+        .query(
+            "SELECT u.turn_id, u.ingredient_id, u.quantity
+             FROM turn_ingredient_usage u
+             INNER JOIN turns t ON t.turn_id = u.turn_id
+             WHERE t.team_id = ANY($1) AND t.game_id = $2
+             ORDER BY u.turn_id, u.ingredient_id",
+            &[&ids, &game_id],
+        )
+        .await?;
+    for row in &usage_rows {
+        let turn_id: TurnId = row.get("turn_id");
+        if let Some(turn) = turns.iter_mut().find(|t| t.turn_id == turn_id) {
+            turn.usage.push(IngredientUsage {
+                ingredient_id: row.get("ingredient_id"),
+                quantity: row.get("quantity"),
+            });
+        }
+    }
+    let players: Vec<Player> = client
+        .query(
+            "SELECT p.* FROM players p
+             INNER JOIN teams t ON t.team_id = p.team_id
+             WHERE p.team_id = ANY($1) AND t.game_id = $2
+             ORDER BY p.player_id",
+            &[&ids, &game_id],
+        )
+        .await?
+        .iter()
+        .map(build_player_from_row)
+        .collect();
+
+    let teams = team_ids
+        .iter()
+        .map(|&team_id| TeamSnapshot {
+            team_id,
+            team: team_rows
+                .iter()
+                .map(build_team_from_row)
+                .find(|t| t.team_id == team_id),
+            players: players
+                .iter()
+                .filter(|p| p.team_id == team_id)
+                .cloned()
+                .collect(),
+            turns: turns
+                .iter()
+                .filter(|t| t.team_id == team_id)
+                .cloned()
+                .collect(),
+        })
+        .collect();
+
+    Ok(GameSnapshot {
+        started: game.get::<_, Option<bool>>("started").unwrap_or(false),
+        finished: game.get::<_, Option<bool>>("finished").unwrap_or(false),
+        start_time: game.get("start_time"),
+        teams,
+        limits: None,
+        stock: None,
+    })
+}
+
+/// Reads the current state of the parts of a game that a snapshot covers.
+async fn snapshot_like(
+    client: &impl GenericClient,
+    game_id: GameId,
+    like: &GameSnapshot,
+) -> Result<GameSnapshot, AppError> {
+    let mut snapshot = snapshot_teams(client, game_id, &like.team_ids()).await?;
+    if like.limits.is_some() {
+        snapshot.limits = Some(get_drinking_limits(client, game_id).await?);
+    }
+    if like.stock.is_some() {
+        snapshot.stock = Some(snapshot_stock(client, game_id).await?);
+    }
+    Ok(snapshot)
+}
+
+async fn snapshot_stock(
+    client: &impl GenericClient,
+    game_id: GameId,
+) -> Result<StockSnapshot, AppError> {
+    Ok(StockSnapshot {
+        opening: get_opening_stock(client, game_id).await?,
+        restocks: get_restocks(client, game_id).await?,
+    })
+}
+
+/// Writes a snapshot back: restores the game flags and start time, the teams, players,
+/// turns and drinks in it and the drinking limits and ingredient stock if it has them.
+/// Turns created after the snapshot are deleted; rows that did not change keep their identity.
+pub async fn restore_snapshot(
+    client: &impl GenericClient,
+    game_id: GameId,
+    snapshot: &GameSnapshot,
+) -> Result<(), AppError> {
+    client
+        .execute(
+            "UPDATE games SET started = $2, finished = $3, start_time = $4 WHERE game_id = $1",
+            &[
+                &game_id,
+                &snapshot.started,
+                &snapshot.finished,
+                &snapshot.start_time,
+            ],
+        )
+        .await?;
+    if let Some(limits) = &snapshot.limits {
+        set_drinking_limits(client, game_id, limits).await?;
+    }
+    if let Some(stock) = &snapshot.stock {
+        restore_stock(client, game_id, stock).await?;
+    }
+
+    for team_snapshot in &snapshot.teams {
+        let Some(team) = &team_snapshot.team else {
+            client
+                .execute(
+                    "DELETE FROM teams WHERE team_id = $1",
+                    &[&team_snapshot.team_id],
+                )
+                .await?;
+            continue;
+        };
+
+        client
+            .execute(
+                "INSERT INTO teams (team_id, game_id, team_name, team_hash, moral_victory_eligible,
+                    sober)
+                 VALUES ($1, $2, $3, $4, $5, $6)
+                 ON CONFLICT (team_id) DO UPDATE SET
+                    team_name = EXCLUDED.team_name,
+                    team_hash = EXCLUDED.team_hash,
+                    moral_victory_eligible = EXCLUDED.moral_victory_eligible,
+                    sober = EXCLUDED.sober",
+                &[
+                    &team.team_id,
+                    &team.game_id,
+                    &team.team_name,
+                    &team.team_hash,
+                    &team.moral_victory_eligible,
+                    &team.sober,
+                ],
+            )
+            .await?;
+
+        let player_ids: Vec<PlayerId> = team_snapshot.players.iter().map(|p| p.player_id).collect();
+        client
+            .execute(
+                "DELETE FROM players WHERE team_id = $1 AND NOT (player_id = ANY($2))",
+                &[&team.team_id, &player_ids],
+            )
+            .await?;
+        for player in &team_snapshot.players {
+            client
+                .execute(
+                    "INSERT INTO players (player_id, team_id, player_name, sober)
+                     VALUES ($1, $2, $3, $4)
+                     ON CONFLICT (player_id) DO UPDATE SET
+                        player_name = EXCLUDED.player_name,
+                        sober = EXCLUDED.sober",
+                    &[
+                        &player.player_id,
+                        &player.team_id,
+                        &player.player_name,
+                        &player.sober,
+                    ],
+                )
+                .await?;
+        }
+
+        let turn_ids: Vec<TurnId> = team_snapshot.turns.iter().map(|t| t.turn_id).collect();
+        client
+            .execute(
+                "DELETE FROM turns WHERE team_id = $1 AND NOT (turn_id = ANY($2))",
+                &[&team.team_id, &turn_ids],
+            )
+            .await?;
+
+        for turn in &team_snapshot.turns {
+            restore_turn(client, turn).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Restores the opening stock and restocks of a game. Restocks keep their ids.
+async fn restore_stock(
+    client: &impl GenericClient,
+    game_id: GameId,
+    stock: &StockSnapshot,
+) -> Result<(), AppError> {
+    let ingredient_ids: Vec<IngredientId> = stock.opening.iter().map(|s| s.ingredient_id).collect();
+    client
+        .execute(
+            "DELETE FROM ingredient_stock WHERE game_id = $1 AND NOT (ingredient_id = ANY($2))",
+            &[&game_id, &ingredient_ids],
+        )
+        .await?;
+    for opening in &stock.opening {
+        set_opening_stock(client, game_id, opening).await?;
+    }
+    let restock_ids: Vec<RestockId> = stock.restocks.iter().map(|r| r.restock_id).collect();
+    client
+        .execute(
+            "DELETE FROM ingredient_restocks WHERE game_id = $1 AND NOT (restock_id = ANY($2))",
+            &[&game_id, &restock_ids],
+        )
+        .await?;
+    for restock in &stock.restocks {
+        client
+            .execute(
+                "INSERT INTO ingredient_restocks
+                    (restock_id, game_id, ingredient_id, quantity, uid, created_at)
+                 VALUES ($1, $2, $3, $4, $5, $6)
+                 ON CONFLICT (restock_id) DO NOTHING",
+                &[
+                    &restock.restock_id,
+                    &game_id,
+                    &restock.ingredient_id,
+                    &restock.quantity,
+                    &restock.uid,
+                    &restock.created_at,
+                ],
+            )
+            .await?;
+    }
+    Ok(())
+}
+
+async fn restore_turn(client: &impl GenericClient, turn: &TurnRow) -> Result<(), AppError> {
+    client
+        .execute(
+            "INSERT INTO turns (turn_id, team_id, game_id, start_time, thrown_at, confirmed_at,
+                mixing_at, mixed_at, delivered_at, end_time, dice1, dice2, dice3, dice4,
+                place_number, via_number, penalty, double_tampere, over_limit)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18,
+                $19)
+             ON CONFLICT (turn_id) DO UPDATE SET
+                start_time = EXCLUDED.start_time,
+                thrown_at = EXCLUDED.thrown_at,
+                confirmed_at = EXCLUDED.confirmed_at,
+                mixing_at = EXCLUDED.mixing_at,
+                mixed_at = EXCLUDED.mixed_at,
+                delivered_at = EXCLUDED.delivered_at,
+                end_time = EXCLUDED.end_time,
+                dice1 = EXCLUDED.dice1,
+                dice2 = EXCLUDED.dice2,
+                dice3 = EXCLUDED.dice3,
+                dice4 = EXCLUDED.dice4,
+                place_number = EXCLUDED.place_number,
+                via_number = EXCLUDED.via_number,
+                penalty = EXCLUDED.penalty,
+                double_tampere = EXCLUDED.double_tampere,
+                over_limit = EXCLUDED.over_limit",
+            &[
+                &turn.turn_id,
+                &turn.team_id,
+                &turn.game_id,
+                &turn.start_time,
+                &turn.thrown_at,
+                &turn.confirmed_at,
+                &turn.mixing_at,
+                &turn.mixed_at,
+                &turn.delivered_at,
+                &turn.end_time,
+                &turn.dice1,
+                &turn.dice2,
+                &turn.dice3,
+                &turn.dice4,
+                &turn.place_number,
+                &turn.via_number,
+                &turn.penalty,
+                &turn.double_tampere,
+                &turn.over_limit,
+            ],
+        )
+        .await?;
+
+    client
+        .execute(
+            "DELETE FROM turn_drinks WHERE turn_id = $1",
+            &[&turn.turn_id],
+        )
+        .await?;
+    for drink in &turn.drinks {
+        client
+            .execute(
+                "INSERT INTO turn_drinks (turn_id, drink_id, n, on_table, optional, substituted_for)
+                 VALUES ($1, $2, $3, $4, $5, $6)",
+                &[
+                    &turn.turn_id,
+                    &drink.drink_id,
+                    &drink.n,
+                    &drink.on_table,
+                    &drink.optional,
+                    &drink.substituted_for,
+                ],
+            )
+            .await?;
+        for serving in &drink.players {
+            client
+                .execute(
+                    "INSERT INTO turn_drink_players (turn_id, drink_id, player_id, n)
+                     VALUES ($1, $2, $3, $4)",
+                    &[
+                        &turn.turn_id,
+                        &drink.drink_id,
+                        &serving.player_id,
+                        &serving.n,
+                    ],
+                )
+                .await?;
+        }
+    }
+
+    client
+        .execute(
+            "DELETE FROM turn_ingredient_usage WHERE turn_id = $1",
+            &[&turn.turn_id],
+        )
+        .await?;
+    for usage in &turn.usage {
+        client
+            .execute(
+                "INSERT INTO turn_ingredient_usage (turn_id, ingredient_id, quantity)
+                 VALUES ($1, $2, $3)",
+                &[&turn.turn_id, &usage.ingredient_id, &usage.quantity],
+            )
+            .await?;
+    }
+    Ok(())
+}
+
+/// Locks a game for an action on its own settings, so that concurrent changes to them are
+/// logged in order, and captures the game without any of its teams.
+async fn lock_game(client: &impl GenericClient, game_id: GameId) -> Result<GameSnapshot, AppError> {
+    client
+        .query_opt(
+            "SELECT game_id FROM games WHERE game_id = $1 FOR NO KEY UPDATE",
+            &[&game_id],
+        )
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Game {} not found", game_id)))?;
+    snapshot_teams(client, game_id, &[]).await
+}
+
+/// Captures the teams an action is about to change, so the action can be logged with
+/// its before and after state once it is done, together with the events it produced.
+///
+/// Create it before mutating and call [`ActionRecorder::finish`] in the same transaction.
+pub struct ActionRecorder {
+    game_id: GameId,
+    before: GameSnapshot,
+    events: Vec<GameEvent>,
+}
+
+impl ActionRecorder {
+    /// Locks the given teams of a game and captures their state. Fails if a team is not in
+    /// the game.
+    /// Teams are locked in id order so that concurrent actions cannot deadlock.
+    pub async fn begin(
+        client: &impl GenericClient,
+        game_id: GameId,
+        team_ids: &[TeamId],
+    ) -> Result<Self, AppError> {
+        let ids: Vec<TeamId> = team_ids.to_vec();
+        let locked: Vec<TeamId> = client
+            .query(
+                "SELECT team_id FROM teams WHERE team_id = ANY($1) AND game_id = $2
+                 ORDER BY team_id FOR UPDATE",
+                &[&ids, &game_id],
+            )
+            .await?
+            .iter()
+            .map(|row| row.get("team_id"))
+            .collect();
+        if let Some(missing) = team_ids.iter().find(|id| !locked.contains(id)) {
+            return Err(AppError::NotFound(format!(
+                "Team {} not found in game {}",
+                missing, game_id
+            )));
+        }
+        let before = snapshot_teams(client, game_id, team_ids).await?;
+        Ok(Self {
+            game_id,
+            before,
+            events: Vec::new(),
+        })
+    }
+
+    /// Starts recording an action on a turn; the action covers the team of the turn.
+    pub async fn begin_turn(
+        client: &impl GenericClient,
+        turn_id: TurnId,
+    ) -> Result<Self, AppError> {
+        let row = client
+            .query_opt(
+                "SELECT game_id, team_id FROM turns WHERE turn_id = $1",
+                &[&turn_id],
+            )
+            .await?
+            .ok_or_else(|| AppError::NotFound(format!("Turn {} not found", turn_id)))?;
+        Self::begin(client, row.get("game_id"), &[row.get("team_id")]).await
+    }
+
+    /// Starts recording an action on every team of the game.
+    pub async fn begin_game(
+        client: &impl GenericClient,
+        game_id: GameId,
+    ) -> Result<Self, AppError> {
+        let rows = client
+            .query("SELECT team_id FROM teams WHERE game_id = $1", &[&game_id])
+            .await?;
+        let team_ids: Vec<TeamId> = rows.iter().map(|r| r.get("team_id")).collect();
+        Self::begin(client, game_id, &team_ids).await
+    }
+
+    /// Starts recording an action on the drinking limits of a game.
+    pub async fn begin_limits(
+        client: &impl GenericClient,
+        game_id: GameId,
+    ) -> Result<Self, AppError> {
+        let mut before = lock_game(client, game_id).await?;
+        before.limits = Some(get_drinking_limits(client, game_id).await?);
+        Ok(Self {
+            game_id,
+            before,
+            events: Vec::new(),
+        })
+    }
+
+    /// Starts recording an action on the ingredient stock of a game.
+    pub async fn begin_stock(
+        client: &impl GenericClient,
+        game_id: GameId,
+    ) -> Result<Self, AppError> {
+        let mut before = lock_game(client, game_id).await?;
+        before.stock = Some(snapshot_stock(client, game_id).await?);
+        Ok(Self {
+            game_id,
+            before,
+            events: Vec::new(),
+        })
+    }
+
+    /// Adds a team created by the action; it did not exist before.
+    pub fn add_new_team(&mut self, team_id: TeamId) {
+        self.before.teams.push(TeamSnapshot {
+            team_id,
+            team: None,
+            players: Vec::new(),
+            turns: Vec::new(),
+        });
+    }
+
+    /// Adds an event to the event stream of the game along with the action.
+    pub fn event(&mut self, event: GameEvent) {
+        self.events.push(event);
+    }
+
+    /// Logs the action and its events. Actions that changed nothing are not logged.
+    pub async fn finish(
+        self,
+        client: &impl GenericClient,
+        uid: UserId,
+        action: &str,
+    ) -> Result<(), AppError> {
+        let after = snapshot_like(client, self.game_id, &self.before).await?;
+        if serde_json::to_value(&self.before).is_ok_and(|before| after.matches(&before)) {
+            return Ok(());
+        }
+        let action_id = insert_action(
+            client,
+            self.game_id,
+            Some(uid),
+            action,
+            None,
+            &self.before,
+            &after,
+        )
+        .await?;
+        insert_events(client, self.game_id, action_id, &self.events).await
+    }
+}
+
+async fn insert_action(
+    client: &impl GenericClient,
+    game_id: GameId,
+    uid: Option<UserId>,
+    action: &str,
+    reverts: Option<ActionId>,
+    before: &GameSnapshot,
+    after: &GameSnapshot,
+) -> Result<ActionId, AppError> {
+    let row = client
+        .query_one(
+            "INSERT INTO game_actions (game_id, uid, action, reverts, before, after)
+             VALUES ($1, $2, $3, $4, $5, $6)
+             RETURNING action_id",
+            &[
+                &game_id,
+                &uid,
+                &action,
+                &reverts,
+                &Json(before),
+                &Json(after),
+            ],
+        )
+        .await?;
+    Ok(row.get("action_id"))
+}
+
+/// The latest action logged for a game, None if it has none. Every change to a game logs an
+/// action, so data read after it includes every change up to it.
+pub async fn latest_action_id(
+    client: &impl GenericClient,
+    game_id: GameId,
+) -> Result<Option<ActionId>, AppError> {
+    let row = client
+        .query_one(
+            "SELECT max(action_id) AS action_id FROM game_actions WHERE game_id = $1",
+            &[&game_id],
+        )
+        .await?;
+    Ok(row.get("action_id"))
+}
+
+/// An action log row with its snapshots.
+pub struct StoredAction {
+    pub action: GameAction,
+    pub before: Value,
+    pub after: Value,
+}
+
+/// Retrieves the action log of a game, oldest first, with the `undone` flags resolved.
+pub async fn get_game_actions(
+    client: &impl GenericClient,
+    game_id: GameId,
+) -> Result<Vec<StoredAction>, AppError> {
+    let rows = client
+        .query(
+            "SELECT * FROM game_actions WHERE game_id = $1 ORDER BY action_id",
+            &[&game_id],
+        )
+        .await?;
+
+    let mut actions: Vec<StoredAction> = rows
+        .iter()
+        .map(|row| {
+            let Json(before): Json<Value> = row.get("before");
+            let Json(after): Json<Value> = row.get("after");
+            let team_ids = serde_json::from_value::<GameSnapshot>(after.clone())
+                .map(|s| s.team_ids())
+                .unwrap_or_default();
+            StoredAction {
+                action: GameAction {
+                    action_id: row.get("action_id"),
+                    game_id: row.get("game_id"),
+                    uid: row.get("uid"),
+                    action: row.get("action"),
+                    reverts: row.get("reverts"),
+                    team_ids,
+                    created_at: row.get("created_at"),
+                    undone: false,
+                },
+                before,
+                after,
+            }
+        })
+        .collect();
+
+    // An action is undone if a later action that is not undone itself reverts it.
+    // Reverts always point backwards, so walking from the newest action resolves every chain.
+    let mut undone: HashSet<ActionId> = HashSet::new();
+    for stored in actions.iter_mut().rev() {
+        if undone.contains(&stored.action.action_id) {
+            stored.action.undone = true;
+        } else if let Some(target) = stored.action.reverts {
+            undone.insert(target);
+        }
+    }
+
+    Ok(actions)
+}
+
+/// Reverts a logged action by restoring its before state, and logs the revert as "undo"
+/// (or "redo" when the reverted action was itself an undo).
+///
+/// Fails with a conflict if the action is already undone, or if the teams it touched have
+/// changed since, since restoring would then also throw away the later changes.
+pub async fn revert_action(
+    client: &impl GenericClient,
+    stored: &StoredAction,
+    uid: UserId,
+) -> Result<(), AppError> {
+    let action = &stored.action;
+    if action.undone {
+        return Err(AppError::Conflict(format!(
+            "Action {} is already undone",
+            action.action_id
+        )));
+    }
+
+    let before: GameSnapshot = serde_json::from_value(stored.before.clone())
+        .map_err(|e| AppError::Database(format!("Invalid action snapshot: {}", e)))?;
+    let current = snapshot_like(client, action.game_id, &before).await?;
+    if !current.matches(&stored.after) {
+        return Err(AppError::Conflict(format!(
+            "Action {} ({}) cannot be undone: its teams have changed since",
+            action.action_id, action.action
+        )));
+    }
+
+    restore_snapshot(client, action.game_id, &before).await?;
+    let kind = if action.action == "undo" {
+        "redo"
+    } else {
+        "undo"
+    };
+    insert_action(
+        client,
+        action.game_id,
+        Some(uid),
+        kind,
+        Some(action.action_id),
+        &current,
+        &before,
+    )
+    .await?;
+    Ok(())
+}
+
+/// Ids of the actions that have been undone.
+pub fn undone_action_ids(actions: &[StoredAction]) -> HashSet<ActionId> {
+    actions
+        .iter()
+        .filter(|a| a.action.undone)
+        .map(|a| a.action.action_id)
+        .collect()
+}
@@ -0,0 +1,444 @@
+use crate::database::boards::{get_board, get_board_places};
+use crate::database::drinks::{get_drink_ingredients, get_drinks_ingredients, get_ingredients};
+use crate::engine::specials::place_rule;
+use crate::engine::validation::validate_board;
+use crate::utils::ids::{BoardId, DrinkId, IngredientId, PlaceId};
+use crate::utils::state::AppError;
+use crate::utils::types::{
+    Board, BoardExport, BoardImportResult, DiagnosticSeverity, DrinkIngredients, Drinks,
+    ImportConflict, Ingredient,
+};
+use deadpool_postgres::GenericClient;
+use std::collections::{BTreeSet, HashMap};
+
+/// Version of the [`BoardExport`] format written by [`export_board`].
+pub const BOARD_EXPORT_VERSION: i32 = 1;
+
+/// Exports a board with its places, connections, place drinks and the drinks they reference,
+/// including their non-alcoholic equivalents.
+pub async fn export_board(
+    client: &impl GenericClient,
+    board_id: BoardId,
+) -> Result<BoardExport, AppError> {
+    let board = get_board_places(client, board_id).await?;
+    if board.board.id != board_id {
+        return Err(AppError::NotFound(format!("Board {} not found", board_id)));
+    }
+
+    let drink_ids: BTreeSet<DrinkId> = board
+        .places
+        .iter()
+        .flat_map(|p| &p.drinks.drinks)
+        .flat_map(|pd| [Some(pd.drink.id), pd.non_alcoholic.as_ref().map(|d| d.id)])
+        .flatten()
+        .collect();
+    let mut drinks = Vec::with_capacity(drink_ids.len());
+    for drink_id in drink_ids {
+        drinks.push(get_drink_ingredients(client, drink_id).await?);
+    }
+
+    Ok(BoardExport {
+        version: BOARD_EXPORT_VERSION,
+        board,
+        drinks,
+    })
+}
+
+/// Imports an exported board as a new board, remapping all IDs.
+///
+/// The board is validated first and rejected if it has errors. Drinks and ingredients are
+/// matched to existing ones by name as `on_conflict` says. Should be run in a transaction,
+/// since a failed import leaves the rows created so far behind.
+pub async fn import_board(
+    client: &impl GenericClient,
+    export: &BoardExport,
+    on_conflict: ImportConflict,
+) -> Result<BoardImportResult, AppError> {
+    if export.version != BOARD_EXPORT_VERSION {
+        return Err(AppError::Validation(format!(
+            "Unsupported board export version {} (expected {})",
+            export.version, BOARD_EXPORT_VERSION
+        )));
+    }
+
+    let catalog = Drinks {
+        drinks: export.drinks.iter().map(|d| d.drink.clone()).collect(),
+    };
+    let diagnostics = validate_board(&export.board, &catalog);
+    let errors: Vec<&str> = diagnostics
+        .iter()
+        .filter(|d| d.severity == DiagnosticSeverity::Error)
+        .map(|d| d.message.as_str())
+        .collect();
+    if !errors.is_empty() {
+        return Err(AppError::Validation(format!(
+            "The board has errors: {}",
+            errors.join("; ")
+        )));
+    }
+
+    let mut result = BoardImportResult {
+        board: export.board.board.clone(),
+        created_drinks: 0,
+        reused_drinks: 0,
+        created_ingredients: 0,
+        reused_ingredients: 0,
+        diagnostics,
+    };
+
+    let ingredient_ids = import_ingredients(client, export, on_conflict, &mut result).await?;
+    let drink_ids =
+        import_drinks(client, export, on_conflict, &ingredient_ids, &mut result).await?;
+
+    let board_id: BoardId = client
+        .query_one(
+            "INSERT INTO boards (name) VALUES ($1) RETURNING board_id",
+            &[&export.board.board.name],
+        )
+        .await?
+        .get(0);
+    result.board.id = board_id;
+    result.board.frozen = false;
+
+    // Places may be shared by several board places, so each is created once
+    let mut place_ids: HashMap<PlaceId, PlaceId> = HashMap::new();
+    for board_place in &export.board.places {
+        let place = &board_place.place;
+        let place_id = match place_ids.get(&place.place_id) {
+            Some(&place_id) => place_id,
+            None => {
+                let special = place_rule(place)?.map(|rule| rule.expression());
+                let place_id: PlaceId = client
+                    .query_one(
+                        "\
+                        INSERT INTO places (place_name, rule, place_type, special) \
+                        VALUES ($1, $2, $3, $4) RETURNING place_id",
+                        &[&place.place_name, &place.rule, &place.place_type, &special],
+                    )
+                    .await?
+                    .get(0);
+                place_ids.insert(place.place_id, place_id);
+                place_id
+            }
+        };
+
+        client
+            .execute(
+                "\
+                INSERT INTO board_places (board_id, place_number, place_id, area, start, \"end\", x, y) \
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+                &[
+                    &board_id,
+                    &board_place.place_number,
+                    &place_id,
+                    &board_place.area,
+                    &board_place.start,
+                    &board_place.end,
+                    &board_place.x,
+                    &board_place.y,
+                ],
+            )
+            .await?;
+    }
+
+    // Connections and drinks reference the board places, so they go in once all places exist
+    for board_place in &export.board.places {
+        for conn in &board_place.connections.forwards {
+            client
+                .execute(
+                    "\
+                    INSERT INTO place_connections (board_id, origin, target, on_land, dashed) \
+                    VALUES ($1, $2, $3, $4, $5)",
+                    &[
+                        &board_id,
+                        &board_place.place_number,
+                        &conn.target,
+                        &conn.on_land,
+                        &conn.dashed,
+                    ],
+                )
+                .await?;
+        }
+        for place_drink in &board_place.drinks.drinks {
+            client
+                .execute(
+                    "\
+                    INSERT INTO place_drinks (drink_id, board_id, place_number, refill, optional, on_table, n) \
+                    VALUES ($1, $2, $3, $4, $5, $6, $7)",
+                    &[
+                        &drink_ids[&place_drink.drink.id],
+                        &board_id,
+                        &board_place.place_number,
+                        &place_drink.refill,
+                        &place_drink.optional,
+                        &place_drink.on_table,
+                        &place_drink.n,
+                    ],
+                )
+                .await?;
+        }
+    }
+
+    Ok(result)
+}
+
+/// Deep-copies a board into a new, editable board with the given name.
+///
+/// Places are copied too, so editing the clone never changes the original board; the
+/// drinks of the places are shared.
+pub async fn clone_board(
+    client: &impl GenericClient,
+    board_id: BoardId,
+    name: &str,
+) -> Result<Board, AppError> {
+    let original = get_board(client, board_id).await?;
+    if original.id != board_id {
+        return Err(AppError::NotFound(format!("Board {} not found", board_id)));
+    }
+
+    let new_id: BoardId = client
+        .query_one(
+            "INSERT INTO boards (name) VALUES ($1) RETURNING board_id",
+            &[&name],
+        )
+        .await?
+        .get(0);
+
+    let place_rows = client
+        .query(
+            "SELECT DISTINCT place_id FROM board_places WHERE board_id = $1 ORDER BY place_id",
+            &[&board_id],
+        )
+        .await?;
+    for row in place_rows {
+        let place_id: PlaceId = row.get(0);
+        client
+            .execute(
+                "\
+                WITH new_place AS (
+                    INSERT INTO places (place_name, rule, place_type, special)
+                    SELECT place_name, rule, place_type, special FROM places WHERE place_id = $3
+                    RETURNING place_id
+                )
+                INSERT INTO board_places (board_id, place_number, place_id, area, start, \"end\", x, y)
+                SELECT $2, bp.place_number, new_place.place_id, bp.area, bp.start, bp.\"end\", bp.x, bp.y
+                FROM board_places AS bp, new_place
+                WHERE bp.board_id = $1 AND bp.place_id = $3",
+                &[&board_id, &new_id, &place_id],
+            )
+            .await?;
+    }
+
+    client
+        .execute(
+            "\
+            INSERT INTO place_connections (board_id, origin, target, on_land, dashed)
+            SELECT $2, origin, target, on_land, dashed FROM place_connections WHERE board_id = $1",
+            &[&board_id, &new_id],
+        )
+        .await?;
+    client
+        .execute(
+            "\
+            INSERT INTO place_drinks (drink_id, board_id, place_number, refill, optional, on_table, n)
+            SELECT drink_id, $2, place_number, refill, optional, on_table, n
+            FROM place_drinks WHERE board_id = $1",
+            &[&board_id, &new_id],
+        )
+        .await?;
+
+    Ok(Board {
+        id: new_id,
+        name: name.to_string(),
+        frozen: false,
+    })
+}
+
+/// Maps the ingredients of the exported drinks to existing or new ingredients.
+async fn import_ingredients(
+    client: &impl GenericClient,
+    export: &BoardExport,
+    on_conflict: ImportConflict,
+    result: &mut BoardImportResult,
+) -> Result<HashMap<IngredientId, IngredientId>, AppError> {
+    let existing = get_ingredients(client).await?.ingredients;
+    let mut ids = HashMap::new();
+
+    let ingredients: Vec<&Ingredient> = export
+        .drinks
+        .iter()
+        .flat_map(|d| d.ingredients.iter().map(|iq| &iq.ingredient))
+        .collect();
+    for ingredient in ingredients {
+        if ids.contains_key(&ingredient.id) {
+            continue;
+        }
+        let same_name: Vec<&Ingredient> = existing
+            .iter()
+            .filter(|i| i.name == ingredient.name)
+            .collect();
+        let reused = match (on_conflict, same_name.is_empty()) {
+            (ImportConflict::Copy, _) | (_, true) => None,
+            (ImportConflict::Fail, false) => {
+                return Err(AppError::Conflict(format!(
+                    "Ingredient '{}' already exists",
+                    ingredient.name
+                )));
+            }
+            (ImportConflict::Reuse, false) => {
+                let identical = same_name
+                    .iter()
+                    .find(|i| i.abv == ingredient.abv && i.carbonated == ingredient.carbonated);
+                match identical {
+                    Some(i) => Some(i.id),
+                    None => {
+                        return Err(AppError::Conflict(format!(
+                            "Ingredient '{}' already exists with a different abv or carbonation",
+                            ingredient.name
+                        )));
+                    }
+                }
+            }
+        };
+
+        let id = match reused {
+            Some(id) => {
+                result.reused_ingredients += 1;
+                id
+            }
+            None => {
+                result.created_ingredients += 1;
+                client
+                    .query_one(
+                        "\
+                        INSERT INTO ingredients (name, abv, carbonated) \
+                        VALUES ($1, $2, $3) RETURNING ingredient_id",
+                        &[&ingredient.name, &ingredient.abv, &ingredient.carbonated],
+                    )
+                    .await?
+                    .get(0)
+            }
+        };
+        ids.insert(ingredient.id, id);
+    }
+    Ok(ids)
+}
+
+/// Maps the exported drinks to existing or new drinks, creating the recipes of new ones.
+async fn import_drinks(
+    client: &impl GenericClient,
+    export: &BoardExport,
+    on_conflict: ImportConflict,
+    ingredient_ids: &HashMap<IngredientId, IngredientId>,
+    result: &mut BoardImportResult,
+) -> Result<HashMap<DrinkId, DrinkId>, AppError> {
+    let existing = get_drinks_ingredients(client).await?.drink_ingredients;
+    let mut ids = HashMap::new();
+    let mut created = Vec::new();
+
+    for drink in &export.drinks {
+        let same_name: Vec<&DrinkIngredients> = existing
+            .iter()
+            .filter(|d| d.drink.name == drink.drink.name)
+            .collect();
+        let reused = match (on_conflict, same_name.is_empty()) {
+            (ImportConflict::Copy, _) | (_, true) => None,
+            (ImportConflict::Fail, false) => {
+                return Err(AppError::Conflict(format!(
+                    "Drink '{}' already exists",
+                    drink.drink.name
+                )));
+            }
+            (ImportConflict::Reuse, false) => {
+                match same_name
+                    .iter()
+                    .find(|d| same_drink(d, drink, ingredient_ids))
+                {
+                    Some(d) => Some(d.drink.id),
+                    None => {
+                        return Err(AppError::Conflict(format!(
+                            "Drink '{}' already exists with different flags or ingredients",
+                            drink.drink.name
+                        )));
+                    }
+                }
+            }
+        };
+
+        let id = match reused {
+            Some(id) => {
+                result.reused_drinks += 1;
+                id
+            }
+            None => {
+                result.created_drinks += 1;
+                let id: DrinkId = client
+                    .query_one(
+                        "\
+                        INSERT INTO drinks (name, favorite, no_mix_required) \
+                        VALUES ($1, $2, $3) RETURNING drink_id",
+                        &[
+                            &drink.drink.name,
+                            &drink.drink.favorite,
+                            &drink.drink.no_mix_required,
+                        ],
+                    )
+                    .await?
+                    .get(0);
+                for iq in &drink.ingredients {
+                    client
+                        .execute(
+                            "\
+                            INSERT INTO drink_ingredients (drink_id, ingredient_id, quantity) \
+                            VALUES ($1, $2, $3)",
+                            &[&id, &ingredient_ids[&iq.ingredient.id], &iq.quantity],
+                        )
+                        .await?;
+                }
+                created.push((id, drink.drink.non_alcoholic));
+                id
+            }
+        };
+        ids.insert(drink.drink.id, id);
+    }
+
+    // Equivalents may come later in the export, so they are linked once every drink exists
+    for (id, non_alcoholic) in created {
+        let Some(non_alcoholic) = non_alcoholic.and_then(|na| ids.get(&na)) else {
+            continue;
+        };
+        client
+            .execute(
+                "UPDATE drinks SET non_alcoholic_id = $2 WHERE drink_id = $1",
+                &[&id, non_alcoholic],
+            )
+            .await?;
+    }
+    Ok(ids)
+}
+
+/// Whether an existing drink has the flags and recipe of an exported one.
+fn same_drink(
+    existing: &DrinkIngredients,
+    exported: &DrinkIngredients,
+    ingredient_ids: &HashMap<IngredientId, IngredientId>,
+) -> bool {
+    let recipe = |drink: &DrinkIngredients, map: Option<&HashMap<IngredientId, IngredientId>>| {
+        let mut recipe: Vec<(IngredientId, f64)> = drink
+            .ingredients
+            .iter()
+            .map(|iq| {
+                let id = iq.ingredient.id;
+                (
+                    map.and_then(|m| m.get(&id).copied()).unwrap_or(id),
+                    iq.quantity,
+                )
+            })
+            .collect();
+        recipe.sort_by_key(|(id, _)| *id);
+        recipe
+    };
+    existing.drink.favorite == exported.drink.favorite
+        && existing.drink.no_mix_required == exported.drink.no_mix_required
+        && recipe(existing, None) == recipe(exported, Some(ingredient_ids))
+}
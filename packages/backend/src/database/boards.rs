@@ -1,17 +1,18 @@
-use crate::utils::ids::BoardId;
+use crate::engine::specials::{place_rule, required_dice};
+use crate::utils::ids::{BoardId, DrinkId, PlaceId};
 use crate::utils::state::AppError;
 use crate::utils::types::{
-    Board, BoardPlace, BoardPlaces, Boards, Connection, Connections, Drink, Place, PlaceDrink,
-    PlaceDrinks, Places,
+    Board, BoardConnections, BoardEditResult, BoardPlace, BoardPlaceUpdate, BoardPlaces, Boards,
+    Connection, Connections, Drink, Place, PlaceDrink, PlaceDrinks, Places,
 };
-use deadpool_postgres::Client;
-use std::collections::HashMap;
+use deadpool_postgres::{Client, GenericClient};
+use std::collections::{HashMap, HashSet};
 use tokio_postgres::Row;
 
 /// Retrieves all game boards.
 pub async fn get_boards(client: &Client) -> Result<Boards, AppError> {
     let query = client
-        .query("SELECT board_id, name FROM boards;", &[])
+        .query("SELECT board_id, name, frozen FROM boards;", &[])
         .await?;
 
     Ok(Boards {
@@ -20,6 +21,7 @@ pub async fn get_boards(client: &Client) -> Result<Boards, AppError> {
             .map(|row| Board {
                 id: row.get("board_id"),
                 name: row.get("name"),
+                frozen: row.get("frozen"),
             })
             .collect(),
     })
@@ -35,24 +37,15 @@ pub async fn get_places(client: &Client) -> Result<Places, AppError> {
         .await?;
 
     Ok(Places {
-        places: query
-            .into_iter()
-            .map(|row| Place {
-                place_id: row.get("place_id"),
-                place_name: row.get("place_name"),
-                place_type: row.get("place_type"),
-                rule: row.get("rule"),
-                special: row.get("special"),
-            })
-            .collect(),
+        places: query.into_iter().map(|row| build_place(&row, "")).collect(),
     })
 }
 
 /// Retrieves a single board by ID.
-pub async fn get_board(client: &Client, board_id: BoardId) -> Result<Board, AppError> {
+pub async fn get_board(client: &impl GenericClient, board_id: BoardId) -> Result<Board, AppError> {
     let query = client
         .query_opt(
-            "SELECT board_id, name FROM boards WHERE board_id = $1",
+            "SELECT board_id, name, frozen FROM boards WHERE board_id = $1",
             &[&board_id],
         )
         .await?;
@@ -60,14 +53,38 @@ pub async fn get_board(client: &Client, board_id: BoardId) -> Result<Board, AppE
         Some(row) => Ok(Board {
             id: row.get("board_id"),
             name: row.get("name"),
+            frozen: row.get("frozen"),
         }),
         None => Ok(Board {
             id: BoardId(-1),
             name: "No Boards!".to_string(),
+            frozen: false,
         }),
     }
 }
 
+/// Checks that a board exists and has not been frozen by a game starting on it.
+pub async fn check_board_editable(
+    client: &impl GenericClient,
+    board_id: BoardId,
+) -> Result<(), AppError> {
+    let frozen: Option<bool> = client
+        .query_opt(
+            "SELECT frozen FROM boards WHERE board_id = $1",
+            &[&board_id],
+        )
+        .await?
+        .map(|row| row.get(0));
+    match frozen {
+        None => Err(AppError::NotFound(format!("Board {} not found", board_id))),
+        Some(true) => Err(AppError::Conflict(format!(
+            "Board {} is frozen because a game has started on it; clone it to make changes",
+            board_id
+        ))),
+        Some(false) => Ok(()),
+    }
+}
+
 /// Creates a new board.
 pub async fn post_board(client: &Client, board: Board) -> Result<u64, AppError> {
     Ok(client
@@ -75,17 +92,25 @@ pub async fn post_board(client: &Client, board: Board) -> Result<u64, AppError>
         .await?)
 }
 
+/// Builds a Place from a row, reading the columns with the given prefix (e.g. "via_").
+fn build_place(row: &Row, prefix: &str) -> Place {
+    let column = |name: &str| format!("{prefix}{name}");
+    let special: Option<String> = row.get(column("special").as_str());
+    Place {
+        place_id: row.get(column("place_id").as_str()),
+        place_name: row.get(column("place_name").as_str()),
+        rule: row.get(column("rule").as_str()),
+        place_type: row.get(column("place_type").as_str()),
+        required_dice: required_dice(special.as_deref()),
+        special,
+    }
+}
+
 /// Builds a BoardPlace struct from a row (without connections/drinks).
 pub fn build_board_place(row: &Row, board_id: BoardId) -> BoardPlace {
     BoardPlace {
         board_id,
-        place: Place {
-            place_id: row.get("place_id"),
-            place_name: row.get("place_name"),
-            rule: row.get("rule"),
-            place_type: row.get("place_type"),
-            special: row.get("special"),
-        },
+        place: build_place(row, ""),
         place_number: row.get("place_number"),
         start: row.get("start"),
         area: row.get("area"),
@@ -104,13 +129,7 @@ pub fn build_board_place(row: &Row, board_id: BoardId) -> BoardPlace {
 pub fn build_via_board_place(row: &Row, board_id: BoardId) -> BoardPlace {
     BoardPlace {
         board_id,
-        place: Place {
-            place_id: row.get("via_place_id"),
-            place_name: row.get("via_place_name"),
-            rule: row.get("via_rule"),
-            place_type: row.get("via_place_type"),
-            special: row.get("via_special"),
-        },
+        place: build_place(row, "via_"),
         place_number: row.get("via_number"),
         start: row.get("via_start"),
         area: row.get("via_area"),
@@ -130,7 +149,7 @@ pub fn build_via_board_place(row: &Row, board_id: BoardId) -> BoardPlace {
 /// Forward connections have origin = place_number.
 /// Backward connections have target = place_number (origin/target are swapped when reading).
 pub async fn get_board_place_connections(
-    client: &Client,
+    client: &impl GenericClient,
     board_id: BoardId,
     place_number: i32,
 ) -> Result<Connections, AppError> {
@@ -176,9 +195,180 @@ pub async fn get_board_place_connections(
     })
 }
 
+/// Checks that a board can be edited, returning warnings about the games created on it
+/// that have not started yet and will be played with the edited board.
+async fn board_edit_warnings(
+    client: &impl GenericClient,
+    board_id: BoardId,
+) -> Result<Vec<String>, AppError> {
+    check_board_editable(client, board_id).await?;
+    let games: Vec<String> = client
+        .query(
+            "SELECT name FROM games WHERE board_id = $1 ORDER BY game_id",
+            &[&board_id],
+        )
+        .await?
+        .into_iter()
+        .map(|row| row.get::<_, Option<String>>(0).unwrap_or_default())
+        .collect();
+    if games.is_empty() {
+        Ok(vec![])
+    } else {
+        Ok(vec![format!(
+            "Games {:?} are on board {} and will be played with the change",
+            games, board_id
+        )])
+    }
+}
+
+/// Checks that a place is not used on a frozen board, returning warnings about the
+/// boards that use it.
+async fn place_edit_warnings(
+    client: &impl GenericClient,
+    place_id: PlaceId,
+) -> Result<Vec<String>, AppError> {
+    let rows = client
+        .query(
+            "\
+            SELECT DISTINCT b.board_id, b.frozen
+            FROM board_places AS bp
+            INNER JOIN boards AS b ON b.board_id = bp.board_id
+            WHERE bp.place_id = $1
+            ORDER BY b.board_id",
+            &[&place_id],
+        )
+        .await?;
+
+    let mut warnings = Vec::new();
+    for row in rows {
+        let board_id: BoardId = row.get("board_id");
+        if row.get("frozen") {
+            return Err(AppError::Conflict(format!(
+                "Place {} is used on board {}, which is frozen because a game has started on it",
+                place_id, board_id
+            )));
+        }
+        warnings.push(format!("Place {} is used on board {}", place_id, board_id));
+        warnings.extend(board_edit_warnings(client, board_id).await?);
+    }
+    Ok(warnings)
+}
+
+/// Retrieves all connections of a board in their forward direction.
+pub async fn get_connections(
+    client: &impl GenericClient,
+    board_id: BoardId,
+) -> Result<BoardConnections, AppError> {
+    let query_str = "\
+    SELECT origin, target, on_land, dashed
+    FROM place_connections
+    WHERE board_id = $1
+    ORDER BY origin, target";
+
+    let query = client.query(query_str, &[&board_id]).await?;
+
+    Ok(BoardConnections {
+        connections: query
+            .into_iter()
+            .map(|row| Connection {
+                board_id,
+                origin: row.get("origin"),
+                target: row.get("target"),
+                on_land: row.get("on_land"),
+                dashed: row.get("dashed"),
+            })
+            .collect(),
+    })
+}
+
+/// Creates connections or updates the flags of existing ones.
+///
+/// Both ends of every connection must be places on the board; the `board_id` of the
+/// connections is ignored.
+pub async fn upsert_connections(
+    client: &impl GenericClient,
+    board_id: BoardId,
+    connections: &[Connection],
+) -> Result<u64, AppError> {
+    check_board_editable(client, board_id).await?;
+
+    let place_numbers: HashSet<i32> = client
+        .query(
+            "SELECT place_number FROM board_places WHERE board_id = $1",
+            &[&board_id],
+        )
+        .await?
+        .into_iter()
+        .map(|row| row.get(0))
+        .collect();
+    for conn in connections {
+        if conn.origin == conn.target {
+            return Err(AppError::Validation(format!(
+                "Place {} cannot be connected to itself",
+                conn.origin
+            )));
+        }
+        if let Some(missing) = [conn.origin, conn.target]
+            .into_iter()
+            .find(|n| !place_numbers.contains(n))
+        {
+            return Err(AppError::Validation(format!(
+                "Connection {} -> {} refers to place {}, which is not on board {}",
+                conn.origin, conn.target, missing, board_id
+            )));
+        }
+    }
+
+    let query_str = "\
+    INSERT INTO place_connections (board_id, origin, target, on_land, dashed) \
+    VALUES ($1, $2, $3, $4, $5) \
+    ON CONFLICT (board_id, origin, target) \
+    DO UPDATE SET on_land = EXCLUDED.on_land, dashed = EXCLUDED.dashed";
+
+    for conn in connections {
+        client
+            .execute(
+                query_str,
+                &[
+                    &board_id,
+                    &conn.origin,
+                    &conn.target,
+                    &conn.on_land,
+                    &conn.dashed,
+                ],
+            )
+            .await?;
+    }
+    Ok(connections.len() as u64)
+}
+
+/// Deletes the connection from `origin` to `target`.
+pub async fn delete_connection(
+    client: &impl GenericClient,
+    board_id: BoardId,
+    origin: i32,
+    target: i32,
+) -> Result<u64, AppError> {
+    check_board_editable(client, board_id).await?;
+
+    let query_str = "\
+    DELETE FROM place_connections WHERE board_id = $1 AND origin = $2 AND target = $3";
+
+    match client
+        .execute(query_str, &[&board_id, &origin, &target])
+        .await?
+    {
+        0 => Err(AppError::NotFound(format!(
+            "Board {} has no connection {} -> {}",
+            board_id, origin, target
+        ))),
+        n => Ok(n),
+    }
+}
+
 /// Builds a BoardPlace struct from a row and fetches its connections and drinks.
 async fn build_board_place_and_get_connections(
-    client: &Client,
+    client: &impl GenericClient,
     row: &Row,
 ) -> Result<BoardPlace, AppError> {
     let board_id = row.get("board_id");
@@ -192,7 +382,10 @@ async fn build_board_place_and_get_connections(
 /// Retrieves all places on a board with their connections and drinks.
 ///
 /// Fetches all connections for the board in a single query for efficiency.
-pub async fn get_board_places(client: &Client, board_id: BoardId) -> Result<BoardPlaces, AppError> {
+pub async fn get_board_places(
+    client: &impl GenericClient,
+    board_id: BoardId,
+) -> Result<BoardPlaces, AppError> {
     let board: Board = get_board(client, board_id).await?;
 
     let places_query_str = "\
@@ -286,7 +479,7 @@ pub async fn get_board_places(client: &Client, board_id: BoardId) -> Result<Boar
 
 /// Retrieves a specific place on a board by place number.
 pub async fn get_board_place(
-    client: &Client,
+    client: &impl GenericClient,
     board_id: BoardId,
     place_number: i32,
 ) -> Result<BoardPlace, AppError> {
@@ -317,7 +510,7 @@ pub async fn get_board_place(
 
 /// Retrieves drinks associated with a place on a board.
 pub async fn get_place_drinks(
-    client: &Client,
+    client: &impl GenericClient,
     place_number: i32,
     board_id: BoardId,
 ) -> Result<PlaceDrinks, AppError> {
@@ -329,6 +522,10 @@ pub async fn get_place_drinks(
         d.name,
         d.favorite,
         d.no_mix_required,
+        d.non_alcoholic_id,
+        na.name AS na_name,
+        na.favorite AS na_favorite,
+        na.no_mix_required AS na_no_mix_required,
         pd.refill,
         pd.optional,
         pd.on_table,
@@ -336,6 +533,8 @@ pub async fn get_place_drinks(
     FROM place_drinks AS pd
     INNER JOIN drinks AS d
         ON d.drink_id = pd.drink_id
+    LEFT JOIN drinks AS na
+        ON na.drink_id = d.non_alcoholic_id
     WHERE pd.place_number = $1 AND pd.board_id = $2";
 
     let query = client.query(query_str, &[&place_number, &board_id]).await?;
@@ -351,11 +550,21 @@ pub async fn get_place_drinks(
                     name: row.get("name"),
                     favorite: row.get("favorite"),
                     no_mix_required: row.get("no_mix_required"),
+                    non_alcoholic: row.get("non_alcoholic_id"),
                 },
                 refill: row.get("refill"),
                 optional: row.get("optional"),
                 on_table: row.get("on_table"),
                 n: row.get("n"),
+                non_alcoholic: row
+                    .get::<_, Option<DrinkId>>("non_alcoholic_id")
+                    .map(|id| Drink {
+                        id,
+                        name: row.get("na_name"),
+                        favorite: row.get("na_favorite"),
+                        no_mix_required: row.get("na_no_mix_required"),
+                        non_alcoholic: None,
+                    }),
             })
             .collect(),
     })
@@ -375,6 +584,8 @@ pub async fn set_place_drinks(client: &Client, drinks: PlaceDrinks) -> Result<u6
         ));
     }
 
+    check_board_editable(client, drinks.drinks[0].board_id).await?;
+
     // Delete existing drinks for the place
     let delete_str = "DELETE FROM place_drinks WHERE place_number = $1 AND board_id = $2";
     client
@@ -408,7 +619,10 @@ pub async fn set_place_drinks(client: &Client, drinks: PlaceDrinks) -> Result<u6
 }
 
 /// Creates a new place definition.
-pub async fn add_place(client: &Client, place: Place) -> Result<u64, AppError> {
+/// The special rule is validated and stored in its canonical form.
+pub async fn add_place(client: &Client, mut place: Place) -> Result<u64, AppError> {
+    place.special = place_rule(&place)?.map(|rule| rule.expression());
+
     let query_str = "\
     INSERT INTO places (place_name, rule, place_type, special) \
     VALUES ($1, $2, $3, $4)";
@@ -426,12 +640,138 @@ pub async fn add_place(client: &Client, place: Place) -> Result<u64, AppError> {
         .await?)
 }
 
+/// Updates the name, rule, type and special rule of a place.
+/// The special rule is validated and stored in its canonical form.
+pub async fn update_place(
+    client: &impl GenericClient,
+    place_id: PlaceId,
+    mut place: Place,
+) -> Result<BoardEditResult, AppError> {
+    place.special = place_rule(&place)?.map(|rule| rule.expression());
+    let warnings = place_edit_warnings(client, place_id).await?;
+
+    let query_str = "\
+    UPDATE places SET place_name = $1, rule = $2, place_type = $3, special = $4 \
+    WHERE place_id = $5";
+
+    let rows = client
+        .execute(
+            query_str,
+            &[
+                &place.place_name,
+                &place.rule,
+                &place.place_type,
+                &place.special,
+                &place_id,
+            ],
+        )
+        .await?;
+    if rows == 0 {
+        return Err(AppError::NotFound(format!("Place {} not found", place_id)));
+    }
+    Ok(BoardEditResult { rows, warnings })
+}
+
+/// Deletes a place definition, removing it from every board it is on.
+pub async fn delete_place(
+    client: &impl GenericClient,
+    place_id: PlaceId,
+) -> Result<BoardEditResult, AppError> {
+    let warnings = place_edit_warnings(client, place_id).await?;
+    let rows = client
+        .execute("DELETE FROM places WHERE place_id = $1", &[&place_id])
+        .await?;
+    if rows == 0 {
+        return Err(AppError::NotFound(format!("Place {} not found", place_id)));
+    }
+    Ok(BoardEditResult { rows, warnings })
+}
+
+/// Updates the area and start/end flags of a board place, and renumbers it if a new
+/// place_number is given. Connections and drinks of the place follow the renumbering.
+pub async fn update_board_place(
+    client: &impl GenericClient,
+    board_id: BoardId,
+    place_number: i32,
+    update: BoardPlaceUpdate,
+) -> Result<BoardEditResult, AppError> {
+    let warnings = board_edit_warnings(client, board_id).await?;
+
+    if let Some(new_number) = update.place_number.filter(|n| *n != place_number) {
+        let taken = client
+            .query_opt(
+                "SELECT 1 FROM board_places WHERE board_id = $1 AND place_number = $2",
+                &[&board_id, &new_number],
+            )
+            .await?;
+        if taken.is_some() {
+            return Err(AppError::Conflict(format!(
+                "Board {} already has a place number {}",
+                board_id, new_number
+            )));
+        }
+    }
+
+    let query_str = "\
+    UPDATE board_places SET \
+        place_number = COALESCE($3, place_number), \
+        area = COALESCE($4, area), \
+        start = COALESCE($5, start), \
+        \"end\" = COALESCE($6, \"end\") \
+    WHERE board_id = $1 AND place_number = $2";
+
+    let rows = client
+        .execute(
+            query_str,
+            &[
+                &board_id,
+                &place_number,
+                &update.place_number,
+                &update.area,
+                &update.start,
+                &update.end,
+            ],
+        )
+        .await?;
+    if rows == 0 {
+        return Err(AppError::NotFound(format!(
+            "Board {} has no place number {}",
+            board_id, place_number
+        )));
+    }
+    Ok(BoardEditResult { rows, warnings })
+}
+
+/// Removes a place from a board together with its connections and drinks.
+pub async fn delete_board_place(
+    client: &impl GenericClient,
+    board_id: BoardId,
+    place_number: i32,
+) -> Result<BoardEditResult, AppError> {
+    let warnings = board_edit_warnings(client, board_id).await?;
+    let rows = client
+        .execute(
+            "DELETE FROM board_places WHERE board_id = $1 AND place_number = $2",
+            &[&board_id, &place_number],
+        )
+        .await?;
+    if rows == 0 {
+        return Err(AppError::NotFound(format!(
+            "Board {} has no place number {}",
+            board_id, place_number
+        )));
+    }
+    Ok(BoardEditResult { rows, warnings })
+}
+
 /// Adds a place to a board at a specific position.
 pub async fn add_board_place(
     client: &Client,
     board_id: BoardId,
     place: BoardPlace,
 ) -> Result<u64, AppError> {
+    check_board_editable(client, board_id).await?;
+
     let query_str = "\
     INSERT INTO board_places (board_id, place_number, place_id, start, \"end\", x, y) \
     VALUES ($1, $2, $3, $4, $5, $6, $7)";
@@ -453,6 +793,7 @@ pub async fn add_board_place(
 }
 
 /// Updates the x,y coordinates of a place on a board.
+/// Allowed on frozen boards too, since the layout does not affect how games are played.
 pub async fn update_coordinates(
     client: &Client,
     board_id: BoardId,
@@ -469,123 +810,11 @@ pub async fn update_coordinates(
         .await?)
 }
 
-/// Moves a team forward on the board by the given throw amount.
-/// Returns (final_place, via_place) where via_place is set when the team passes through
-/// an intermediate place (on_land connection or -D1 special).
-/// `backward_throw` is the number of steps to move backwards if landing on a -D1 special.
-pub fn move_forwards<'a>(
-    mut current_place: &'a BoardPlace,
-    board_places: &'a BoardPlaces,
-    throw: i8,
-    backward_throw: Option<i8>,
-) -> Result<(&'a BoardPlace, Option<&'a BoardPlace>), AppError> {
-    for step in 0..throw {
-        let conns = &current_place.connections;
-
-        enum Choice<'a> {
-            Forward(&'a Connection),
-            OnLand,
-            Backward,
-        }
-
-        // Pick a forward, non-on-land connection if available
-        let chosen = conns
-            .forwards
-            .iter()
-            .find(|c| !c.on_land)
-            .map(|c| Choice::Forward(c))
-            // Otherwise, pick a forward, on-land connection if available (e.g. returning from Tampere)
-            .or_else(|| conns.forwards.first().map(|_| Choice::OnLand))
-            // Otherwise, fall back to a backward non-on-land connection
-            // (never traverse on-land backwards connections)
-            .or_else(|| {
-                conns
-                    .backwards
-                    .iter()
-                    .find(|c| !c.on_land)
-                    .map(|_| Choice::Backward)
-            });
-
-        match chosen {
-            Some(Choice::Backward) => {
-                // If there's only a backwards connection, reverse direction for the remaining steps
-                // Used at end of board
-                current_place = move_backwards(current_place, board_places, throw - step as i8)?;
-                break;
-            }
-            Some(Choice::OnLand) => {
-                // If the only forward connection is on_land, stop here
-                // The code after the loop will take this connection
-                // Used when returning from Tampere
-                break;
-            }
-            Some(Choice::Forward(chosen)) => {
-                // Take the chosen forward connection
-                current_place = board_places
-                    .find_place(chosen.target)
-                    .unwrap_or(current_place);
-            }
-            None => {
-                // If there are no connections at all, stop here
-                // Should never happen if the board is properly designed
-                tracing::info!("No more connections, stopping movement.");
-                break;
-            }
-        }
-    }
-
-    // If landing on a -D1 special, move backwards from start position by backward_throw
-    if current_place.place.special.as_deref() == Some("-D1") {
-        if let Some(bt) = backward_throw {
-            let dest = move_backwards(current_place, board_places, bt)?;
-            return Ok((dest, Some(current_place)));
-        }
-    }
-
-    // If there are any forward on_land connections in the final resting spot, take the first one
-    // Used to go to Tampere and Raide-Jokeri
-    if let Some(on_land) = current_place
-        .connections
-        .forwards
-        .iter()
-        .find(|c| c.on_land)
-    {
-        let dest = board_places
-            .find_place(on_land.target)
-            .unwrap_or(current_place);
-        return Ok((dest, Some(current_place)));
-    }
-
-    Ok((current_place, None))
-}
-
-/// Moves a team backward on the board by the given throw amount.
-pub fn move_backwards<'a>(
-    mut current_place: &'a BoardPlace,
-    board_places: &'a BoardPlaces,
-    throw: i8,
-) -> Result<&'a BoardPlace, AppError> {
-    for _ in 0..throw {
-        // Pick a non-on-land backwards connection if available
-        let Some(conn) = &current_place
-            .connections
-            .backwards
-            .iter()
-            .find(|c| !c.on_land)
-        else {
-            return Err(AppError::NotFound(
-                "Not enough backwards connections found".to_string(),
-            ));
-        };
-        current_place = board_places
-            .find_place(conn.target)
-            .unwrap_or(current_place);
-    }
-    Ok(current_place)
-}
-
 /// Gets the starting place number for a board.
-pub async fn get_first_place(client: &Client, board_id: BoardId) -> Result<i32, AppError> {
+pub async fn get_first_place(
+    client: &impl GenericClient,
+    board_id: BoardId,
+) -> Result<i32, AppError> {
     let query_str = "
     SELECT place_number FROM board_places WHERE board_id = $1 AND start = TRUE";
     let row = client.query_one(query_str, &[&board_id]).await?;
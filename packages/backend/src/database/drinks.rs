@@ -1,3 +1,4 @@
+use crate::engine::alcohol::{cl_to_grams, DrinkAlcohol};
 use crate::utils::ids::{DrinkId, IngredientId};
 use crate::utils::state::AppError;
 use crate::utils::types::DrinkIngredientsPost;
@@ -7,10 +8,11 @@ use crate::utils::{
         Drink, DrinkIngredients, Drinks, DrinksIngredients, Ingredient, IngredientQty, Ingredients,
     },
 };
-use deadpool_postgres::Client;
+use deadpool_postgres::{Client, GenericClient};
+use std::collections::HashMap;
 
 /// Retrieves all ingredients from the database.
-pub async fn get_ingredients(client: &Client) -> Result<Ingredients, AppError> {
+pub async fn get_ingredients(client: &impl GenericClient) -> Result<Ingredients, AppError> {
     let query_str = "\
     SELECT ingredient_id, name, abv, carbonated FROM ingredients";
 
@@ -61,20 +63,25 @@ pub async fn delete_drink(client: &Client, drink_id: DrinkId) -> Result<u64, App
 /// Inserts a new drink into the database.
 pub async fn post_drink(client: &Client, drink: Drink) -> Result<u64, AppError> {
     let query_str = "\
-    INSERT INTO drinks (name, favorite, no_mix_required) VALUES ($1, $2, $3)";
+    INSERT INTO drinks (name, favorite, no_mix_required, non_alcoholic_id) VALUES ($1, $2, $3, $4)";
 
     Ok(client
         .execute(
             query_str,
-            &[&drink.name, &drink.favorite, &drink.no_mix_required],
+            &[
+                &drink.name,
+                &drink.favorite,
+                &drink.no_mix_required,
+                &drink.non_alcoholic,
+            ],
         )
         .await?)
 }
 
 /// Retrieves all drinks from the database.
-pub async fn get_drinks(client: &Client) -> Result<Drinks, AppError> {
+pub async fn get_drinks(client: &impl GenericClient) -> Result<Drinks, AppError> {
     let query_str = "\
-    SELECT drink_id, name, favorite, no_mix_required FROM drinks";
+    SELECT drink_id, name, favorite, no_mix_required, non_alcoholic_id FROM drinks";
 
     let rows = client.query(query_str, &[]).await?;
     let drinks = rows
@@ -84,6 +91,7 @@ pub async fn get_drinks(client: &Client) -> Result<Drinks, AppError> {
             name: row.get(1),
             favorite: row.get(2),
             no_mix_required: row.get(3),
+            non_alcoholic: row.get(4),
         })
         .collect();
     Ok(Drinks { drinks })
@@ -126,7 +134,7 @@ pub async fn add_ingredients(
 
 /// Retrieves all ingredients for a specific drink.
 pub async fn get_drink_ingredients(
-    client: &Client,
+    client: &impl GenericClient,
     drink_id: DrinkId,
 ) -> Result<DrinkIngredients, AppError> {
     let query_str = "
@@ -135,6 +143,7 @@ pub async fn get_drink_ingredients(
         dr.name AS drink_name,
         dr.favorite,
         dr.no_mix_required,
+        dr.non_alcoholic_id,
         di.ingredient_id,
         i.name AS ingredient_name,
         i.abv,
@@ -161,6 +170,7 @@ pub async fn get_drink_ingredients(
         name: first_row.get("drink_name"),
         favorite: first_row.get("favorite"),
         no_mix_required: first_row.get("no_mix_required"),
+        non_alcoholic: first_row.get("non_alcoholic_id"),
     };
 
     let ingredients: Vec<_> = query
@@ -201,7 +211,9 @@ pub async fn get_drink_ingredients(
 }
 
 /// Retrieves all drinks with their ingredients and calculated ABV.
-pub async fn get_drinks_ingredients(client: &Client) -> Result<DrinksIngredients, AppError> {
+pub async fn get_drinks_ingredients(
+    client: &impl GenericClient,
+) -> Result<DrinksIngredients, AppError> {
     let mut drink_ingredients: Vec<DrinkIngredients> = Vec::new();
     let drinks = get_drinks(client).await?;
     for drink in drinks.drinks {
@@ -210,6 +222,35 @@ pub async fn get_drinks_ingredients(client: &Client) -> Result<DrinksIngredients
     Ok(DrinksIngredients { drink_ingredients })
 }
 
+/// Retrieves the ingredients of one serving of each of the given drinks.
+pub async fn get_recipes(
+    client: &impl GenericClient,
+    drink_ids: &[DrinkId],
+) -> Result<HashMap<DrinkId, Vec<IngredientQty>>, AppError> {
+    let mut recipes = HashMap::new();
+    for &drink_id in drink_ids {
+        let recipe = get_drink_ingredients(client, drink_id).await?;
+        recipes.insert(drink_id, recipe.ingredients);
+    }
+    Ok(recipes)
+}
+
+/// Retrieves the grams of ethanol in one serving of each drink that contains alcohol.
+pub async fn get_drink_alcohol(client: &impl GenericClient) -> Result<DrinkAlcohol, AppError> {
+    let query_str = "\
+    SELECT di.drink_id, SUM(di.quantity * i.abv / 100) AS alcohol_cl
+    FROM drink_ingredients AS di
+    JOIN ingredients AS i ON i.ingredient_id = di.ingredient_id
+    WHERE i.abv > 0
+    GROUP BY di.drink_id";
+
+    let query = client.query(query_str, &[]).await?;
+    Ok(query
+        .into_iter()
+        .map(|row| (row.get("drink_id"), cl_to_grams(row.get("alcohol_cl"))))
+        .collect())
+}
+
 /// Removes an ingredient from a drink.
 pub async fn delete_ingredient_from_drink(
     client: &Client,
@@ -223,10 +264,11 @@ pub async fn delete_ingredient_from_drink(
         .await?)
 }
 
-/// Updates a drink's name, favorite status, and no-mix flag.
+/// Updates a drink's name, favorite status, no-mix flag, and non-alcoholic equivalent.
 pub async fn update_drink(client: &Client, drink: Drink) -> Result<u64, AppError> {
     let query_str = "\
-    UPDATE drinks SET name = $1, favorite = $2, no_mix_required = $3 WHERE drink_id = $4";
+    UPDATE drinks SET name = $1, favorite = $2, no_mix_required = $3, non_alcoholic_id = $4
+    WHERE drink_id = $5";
 
     Ok(client
         .execute(
@@ -235,6 +277,7 @@ pub async fn update_drink(client: &Client, drink: Drink) -> Result<u64, AppError
                 &drink.name,
                 &drink.favorite,
                 &drink.no_mix_required,
+                &drink.non_alcoholic,
                 &drink.id,
             ],
         )
@@ -0,0 +1,55 @@
+use std::collections::HashSet;
+
+use crate::engine::events::{GameEvent, RecordedEvent};
+use crate::utils::ids::{ActionId, GameId};
+use crate::utils::state::AppError;
+use deadpool_postgres::GenericClient;
+use tokio_postgres::types::Json;
+
+/// Appends events to the event stream of a game.
+pub async fn insert_events(
+    client: &impl GenericClient,
+    game_id: GameId,
+    action_id: ActionId,
+    events: &[GameEvent],
+) -> Result<(), AppError> {
+    for event in events {
+        client
+            .execute(
+                "INSERT INTO game_events (game_id, action_id, event) VALUES ($1, $2, $3)",
+                &[&game_id, &action_id, &Json(event)],
+            )
+            .await?;
+    }
+    Ok(())
+}
+
+/// Retrieves the event stream of a game, oldest first.
+/// Events of the actions in `undone_actions` are marked undone.
+pub async fn get_game_events(
+    client: &impl GenericClient,
+    game_id: GameId,
+    undone_actions: &HashSet<ActionId>,
+) -> Result<Vec<RecordedEvent>, AppError> {
+    let rows = client
+        .query(
+            "SELECT * FROM game_events WHERE game_id = $1 ORDER BY event_id",
+            &[&game_id],
+        )
+        .await?;
+
+    rows.iter()
+        .map(|row| {
+            let Json(event): Json<GameEvent> = row.try_get("event")?;
+            let action_id: Option<ActionId> = row.get("action_id");
+            Ok(RecordedEvent {
+                event_id: row.get("event_id"),
+                game_id: row.get("game_id"),
+                action_id,
+                created_at: row.get("created_at"),
+                undone: action_id.is_some_and(|id| undone_actions.contains(&id)),
+                event,
+            })
+        })
+        .collect()
+}
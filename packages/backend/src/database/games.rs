@@ -1,16 +1,19 @@
-use crate::database::boards::{
-    build_board_place, build_via_board_place, get_board_place, get_first_place,
-};
-use crate::database::team::{get_team_by_id, get_teams};
+use crate::database::boards::{build_board_place, build_via_board_place, get_first_place};
+use crate::database::drinks::get_drink_alcohol;
+use crate::database::inventory::record_ingredient_usage;
+use crate::database::players::{get_game_players, get_game_servings, get_turn_servings};
+use crate::database::team::get_teams;
 use crate::database::turns::build_turn;
-use crate::utils::ids::{BoardId, GameId, PlaceId, TeamId, TurnId};
+use crate::engine::alcohol::add_alcohol_intake;
+use crate::engine::events::FirstTurn;
+use crate::utils::ids::{BoardId, DrinkId, GameId, PlaceId, TeamId, TurnId};
 use crate::utils::state::AppError;
 use crate::utils::types::{
-    Board, Drink, FirstTurnPost, Game, GameData, GameTeam, Games, PostGame, TeamLatestTurn, Turn,
-    TurnDrink, TurnDrinks,
+    AlcoholIntake, Board, Drink, FirstTurnPost, Game, GameData, GamePlayer, GameTeam, Games,
+    PostGame, Turn, TurnDrink, TurnDrinks,
 };
 use chrono::{DateTime, Utc};
-use deadpool_postgres::Client;
+use deadpool_postgres::{Client, GenericClient};
 use std::collections::HashMap;
 use tokio_postgres::Row;
 
@@ -19,7 +22,7 @@ pub async fn get_games(client: &Client) -> Result<Games, AppError> {
     let rows = client
         .query(
             "
-            SELECT games.*, boards.name AS board_name
+            SELECT games.*, boards.name AS board_name, boards.frozen AS board_frozen
             FROM games
             INNER JOIN boards ON games.board_id = boards.board_id",
             &[],
@@ -30,11 +33,14 @@ pub async fn get_games(client: &Client) -> Result<Games, AppError> {
 }
 
 /// Retrieves a game by its ID
-pub async fn get_game_by_id(client: &Client, game_id: GameId) -> Result<Game, AppError> {
+pub async fn get_game_by_id(
+    client: &impl GenericClient,
+    game_id: GameId,
+) -> Result<Game, AppError> {
     let row_opt = client
         .query_opt(
             "
-            SELECT games.*, boards.name AS board_name
+            SELECT games.*, boards.name AS board_name, boards.frozen AS board_frozen
             FROM games
             INNER JOIN boards ON games.board_id = boards.board_id
             WHERE games.game_id = $1",
@@ -55,7 +61,7 @@ pub async fn post_game(client: &Client, game: PostGame) -> Result<Game, AppError
               INSERT INTO games (name, board_id) VALUES ($1, $2)
               RETURNING *
             )
-            SELECT ins_game.*, boards.name AS board_name
+            SELECT ins_game.*, boards.name AS board_name, boards.frozen AS board_frozen
             FROM ins_game
             INNER JOIN boards ON ins_game.board_id = boards.board_id",
             &[&game.name, &game.board],
@@ -66,17 +72,17 @@ pub async fn post_game(client: &Client, game: PostGame) -> Result<Game, AppError
 
 /// Creates initial turns for all teams in a game at the starting position.
 pub async fn make_first_turns(
-    client: &Client,
+    client: &impl GenericClient,
     first_turn: &FirstTurnPost,
     place_number: i32,
-) -> Result<(), AppError> {
+) -> Result<Vec<FirstTurn>, AppError> {
     let query_str = "\
     WITH ins_turns AS (
       INSERT INTO turns (team_id, game_id, place_number, thrown_at, confirmed_at, mixing_at, mixed_at, delivered_at)
       SELECT team_id, $1::int, $2::int, NOW(), NOW(), NOW(), NOW(), NOW()
       FROM teams
       WHERE game_id = $1
-      RETURNING team_id, turn_id
+      RETURNING team_id, turn_id, place_number
     ),
     drinks(drink_id, n) AS (
       SELECT * FROM unnest($3::int[], $4::int[])
@@ -87,7 +93,7 @@ pub async fn make_first_turns(
       FROM ins_turns it
       CROSS JOIN drinks d
     )
-    SELECT team_id, turn_id FROM ins_turns";
+    SELECT team_id, turn_id, place_number FROM ins_turns ORDER BY team_id";
 
     let (drink_ids, counts): (Vec<i32>, Vec<i32>) = first_turn
         .drinks
@@ -95,18 +101,29 @@ pub async fn make_first_turns(
         .map(|td| (td.drink.id.0, td.n))
         .unzip();
 
-    client
-        .execute(
+    let rows = client
+        .query(
             query_str,
             &[&first_turn.game_id, &place_number, &drink_ids, &counts],
         )
         .await?;
 
-    Ok(())
+    Ok(rows
+        .iter()
+        .map(|row| FirstTurn {
+            team_id: row.get("team_id"),
+            turn_id: row.get("turn_id"),
+            place_number: row.get("place_number"),
+        })
+        .collect())
 }
 
 /// Starts a game by setting its start time and creating initial turns.
-pub async fn start_game(client: &Client, first_turn: FirstTurnPost) -> Result<Game, AppError> {
+/// Returns the game and the turns created for its teams.
+pub async fn start_game(
+    client: &impl GenericClient,
+    first_turn: FirstTurnPost,
+) -> Result<(Game, Vec<FirstTurn>), AppError> {
     let row = client
         .query_one(
             "
@@ -115,23 +132,33 @@ pub async fn start_game(client: &Client, first_turn: FirstTurnPost) -> Result<Ga
               SET started = true, start_time = NOW()
               WHERE game_id = $1
               RETURNING *
+            ), upd_board AS (
+              UPDATE boards
+              SET frozen = true
+              FROM upd_game
+              WHERE boards.board_id = upd_game.board_id
+              RETURNING boards.*
             )
-            SELECT upd_game.*, boards.name AS board_name
+            SELECT upd_game.*, upd_board.name AS board_name, upd_board.frozen AS board_frozen
             FROM upd_game
-            INNER JOIN boards ON upd_game.board_id = boards.board_id",
+            INNER JOIN upd_board ON upd_game.board_id = upd_board.board_id",
             &[&first_turn.game_id],
         )
         .await?;
     let game = build_game_from_row(&row);
 
     let place_number = get_first_place(client, game.board.id).await?;
-    make_first_turns(client, &first_turn, place_number).await?;
+    let first_turns = make_first_turns(client, &first_turn, place_number).await?;
+    // The first turns are delivered straight away
+    for first in &first_turns {
+        record_ingredient_usage(client, first.turn_id).await?;
+    }
 
-    Ok(game)
+    Ok((game, first_turns))
 }
 
 /// Ends a game by marking it finished and ending all active turns.
-pub async fn end_game(client: &Client, game_id: GameId) -> Result<Game, AppError> {
+pub async fn end_game(client: &impl GenericClient, game_id: GameId) -> Result<Game, AppError> {
     let row = client
         .query_one(
             "
@@ -141,7 +168,7 @@ pub async fn end_game(client: &Client, game_id: GameId) -> Result<Game, AppError
               WHERE game_id = $1
               RETURNING *
             )
-            SELECT upd_game.*, boards.name AS board_name
+            SELECT upd_game.*, boards.name AS board_name, boards.frozen AS board_frozen
             FROM upd_game
             INNER JOIN boards ON upd_game.board_id = boards.board_id",
             &[&game_id],
@@ -150,6 +177,23 @@ pub async fn end_game(client: &Client, game_id: GameId) -> Result<Game, AppError
     Ok(build_game_from_row(&row))
 }
 
+/// Retrieves a game and locks its row until the end of the transaction.
+pub async fn lock_game(client: &impl GenericClient, game_id: GameId) -> Result<Game, AppError> {
+    let row = client
+        .query_opt(
+            "
+            SELECT games.*, boards.name AS board_name, boards.frozen AS board_frozen
+            FROM games
+            INNER JOIN boards ON games.board_id = boards.board_id
+            WHERE games.game_id = $1
+            FOR UPDATE OF games",
+            &[&game_id],
+        )
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Game {} not found", game_id)))?;
+    Ok(build_game_from_row(&row))
+}
+
 /// Constructs a Game struct from a database row.
 fn build_game_from_row(row: &Row) -> Game {
     Game {
@@ -161,6 +205,7 @@ fn build_game_from_row(row: &Row) -> Game {
         board: Board {
             id: row.get("board_id"),
             name: row.get("board_name"),
+            frozen: row.get("board_frozen"),
         },
     }
 }
@@ -173,6 +218,7 @@ fn default_game() -> Game {
         board: Board {
             id: BoardId(-404),
             name: "unknown".to_string(),
+            frozen: false,
         },
         started: false,
         finished: false,
@@ -187,7 +233,7 @@ pub async fn get_game_list(client: &Client) -> Result<Vec<Game>, AppError> {
     let rows = client
         .query(
             "
-            SELECT games.*, boards.name AS board_name
+            SELECT games.*, boards.name AS board_name, boards.frozen AS board_frozen
             FROM games
             INNER JOIN boards ON games.board_id = boards.board_id",
             &[],
@@ -210,7 +256,7 @@ pub async fn get_full_game_data(client: &Client, game_id: GameId) -> Result<Game
                 t.turn_id, t.team_id, t.game_id, t.start_time, t.thrown_at,
                 t.confirmed_at, t.mixing_at, t.mixed_at, t.delivered_at,
                 t.end_time, t.dice1, t.dice2, t.dice3, t.dice4,
-                t.place_number, t.via_number, t.penalty, t.double_tampere,
+                t.place_number, t.via_number, t.penalty, t.double_tampere, t.over_limit,
                 bp.start, bp.area, bp.\"end\", bp.x, bp.y,
                 p.place_id, p.place_name, p.rule, p.place_type, p.special,
                 vbp.start AS via_start, vbp.area AS via_area, vbp.\"end\" AS via_end,
@@ -231,7 +277,8 @@ pub async fn get_full_game_data(client: &Client, game_id: GameId) -> Result<Game
     // Fetch all turn drinks separately
     let drink_rows = client
         .query(
-            "SELECT td.turn_id, td.drink_id, td.n, td.on_table, td.optional, d.name, d.favorite, d.no_mix_required
+            "SELECT td.turn_id, td.drink_id, td.n, td.on_table, td.optional, td.substituted_for,
+                d.name, d.favorite, d.no_mix_required, d.non_alcoholic_id
              FROM turn_drinks td
              JOIN drinks d ON d.drink_id = td.drink_id
              JOIN turns t ON t.turn_id = td.turn_id
@@ -241,19 +288,24 @@ pub async fn get_full_game_data(client: &Client, game_id: GameId) -> Result<Game
         .await?;
 
     // Build drinks lookup
+    let mut servings = get_game_servings(client, game_id).await?;
     let mut drinks_by_turn: HashMap<TurnId, Vec<TurnDrink>> = HashMap::new();
     for row in drink_rows {
         let turn_id: TurnId = row.get("turn_id");
+        let drink_id: DrinkId = row.get("drink_id");
         drinks_by_turn.entry(turn_id).or_default().push(TurnDrink {
             drink: Drink {
                 id: row.get("drink_id"),
                 name: row.get("name"),
                 favorite: row.get("favorite"),
                 no_mix_required: row.get("no_mix_required"),
+                non_alcoholic: row.get("non_alcoholic_id"),
             },
             n: row.get("n"),
             on_table: row.get("on_table"),
             optional: row.get("optional"),
+            substituted_for: row.get("substituted_for"),
+            players: servings.remove(&(turn_id, drink_id)).unwrap_or_default(),
         });
     }
 
@@ -273,6 +325,7 @@ pub async fn get_full_game_data(client: &Client, game_id: GameId) -> Result<Game
     }
 
     // Build GameTeam for each team
+    let players = get_game_players(client, game_id).await?;
     let teams = teams
         .into_iter()
         .map(|team| {
@@ -283,16 +336,28 @@ pub async fn get_full_game_data(client: &Client, game_id: GameId) -> Result<Game
                 .rev()
                 .find_map(|t| t.double_tampere)
                 .unwrap_or(false);
+            let team_players = players
+                .iter()
+                .filter(|p| p.team_id == team.team_id)
+                .map(|player| GamePlayer {
+                    player: player.clone(),
+                    alcohol: AlcoholIntake::default(),
+                })
+                .collect();
             GameTeam {
                 team,
                 turns,
                 location,
                 double_tampere,
+                alcohol: AlcoholIntake::default(),
+                players: team_players,
             }
         })
         .collect();
 
-    Ok(GameData { game, teams })
+    let mut game_data = GameData { game, teams };
+    add_alcohol_intake(&mut game_data, &get_drink_alcohol(client).await?);
+    Ok(game_data)
 }
 
 /// Validates that dice values are between 1 and 6
@@ -317,16 +382,21 @@ pub fn check_opt_dice(dice: Option<i32>) -> Result<Option<i32>, AppError> {
 }
 
 /// Retrieves drinks associated with a turn
-pub async fn get_turn_drinks(client: &Client, turn_id: TurnId) -> Result<TurnDrinks, AppError> {
+pub async fn get_turn_drinks(
+    client: &impl GenericClient,
+    turn_id: TurnId,
+) -> Result<TurnDrinks, AppError> {
     let rows = client
         .query(
-            "SELECT td.drink_id, d.name, d.favorite, d.no_mix_required, td.n, td.on_table, td.optional
+            "SELECT td.drink_id, d.name, d.favorite, d.no_mix_required, d.non_alcoholic_id,
+                td.n, td.on_table, td.optional, td.substituted_for
              FROM turn_drinks td
              JOIN drinks d ON td.drink_id = d.drink_id
              WHERE td.turn_id = $1",
             &[&turn_id],
         )
         .await?;
+    let mut servings = get_turn_servings(client, turn_id).await?;
 
     let turn_drinks = TurnDrinks {
         drinks: rows
@@ -337,72 +407,18 @@ pub async fn get_turn_drinks(client: &Client, turn_id: TurnId) -> Result<TurnDri
                     name: row.get("name"),
                     favorite: row.get("favorite"),
                     no_mix_required: row.get("no_mix_required"),
+                    non_alcoholic: row.get("non_alcoholic_id"),
                 },
                 n: row.get("n"),
                 on_table: row.get("on_table"),
                 optional: row.get("optional"),
+                substituted_for: row.get("substituted_for"),
+                players: servings
+                    .remove(&row.get::<_, DrinkId>("drink_id"))
+                    .unwrap_or_default(),
             })
             .collect(),
     };
 
     Ok(turn_drinks)
 }
-
-/// Checks if a place has already been visited in a game
-pub async fn count_place_visits(
-    client: &Client,
-    game_id: GameId,
-    place_number: i32,
-) -> Result<i32, AppError> {
-    Ok(client
-        .query_one(
-            "SELECT COUNT(*)::integer FROM turns
-            WHERE game_id = $1 AND place_number = $2 AND confirmed_at IS NOT NULL",
-            &[&game_id, &place_number],
-        )
-        .await?
-        .get(0))
-}
-
-/// Lightweight version of get_full_game_data that only fetches one team and their latest turn.
-/// Use this when you only need data for the involved team, not all teams.
-pub async fn get_team_latest_turn(
-    client: &Client,
-    game_id: GameId,
-    team_id: TeamId,
-) -> Result<TeamLatestTurn, AppError> {
-    let game = get_game_by_id(client, game_id).await?;
-    let team = get_team_by_id(client, team_id).await?;
-
-    // Get the latest confirmed turn with a location (excludes penalty turns)
-    let latest_turn = client
-        .query_opt(
-            "SELECT * FROM turns
-             WHERE team_id = $1 AND game_id = $2
-               AND confirmed_at IS NOT NULL AND place_number IS NOT NULL
-             ORDER BY turn_id DESC LIMIT 1",
-            &[&team_id, &game_id],
-        )
-        .await?
-        .map(|r| build_turn(&r));
-
-    // Get location from latest confirmed turn
-    let location = match latest_turn.as_ref().and_then(|t| t.place_number) {
-        Some(place_number) => get_board_place(client, game.board.id, place_number)
-            .await
-            .ok(),
-        None => None,
-    };
-
-    let double_tampere = latest_turn
-        .as_ref()
-        .and_then(|t| t.double_tampere)
-        .unwrap_or(false);
-
-    Ok(TeamLatestTurn {
-        team,
-        latest_turn,
-        location,
-        double_tampere,
-    })
-}
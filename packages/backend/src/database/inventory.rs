@@ -0,0 +1,231 @@
+use crate::database::drinks::get_recipes;
+use crate::database::games::get_turn_drinks;
+use crate::engine::inventory::{inventory, turn_usage, StockLevel};
+use crate::utils::ids::{DrinkId, GameId, IngredientId, TurnId, UserId};
+use crate::utils::state::AppError;
+use crate::utils::types::{GameInventory, Ingredient, OpeningStock, Restock};
+use deadpool_postgres::GenericClient;
+use std::collections::HashMap;
+use tokio_postgres::Row;
+
+/// Retrieves the stored stock of each ingredient tracked in a game.
+pub async fn get_stock_levels(
+    client: &impl GenericClient,
+    game_id: GameId,
+) -> Result<Vec<StockLevel>, AppError> {
+    let rows = client
+        .query(
+            "SELECT s.opening, s.low_stock, i.ingredient_id, i.name, i.abv, i.carbonated
+             FROM ingredient_stock s
+             INNER JOIN ingredients i ON i.ingredient_id = s.ingredient_id
+             WHERE s.game_id = $1
+             ORDER BY i.name, i.ingredient_id",
+            &[&game_id],
+        )
+        .await?;
+    Ok(rows
+        .iter()
+        .map(|row| StockLevel {
+            ingredient: Ingredient {
+                id: row.get("ingredient_id"),
+                name: row.get("name"),
+                abv: row.get("abv"),
+                carbonated: row.get("carbonated"),
+            },
+            opening: row.get("opening"),
+            low_stock: row.get("low_stock"),
+        })
+        .collect())
+}
+
+/// Retrieves the opening stock of each ingredient tracked in a game, by ingredient.
+pub async fn get_opening_stock(
+    client: &impl GenericClient,
+    game_id: GameId,
+) -> Result<Vec<OpeningStock>, AppError> {
+    let rows = client
+        .query(
+            "SELECT ingredient_id, opening, low_stock FROM ingredient_stock
+             WHERE game_id = $1 ORDER BY ingredient_id",
+            &[&game_id],
+        )
+        .await?;
+    Ok(rows
+        .iter()
+        .map(|row| OpeningStock {
+            ingredient_id: row.get("ingredient_id"),
+            opening: row.get("opening"),
+            low_stock: row.get("low_stock"),
+        })
+        .collect())
+}
+
+fn build_restock(row: &Row) -> Restock {
+    Restock {
+        restock_id: row.get("restock_id"),
+        ingredient_id: row.get("ingredient_id"),
+        quantity: row.get("quantity"),
+        uid: row.get("uid"),
+        created_at: row.get("created_at"),
+    }
+}
+
+/// Retrieves the restocks of a game, newest first.
+pub async fn get_restocks(
+    client: &impl GenericClient,
+    game_id: GameId,
+) -> Result<Vec<Restock>, AppError> {
+    let rows = client
+        .query(
+            "SELECT restock_id, ingredient_id, quantity, uid, created_at
+             FROM ingredient_restocks WHERE game_id = $1
+             ORDER BY created_at DESC, restock_id DESC",
+            &[&game_id],
+        )
+        .await?;
+    Ok(rows.iter().map(build_restock).collect())
+}
+
+/// Retrieves the ingredients used so far in a game.
+pub async fn get_ingredient_usage(
+    client: &impl GenericClient,
+    game_id: GameId,
+) -> Result<HashMap<IngredientId, f64>, AppError> {
+    let rows = client
+        .query(
+            "SELECT u.ingredient_id, SUM(u.quantity) AS used
+             FROM turn_ingredient_usage u
+             INNER JOIN turns t ON t.turn_id = u.turn_id
+             WHERE t.game_id = $1
+             GROUP BY u.ingredient_id",
+            &[&game_id],
+        )
+        .await?;
+    Ok(rows
+        .iter()
+        .map(|row| (row.get("ingredient_id"), row.get("used")))
+        .collect())
+}
+
+/// Retrieves the ingredient inventory of a game.
+pub async fn get_inventory(
+    client: &impl GenericClient,
+    game_id: GameId,
+) -> Result<GameInventory, AppError> {
+    let levels = get_stock_levels(client, game_id).await?;
+    let restocks = get_restocks(client, game_id).await?;
+    let used = get_ingredient_usage(client, game_id).await?;
+    Ok(inventory(game_id, levels, restocks, &used))
+}
+
+/// Retrieves the ingredients that the drinks of the given turns used.
+pub async fn get_used_ingredients(
+    client: &impl GenericClient,
+    turn_ids: &[TurnId],
+) -> Result<Vec<IngredientId>, AppError> {
+    let ids: Vec<TurnId> = turn_ids.to_vec();
+    let rows = client
+        .query(
+            "SELECT DISTINCT ingredient_id FROM turn_ingredient_usage
+             WHERE turn_id = ANY($1) ORDER BY ingredient_id",
+            &[&ids],
+        )
+        .await?;
+    Ok(rows.iter().map(|row| row.get("ingredient_id")).collect())
+}
+
+/// Records what the drinks of a turn use with the current recipes, unless it was recorded
+/// already.
+pub async fn record_ingredient_usage(
+    client: &impl GenericClient,
+    turn_id: TurnId,
+) -> Result<(), AppError> {
+    let recorded = client
+        .query_opt(
+            "SELECT 1 FROM turn_ingredient_usage WHERE turn_id = $1 LIMIT 1",
+            &[&turn_id],
+        )
+        .await?;
+    if recorded.is_some() {
+        return Ok(());
+    }
+    let drinks = get_turn_drinks(client, turn_id).await?;
+    let drink_ids: Vec<DrinkId> = drinks.drinks.iter().map(|td| td.drink.id).collect();
+    let recipes = get_recipes(client, &drink_ids).await?;
+    for (ingredient_id, quantity) in turn_usage(&drinks, &recipes) {
+        client
+            .execute(
+                "INSERT INTO turn_ingredient_usage (turn_id, ingredient_id, quantity)
+                 VALUES ($1, $2, $3)",
+                &[&turn_id, &ingredient_id, &quantity],
+            )
+            .await?;
+    }
+    Ok(())
+}
+
+/// Removes the recorded usage of a turn whose drinks went back to the queue.
+pub async fn clear_ingredient_usage(
+    client: &impl GenericClient,
+    turn_id: TurnId,
+) -> Result<(), AppError> {
+    client
+        .execute(
+            "DELETE FROM turn_ingredient_usage WHERE turn_id = $1",
+            &[&turn_id],
+        )
+        .await?;
+    Ok(())
+}
+
+/// Creates or replaces the opening stock of an ingredient in a game.
+pub async fn set_opening_stock(
+    client: &impl GenericClient,
+    game_id: GameId,
+    stock: &OpeningStock,
+) -> Result<(), AppError> {
+    client
+        .execute(
+            "INSERT INTO ingredient_stock (game_id, ingredient_id, opening, low_stock)
+             VALUES ($1, $2, $3, $4)
+             ON CONFLICT (game_id, ingredient_id) DO UPDATE SET
+                opening = EXCLUDED.opening,
+                low_stock = EXCLUDED.low_stock",
+            &[
+                &game_id,
+                &stock.ingredient_id,
+                &stock.opening,
+                &stock.low_stock,
+            ],
+        )
+        .await?;
+    Ok(())
+}
+
+/// Records stock brought in during a game. An ingredient without an opening stock starts
+/// being tracked from nothing.
+pub async fn add_restock(
+    client: &impl GenericClient,
+    game_id: GameId,
+    ingredient_id: IngredientId,
+    quantity: f64,
+    uid: UserId,
+) -> Result<Restock, AppError> {
+    client
+        .execute(
+            "INSERT INTO ingredient_stock (game_id, ingredient_id, opening)
+             VALUES ($1, $2, 0)
+             ON CONFLICT (game_id, ingredient_id) DO NOTHING",
+            &[&game_id, &ingredient_id],
+        )
+        .await?;
+    let row = client
+        .query_one(
+            "INSERT INTO ingredient_restocks (game_id, ingredient_id, quantity, uid)
+             VALUES ($1, $2, $3, $4)
+             RETURNING restock_id, ingredient_id, quantity, uid, created_at",
+            &[&game_id, &ingredient_id, &quantity, &uid],
+        )
+        .await?;
+    Ok(build_restock(&row))
+}
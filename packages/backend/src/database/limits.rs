@@ -0,0 +1,64 @@
+use crate::utils::ids::GameId;
+use crate::utils::state::AppError;
+use crate::utils::types::{DrinkingLimits, LimitAction};
+use deadpool_postgres::GenericClient;
+
+/// Retrieves the drinking limits of a game. A game without limits gets none enforced.
+pub async fn get_drinking_limits(
+    client: &impl GenericClient,
+    game_id: GameId,
+) -> Result<DrinkingLimits, AppError> {
+    let row = client
+        .query_opt(
+            "SELECT per_hour, per_game, per_turn, action, substitute_drink_id
+             FROM drinking_limits WHERE game_id = $1",
+            &[&game_id],
+        )
+        .await?;
+    Ok(match row {
+        Some(row) => DrinkingLimits {
+            per_hour: row.get("per_hour"),
+            per_game: row.get("per_game"),
+            per_turn: row.get("per_turn"),
+            action: row.get("action"),
+            substitute_drink: row.get("substitute_drink_id"),
+        },
+        None => DrinkingLimits {
+            per_hour: None,
+            per_game: None,
+            per_turn: None,
+            action: LimitAction::Flag,
+            substitute_drink: None,
+        },
+    })
+}
+
+/// Creates or replaces the drinking limits of a game.
+pub async fn set_drinking_limits(
+    client: &impl GenericClient,
+    game_id: GameId,
+    limits: &DrinkingLimits,
+) -> Result<(), AppError> {
+    client
+        .execute(
+            "INSERT INTO drinking_limits
+                (game_id, per_hour, per_game, per_turn, action, substitute_drink_id)
+             VALUES ($1, $2, $3, $4, $5, $6)
+             ON CONFLICT (game_id) DO UPDATE SET
+                per_hour = EXCLUDED.per_hour,
+                per_game = EXCLUDED.per_game,
+                per_turn = EXCLUDED.per_turn,
+                action = EXCLUDED.action,
+                substitute_drink_id = EXCLUDED.substitute_drink_id",
+            &[
+                &game_id,
+                &limits.per_hour,
+                &limits.per_game,
+                &limits.per_turn,
+                &limits.action,
+                &limits.substitute_drink,
+            ],
+        )
+        .await?;
+    Ok(())
+}
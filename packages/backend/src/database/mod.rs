@@ -1,7 +1,13 @@
+pub mod actions;
+pub mod board_transfer;
 pub mod boards;
 pub mod drinks;
+pub mod events;
 pub mod games;
+pub mod inventory;
+pub mod limits;
 pub mod login;
+pub mod players;
 pub mod team;
 pub mod turns;
 pub mod utils;
@@ -0,0 +1,171 @@
+use crate::utils::ids::{DrinkId, GameId, PlayerId, TeamId, TurnId};
+use crate::utils::state::AppError;
+use crate::utils::types::{Player, PlayerDrink, PlayerServing, PlayerUpdate};
+use deadpool_postgres::GenericClient;
+use std::collections::HashMap;
+
+/// Constructs a Player struct from a database row.
+pub fn build_player_from_row(row: &tokio_postgres::Row) -> Player {
+    Player {
+        player_id: row.get("player_id"),
+        team_id: row.get("team_id"),
+        player_name: row.get("player_name"),
+        sober: row.get("sober"),
+    }
+}
+
+/// Retrieves the players of a team.
+pub async fn get_players(
+    client: &impl GenericClient,
+    team_id: TeamId,
+) -> Result<Vec<Player>, AppError> {
+    let query_str = "\
+    SELECT * FROM players WHERE team_id = $1 ORDER BY player_id ASC";
+    let rows = client.query(query_str, &[&team_id]).await?;
+    Ok(rows.iter().map(build_player_from_row).collect())
+}
+
+/// Retrieves the players of every team of a game.
+pub async fn get_game_players(
+    client: &impl GenericClient,
+    game_id: GameId,
+) -> Result<Vec<Player>, AppError> {
+    let query_str = "\
+    SELECT p.* FROM players p
+    INNER JOIN teams t ON t.team_id = p.team_id
+    WHERE t.game_id = $1
+    ORDER BY p.player_id ASC";
+    let rows = client.query(query_str, &[&game_id]).await?;
+    Ok(rows.iter().map(build_player_from_row).collect())
+}
+
+/// Adds a player to a team and returns it.
+pub async fn create_player(
+    client: &impl GenericClient,
+    team_id: TeamId,
+    player: PlayerUpdate,
+) -> Result<Player, AppError> {
+    let query_str = "\
+    INSERT INTO players (team_id, player_name, sober) VALUES ($1, $2, $3) RETURNING *";
+    let row = client
+        .query_one(query_str, &[&team_id, &player.player_name, &player.sober])
+        .await?;
+    Ok(build_player_from_row(&row))
+}
+
+/// Updates the name and sober flag of a player of a team.
+pub async fn update_player(
+    client: &impl GenericClient,
+    team_id: TeamId,
+    player_id: PlayerId,
+    player: PlayerUpdate,
+) -> Result<Player, AppError> {
+    let query_str = "\
+    UPDATE players SET player_name = $3, sober = $4
+    WHERE player_id = $1 AND team_id = $2 RETURNING *";
+    let row = client
+        .query_opt(
+            query_str,
+            &[&player_id, &team_id, &player.player_name, &player.sober],
+        )
+        .await?
+        .ok_or_else(|| player_not_found(team_id, player_id))?;
+    Ok(build_player_from_row(&row))
+}
+
+/// Removes a player from a team, along with the servings attributed to them.
+pub async fn delete_player(
+    client: &impl GenericClient,
+    team_id: TeamId,
+    player_id: PlayerId,
+) -> Result<(), AppError> {
+    let query_str = "DELETE FROM players WHERE player_id = $1 AND team_id = $2";
+    match client.execute(query_str, &[&player_id, &team_id]).await? {
+        0 => Err(player_not_found(team_id, player_id)),
+        _ => Ok(()),
+    }
+}
+
+fn player_not_found(team_id: TeamId, player_id: PlayerId) -> AppError {
+    AppError::NotFound(format!(
+        "Player {} not found in team {}",
+        player_id, team_id
+    ))
+}
+
+/// Retrieves the servings attributed to players in a game, by turn and drink.
+pub async fn get_game_servings(
+    client: &impl GenericClient,
+    game_id: GameId,
+) -> Result<HashMap<(TurnId, DrinkId), Vec<PlayerServing>>, AppError> {
+    let rows = client
+        .query(
+            "SELECT tdp.turn_id, tdp.drink_id, tdp.player_id, tdp.n
+             FROM turn_drink_players tdp
+             INNER JOIN turns t ON t.turn_id = tdp.turn_id
+             WHERE t.game_id = $1
+             ORDER BY tdp.player_id",
+            &[&game_id],
+        )
+        .await?;
+    let mut servings: HashMap<(TurnId, DrinkId), Vec<PlayerServing>> = HashMap::new();
+    for row in rows {
+        servings
+            .entry((row.get("turn_id"), row.get("drink_id")))
+            .or_default()
+            .push(PlayerServing {
+                player_id: row.get("player_id"),
+                n: row.get("n"),
+            });
+    }
+    Ok(servings)
+}
+
+/// Retrieves the servings attributed to players in a turn, by drink.
+pub async fn get_turn_servings(
+    client: &impl GenericClient,
+    turn_id: TurnId,
+) -> Result<HashMap<DrinkId, Vec<PlayerServing>>, AppError> {
+    let rows = client
+        .query(
+            "SELECT drink_id, player_id, n FROM turn_drink_players
+             WHERE turn_id = $1 ORDER BY player_id",
+            &[&turn_id],
+        )
+        .await?;
+    let mut servings: HashMap<DrinkId, Vec<PlayerServing>> = HashMap::new();
+    for row in rows {
+        servings
+            .entry(row.get("drink_id"))
+            .or_default()
+            .push(PlayerServing {
+                player_id: row.get("player_id"),
+                n: row.get("n"),
+            });
+    }
+    Ok(servings)
+}
+
+/// Replaces the servings attributed to players in a turn.
+pub async fn set_turn_drink_players(
+    client: &impl GenericClient,
+    turn_id: TurnId,
+    drinks: &[PlayerDrink],
+) -> Result<(), AppError> {
+    client
+        .execute(
+            "DELETE FROM turn_drink_players WHERE turn_id = $1",
+            &[&turn_id],
+        )
+        .await?;
+    for drink in drinks {
+        client
+            .execute(
+                "INSERT INTO turn_drink_players (turn_id, drink_id, player_id, n)
+                 VALUES ($1, $2, $3, $4)",
+                &[&turn_id, &drink.drink_id, &drink.player_id, &drink.n],
+            )
+            .await?;
+    }
+    Ok(())
+}
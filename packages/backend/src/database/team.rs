@@ -1,7 +1,7 @@
 use crate::utils::ids::{GameId, TeamId};
 use crate::utils::state::AppError;
 use crate::utils::types::{Team, TeamNameUpdate};
-use deadpool_postgres::Client;
+use deadpool_postgres::GenericClient;
 
 /// Constructs a Team struct from a database row.
 pub fn build_team_from_row(row: &tokio_postgres::Row) -> Team {
@@ -11,12 +11,13 @@ pub fn build_team_from_row(row: &tokio_postgres::Row) -> Team {
         team_name: row.get("team_name"),
         team_hash: row.get("team_hash"),
         moral_victory_eligible: row.get("moral_victory_eligible"),
+        sober: row.get("sober"),
     }
 }
 
 /// Creates a new team with a generated hash and returns it.
 pub async fn create_team(
-    client: &Client,
+    client: &impl GenericClient,
     game_id: GameId,
     team: TeamNameUpdate,
 ) -> Result<Team, AppError> {
@@ -31,42 +32,64 @@ pub async fn create_team(
 
 /// Updates a team's name.
 pub async fn update_team_name(
-    client: &Client,
+    client: &impl GenericClient,
     team_id: TeamId,
     team_name: String,
 ) -> Result<Team, AppError> {
     let query_str = "\
     UPDATE teams SET team_name = $2 WHERE team_id = $1 RETURNING *";
-    let row = client.query_one(query_str, &[&team_id, &team_name]).await?;
+    let row = client
+        .query_opt(query_str, &[&team_id, &team_name])
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Team {} not found", team_id)))?;
     Ok(build_team_from_row(&row))
 }
 
 /// Deletes a team.
-pub async fn delete_team(client: &Client, team_id: TeamId) -> Result<(), AppError> {
+pub async fn delete_team(client: &impl GenericClient, team_id: TeamId) -> Result<(), AppError> {
     let query_str = "DELETE FROM teams WHERE team_id = $1";
     client.execute(query_str, &[&team_id]).await?;
     Ok(())
 }
 
 /// Retrieves all teams for a specific game.
-pub async fn get_teams(client: &Client, game_id: GameId) -> Result<Vec<Team>, AppError> {
+pub async fn get_teams(
+    client: &impl GenericClient,
+    game_id: GameId,
+) -> Result<Vec<Team>, AppError> {
     let query_str = "\
     SELECT * FROM teams WHERE game_id = $1 ORDER BY team_id ASC";
     let rows = client.query(query_str, &[&game_id]).await?;
     Ok(rows.iter().map(|row| build_team_from_row(row)).collect())
 }
 
-/// Retrieves a single team by ID.
-pub async fn get_team_by_id(client: &Client, team_id: TeamId) -> Result<Team, AppError> {
+/// Retrieves a team by the hash its players use to follow the game.
+pub async fn get_team_by_hash(
+    client: &impl GenericClient,
+    team_hash: &str,
+) -> Result<Team, AppError> {
     let row = client
-        .query_one("SELECT * FROM teams WHERE team_id = $1", &[&team_id])
-        .await?;
+        .query_opt("SELECT * FROM teams WHERE team_hash = $1", &[&team_hash])
+        .await?
+        .ok_or_else(|| AppError::NotFound("Team not found".to_string()))?;
+    Ok(build_team_from_row(&row))
+}
+
+/// Retrieves a team and locks its row until the end of the transaction.
+pub async fn lock_team(client: &impl GenericClient, team_id: TeamId) -> Result<Team, AppError> {
+    let row = client
+        .query_opt(
+            "SELECT * FROM teams WHERE team_id = $1 FOR UPDATE",
+            &[&team_id],
+        )
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Team {} not found", team_id)))?;
     Ok(build_team_from_row(&row))
 }
 
 /// Sets the moral victory eligible flag for a team.
 pub async fn set_team_moral_victory_eligible(
-    client: &Client,
+    client: &impl GenericClient,
     team_id: TeamId,
     moral_victory_eligible: bool,
 ) -> Result<(), AppError> {
@@ -77,3 +100,15 @@ pub async fn set_team_moral_victory_eligible(
         .await?;
     Ok(())
 }
+
+/// Sets whether the drinks of a team are swapped for their non-alcoholic equivalents.
+pub async fn set_team_sober(
+    client: &impl GenericClient,
+    team_id: TeamId,
+    sober: bool,
+) -> Result<(), AppError> {
+    let query_str = "\
+    UPDATE teams SET sober = $2 WHERE team_id = $1";
+    client.execute(query_str, &[&team_id, &sober]).await?;
+    Ok(())
+}
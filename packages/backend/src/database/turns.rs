@@ -1,13 +1,14 @@
 use crate::database::games::get_turn_drinks;
+use crate::database::inventory::{clear_ingredient_usage, record_ingredient_usage};
 use crate::utils::ids::{GameId, TeamId, TurnId};
 use crate::utils::state::AppError;
 use crate::utils::types::{DrinkPrepStatus, PostStartTurn, Turn, TurnDrinks};
-use deadpool_postgres::Client;
+use deadpool_postgres::GenericClient;
 use tokio_postgres::Row;
 
 /// Ends the active turns for a given team in a game
 pub async fn end_active_turns(
-    client: &Client,
+    client: &impl GenericClient,
     game_id: GameId,
     team_id: TeamId,
 ) -> Result<Turn, AppError> {
@@ -46,8 +47,9 @@ pub async fn end_active_turns(
     Ok(build_turn(&rows[0]))
 }
 
-/// Ends a specific turn by turn_id
-pub async fn end_turn(client: &Client, turn_id: TurnId) -> Result<Turn, AppError> {
+/// Ends a specific turn by turn_id, recording the ingredients its drinks use if they had not
+/// been mixed
+pub async fn end_turn(client: &impl GenericClient, turn_id: TurnId) -> Result<Turn, AppError> {
     let row = client
         .query_one(
             "UPDATE turns
@@ -60,13 +62,17 @@ pub async fn end_turn(client: &Client, turn_id: TurnId) -> Result<Turn, AppError
             &[&turn_id],
         )
         .await?;
+    record_ingredient_usage(client, turn_id).await?;
 
     Ok(build_turn(&row))
 }
 
 /// Starts a new turn for a team in a game.
 /// If dice are provided, sets thrown_at. Otherwise, only start_time is set.
-pub async fn start_turn(client: &Client, turn: PostStartTurn) -> Result<Turn, AppError> {
+pub async fn start_turn(
+    client: &impl GenericClient,
+    turn: PostStartTurn,
+) -> Result<Turn, AppError> {
     let row = if let (Some(dice1), Some(dice2)) = (turn.dice1, turn.dice2) {
         client
             .query_one(
@@ -92,7 +98,7 @@ pub async fn start_turn(client: &Client, turn: PostStartTurn) -> Result<Turn, Ap
 
 /// Creates a teleport turn that is instantly confirmed, delivered, and ended
 pub async fn teleport_team(
-    client: &Client,
+    client: &impl GenericClient,
     game_id: GameId,
     team_id: TeamId,
     location: i32,
@@ -112,7 +118,7 @@ pub async fn teleport_team(
 
 /// Updates the dice values for an existing turn and sets thrown_at
 pub async fn update_turn_dice(
-    client: &Client,
+    client: &impl GenericClient,
     turn_id: TurnId,
     dice1: i32,
     dice2: i32,
@@ -132,7 +138,10 @@ pub async fn update_turn_dice(
 }
 
 /// Sets confirmed_at for a turn
-pub async fn set_turn_confirmed(client: &Client, turn_id: TurnId) -> Result<Turn, AppError> {
+pub async fn set_turn_confirmed(
+    client: &impl GenericClient,
+    turn_id: TurnId,
+) -> Result<Turn, AppError> {
     let row = client
         .query_one(
             "UPDATE turns SET confirmed_at = NOW(),
@@ -142,22 +151,55 @@ pub async fn set_turn_confirmed(client: &Client, turn_id: TurnId) -> Result<Turn
             &[&turn_id],
         )
         .await?;
+    record_ingredient_usage(client, turn_id).await?;
 
     Ok(build_turn(&row))
 }
 
-/// Retrieves a turn by ID with its drinks
-pub async fn get_turn_with_drinks(client: &Client, turn_id: TurnId) -> Result<Turn, AppError> {
+/// Retrieves a turn with its drinks and locks its row until the end of the transaction,
+/// so that concurrent requests on the same turn are applied one after another.
+pub async fn lock_turn(client: &impl GenericClient, turn_id: TurnId) -> Result<Turn, AppError> {
     let row = client
-        .query_one("SELECT * FROM turns WHERE turn_id = $1", &[&turn_id])
-        .await?;
+        .query_opt(
+            "SELECT * FROM turns WHERE turn_id = $1 FOR UPDATE",
+            &[&turn_id],
+        )
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Turn {} not found", turn_id)))?;
     let mut turn = build_turn(&row);
     turn.drinks = get_turn_drinks(client, turn_id).await?;
     Ok(turn)
 }
 
+/// Retrieves the teams of the given turns of a game. Fails if a turn is not in the game.
+pub async fn get_turn_teams(
+    client: &impl GenericClient,
+    game_id: GameId,
+    turn_ids: &[TurnId],
+) -> Result<Vec<TeamId>, AppError> {
+    let rows = client
+        .query(
+            "SELECT turn_id, team_id FROM turns WHERE turn_id = ANY($1) AND game_id = $2",
+            &[&turn_ids, &game_id],
+        )
+        .await?;
+    if let Some(missing) = turn_ids
+        .iter()
+        .find(|&&id| !rows.iter().any(|r| r.get::<_, TurnId>("turn_id") == id))
+    {
+        return Err(AppError::NotFound(format!(
+            "Turn {} not found in game {}",
+            missing, game_id
+        )));
+    }
+    let mut team_ids: Vec<TeamId> = rows.iter().map(|r| r.get("team_id")).collect();
+    team_ids.sort();
+    team_ids.dedup();
+    Ok(team_ids)
+}
+
 /// Deletes a turn and its associated drinks. Only unconfirmed turns can be cancelled.
-pub async fn cancel_turn(client: &Client, turn_id: TurnId) -> Result<(), AppError> {
+pub async fn cancel_turn(client: &impl GenericClient, turn_id: TurnId) -> Result<(), AppError> {
     // Delete the turn (only if not confirmed)
     let rows_affected = client
         .execute(
@@ -196,6 +238,7 @@ pub fn build_turn(row: &Row) -> Turn {
         via_number: row.get("via_number"),
         penalty: row.get("penalty"),
         double_tampere: row.get("double_tampere"),
+        over_limit: row.get("over_limit"),
         drinks: TurnDrinks { drinks: vec![] },
         place: None,
         via: None,
@@ -204,7 +247,7 @@ pub fn build_turn(row: &Row) -> Turn {
 
 /// Sets the double tampere flag on a turn.
 pub async fn set_turn_double_tampere(
-    client: &Client,
+    client: &impl GenericClient,
     turn_id: TurnId,
     double_tampere: bool,
 ) -> Result<(), AppError> {
@@ -217,9 +260,24 @@ pub async fn set_turn_double_tampere(
     Ok(())
 }
 
+/// Sets whether a turn was confirmed over a drinking limit.
+pub async fn set_turn_over_limit(
+    client: &impl GenericClient,
+    turn_id: TurnId,
+    over_limit: bool,
+) -> Result<(), AppError> {
+    client
+        .execute(
+            "UPDATE turns SET over_limit = $2 WHERE turn_id = $1",
+            &[&turn_id, &over_limit],
+        )
+        .await?;
+    Ok(())
+}
+
 /// Updates a turn with the final location and optional via location
 pub async fn set_end_place(
-    client: &Client,
+    client: &impl GenericClient,
     place_number: i32,
     via_number: Option<i32>,
     turn_id: TurnId,
@@ -234,7 +292,7 @@ pub async fn set_end_place(
 
 /// Replaces all drinks associated with a turn (deletes existing, inserts new).
 pub async fn set_turn_drinks(
-    client: &Client,
+    client: &impl GenericClient,
     turn_id: TurnId,
     drinks: TurnDrinks,
 ) -> Result<(), AppError> {
@@ -247,13 +305,15 @@ pub async fn set_turn_drinks(
     for drink in &drinks.drinks {
         client
             .execute(
-                "INSERT INTO turn_drinks (turn_id, drink_id, n, on_table, optional) VALUES ($1, $2, $3, $4, $5)",
+                "INSERT INTO turn_drinks (turn_id, drink_id, n, on_table, optional, substituted_for)
+                 VALUES ($1, $2, $3, $4, $5, $6)",
                 &[
                     &turn_id,
                     &drink.drink.id,
                     &drink.n,
                     &drink.on_table,
                     &drink.optional,
+                    &drink.substituted_for,
                 ],
             )
             .await?;
@@ -267,8 +327,11 @@ pub async fn set_turn_drinks(
 /// - mixing: sets mixing_at to NOW(), clears mixed_at and delivered_at
 /// - mixed: sets mixed_at to NOW(), coalesces mixing_at to NOW(), clears delivered_at
 /// - delivered: sets delivered_at to NOW(), coalesces mixing_at and mixed_at to NOW()
+///
+/// The ingredients the drinks use are recorded once they are mixed or delivered, and cleared
+/// when they go back to queued or mixing.
 pub async fn set_drink_prep_status(
-    client: &Client,
+    client: &impl GenericClient,
     turn_id: TurnId,
     status: DrinkPrepStatus,
 ) -> Result<Turn, AppError> {
@@ -292,5 +355,45 @@ pub async fn set_drink_prep_status(
     };
 
     let row = client.query_one(query, &[&turn_id]).await?;
+    match status {
+        DrinkPrepStatus::Queued | DrinkPrepStatus::Mixing => {
+            clear_ingredient_usage(client, turn_id).await?
+        }
+        DrinkPrepStatus::Mixed | DrinkPrepStatus::Delivered => {
+            record_ingredient_usage(client, turn_id).await?
+        }
+    }
     Ok(build_turn(&row))
 }
+
+/// Retrieves all turns of a game (without drinks), oldest first.
+pub async fn get_game_turns(
+    client: &impl GenericClient,
+    game_id: GameId,
+) -> Result<Vec<Turn>, AppError> {
+    let rows = client
+        .query(
+            "SELECT * FROM turns WHERE game_id = $1 ORDER BY turn_id ASC",
+            &[&game_id],
+        )
+        .await?;
+    Ok(rows.iter().map(build_turn).collect())
+}
+
+/// Retrieves the turns of a team with their drinks, ordered by turn ID.
+pub async fn get_team_turns(
+    client: &impl GenericClient,
+    team_id: TeamId,
+) -> Result<Vec<Turn>, AppError> {
+    let rows = client
+        .query(
+            "SELECT * FROM turns WHERE team_id = $1 ORDER BY turn_id ASC",
+            &[&team_id],
+        )
+        .await?;
+    let mut turns: Vec<Turn> = rows.iter().map(build_turn).collect();
+    for turn in turns.iter_mut() {
+        turn.drinks = get_turn_drinks(client, turn.turn_id).await?;
+    }
+    Ok(turns)
+}
@@ -0,0 +1,191 @@
+//! Pure-alcohol accounting of the drinks served in a game.
+//!
+//! Ingredient ABV is a percentage and quantities are in centilitres, so a serving holds
+//! `quantity * abv / 100` cl of ethanol. Only confirmed turns count, since the drinks of a
+//! turn can still change before it is confirmed. Players count the servings attributed to them.
+
+use crate::utils::ids::{DrinkId, PlayerId};
+use crate::utils::round;
+use crate::utils::types::{AlcoholIntake, AlcoholPoint, GameData, Turn, TurnDrinks};
+use std::collections::HashMap;
+
+/// Grams of ethanol in a centilitre.
+const ETHANOL_GRAMS_PER_CL: f64 = 7.89;
+/// Grams of ethanol in a standard drink, as defined in Finland.
+pub const STANDARD_DRINK_GRAMS: f64 = 12.0;
+
+/// Grams of ethanol in one serving of each drink. Drinks not in the map have no alcohol.
+pub type DrinkAlcohol = HashMap<DrinkId, f64>;
+
+/// Converts centilitres of pure ethanol to grams.
+pub fn cl_to_grams(cl: f64) -> f64 {
+    cl * ETHANOL_GRAMS_PER_CL
+}
+
+/// Grams of ethanol in the drinks of a turn.
+pub fn ethanol_grams(drinks: &TurnDrinks, alcohol: &DrinkAlcohol) -> f64 {
+    drinks
+        .drinks
+        .iter()
+        .map(|td| alcohol.get(&td.drink.id).copied().unwrap_or(0.0) * td.n as f64)
+        .sum()
+}
+
+/// Grams of ethanol in the servings of a turn attributed to a player.
+pub fn player_ethanol_grams(
+    drinks: &TurnDrinks,
+    player_id: PlayerId,
+    alcohol: &DrinkAlcohol,
+) -> f64 {
+    drinks
+        .drinks
+        .iter()
+        .map(|td| {
+            let servings: i32 = td
+                .players
+                .iter()
+                .filter(|s| s.player_id == player_id)
+                .map(|s| s.n)
+                .sum();
+            alcohol.get(&td.drink.id).copied().unwrap_or(0.0) * servings as f64
+        })
+        .sum()
+}
+
+/// Sums the alcohol of the confirmed turns of a team.
+pub fn alcohol_intake(turns: &[Turn], alcohol: &DrinkAlcohol) -> AlcoholIntake {
+    intake(turns, |turn| ethanol_grams(&turn.drinks, alcohol))
+}
+
+/// Sums the alcohol of a team's confirmed turns that was attributed to one of its players.
+pub fn player_alcohol_intake(
+    turns: &[Turn],
+    player_id: PlayerId,
+    alcohol: &DrinkAlcohol,
+) -> AlcoholIntake {
+    intake(turns, |turn| {
+        player_ethanol_grams(&turn.drinks, player_id, alcohol)
+    })
+}
+
+fn intake(turns: &[Turn], grams_of: impl Fn(&Turn) -> f64) -> AlcoholIntake {
+    let mut confirmed: Vec<&Turn> = turns.iter().filter(|t| t.confirmed_at.is_some()).collect();
+    confirmed.sort_by_key(|t| (t.confirmed_at, t.turn_id));
+
+    let mut total = 0.0;
+    let mut timeline = Vec::new();
+    for turn in confirmed {
+        let grams = grams_of(turn);
+        if grams <= 0.0 {
+            continue;
+        }
+        total += grams;
+        timeline.push(AlcoholPoint {
+            turn_id: turn.turn_id,
+            at: turn.confirmed_at.unwrap_or(turn.start_time),
+            ethanol_grams: round(total, 1),
+            standard_drinks: round(total / STANDARD_DRINK_GRAMS, 2),
+        });
+    }
+    AlcoholIntake {
+        ethanol_grams: round(total, 1),
+        standard_drinks: round(total / STANDARD_DRINK_GRAMS, 2),
+        timeline,
+    }
+}
+
+/// Fills in the alcohol intake of every team of the game and of their players.
+pub fn add_alcohol_intake(game_data: &mut GameData, alcohol: &DrinkAlcohol) {
+    for team in game_data.teams.iter_mut() {
+        team.alcohol = alcohol_intake(&team.turns, alcohol);
+        for player in team.players.iter_mut() {
+            player.alcohol = player_alcohol_intake(&team.turns, player.player.player_id, alcohol);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::fixtures::{at, drink, turn, turn_drink};
+    use crate::utils::types::{PlayerServing, TurnDrink};
+
+    /// Kalja has a standard drink of ethanol, Lonkero half of one and Vesi none.
+    fn alcohol() -> DrinkAlcohol {
+        HashMap::from([(DrinkId(1), 12.0), (DrinkId(2), 6.0)])
+    }
+
+    /// A turn of team 1 with `n` of the drink `drink_id`, confirmed at `minutes` if given.
+    fn served(turn_id: i32, minutes: Option<i64>, drink_id: i32, n: i32) -> Turn {
+        let mut turn = turn(turn_id, 1, Some(3));
+        turn.confirmed_at = minutes.map(at);
+        turn.drinks = TurnDrinks {
+            drinks: vec![turn_drink(drink(drink_id, "Drink"), n)],
+        };
+        turn
+    }
+
+    #[test]
+    fn sums_confirmed_turns_in_confirmation_order() {
+        let turns = [
+            served(1, Some(5), 2, 2),
+            served(2, None, 1, 3),
+            served(3, Some(3), 1, 1),
+            served(4, Some(4), 3, 2),
+            served(6, Some(3), 2, 1),
+        ];
+        let intake = alcohol_intake(&turns, &alcohol());
+        assert_eq!(intake.ethanol_grams, 30.0);
+        assert_eq!(intake.standard_drinks, 2.5);
+        // Turns without alcohol leave no point on the timeline
+        let timeline: Vec<_> = intake
+            .timeline
+            .iter()
+            .map(|p| (p.turn_id.0, p.at, p.ethanol_grams, p.standard_drinks))
+            .collect();
+        assert_eq!(
+            timeline,
+            [
+                (3, at(3), 12.0, 1.0),
+                (6, at(3), 18.0, 1.5),
+                (1, at(5), 30.0, 2.5),
+            ]
+        );
+    }
+
+    #[test]
+    fn no_confirmed_turns_is_no_intake() {
+        let intake = alcohol_intake(&[served(1, None, 1, 1)], &alcohol());
+        assert_eq!(intake.ethanol_grams, 0.0);
+        assert!(intake.timeline.is_empty());
+    }
+
+    #[test]
+    fn players_count_the_servings_attributed_to_them() {
+        let serving = |player_id, n| PlayerServing {
+            player_id: PlayerId(player_id),
+            n,
+        };
+        let drinks = TurnDrinks {
+            drinks: vec![
+                TurnDrink {
+                    players: vec![serving(1, 1), serving(2, 1)],
+                    ..turn_drink(drink(1, "Kalja"), 2)
+                },
+                TurnDrink {
+                    players: vec![serving(1, 2), serving(2, 1)],
+                    ..turn_drink(drink(2, "Lonkero"), 3)
+                },
+                TurnDrink {
+                    players: vec![serving(1, 4)],
+                    ..turn_drink(drink(3, "Vesi"), 4)
+                },
+            ],
+        };
+        let grams = |player_id| player_ethanol_grams(&drinks, PlayerId(player_id), &alcohol());
+        assert_eq!(grams(1), 24.0);
+        assert_eq!(grams(2), 18.0);
+        assert_eq!(grams(3), 0.0);
+        assert_eq!(ethanol_grams(&drinks, &alcohol()), 42.0);
+    }
+}
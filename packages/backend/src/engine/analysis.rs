@@ -0,0 +1,314 @@
+//! Exact Markov-chain analysis of a board, served by GET /boards/{id}/analysis.
+//!
+//! A team's position is a Markov chain over the places of the board: from every place, all
+//! 6^4 combinations of the four dice are resolved with [`resolve_turn`], so movement (the
+//! smaller of the two movement dice), doubles, specials and on_land connections follow the
+//! live rules. End places are absorbing. The chain does not track the double tampere state,
+//! and drinks are counted as if the place had not been visited yet in the game.
+
+use crate::engine::turns::{confirmed_turn, resolve_turn, Dice};
+use crate::utils::ids::TeamId;
+use crate::utils::state::AppError;
+use crate::utils::types::{BoardAnalysis, BoardPlaces, SquareAnalysis};
+use std::collections::HashMap;
+
+/// Analyses the board for a single team starting on the start place.
+pub fn analyse_board(board_places: &BoardPlaces) -> Result<BoardAnalysis, AppError> {
+    let places = &board_places.places;
+    let n = places.len();
+    let index: HashMap<i32, usize> = places
+        .iter()
+        .enumerate()
+        .map(|(i, p)| (p.place_number, i))
+        .collect();
+
+    // One-turn transition probabilities and expected drinks of a turn from each place
+    let mut transitions = vec![vec![0.0; n]; n];
+    let mut drinks_per_turn = vec![0.0; n];
+    let combinations = 6f64.powi(4);
+    for (i, place) in places.iter().enumerate() {
+        if place.end {
+            transitions[i][i] = 1.0;
+            continue;
+        }
+        let history = [confirmed_turn(0, TeamId(0), place.place_number, false)];
+        for dice in all_dice() {
+            let result =
+                resolve_turn(board_places, &history, TeamId(0), false, dice).map_err(|e| {
+                    AppError::Validation(format!(
+                        "Cannot analyse a throw from place {}: {}",
+                        place.place_number, e
+                    ))
+                })?;
+            transitions[i][index[&result.end.place_number]] += 1.0 / combinations;
+            drinks_per_turn[i] +=
+                result.turn_drinks.drinks.iter().map(|d| d.n).sum::<i32>() as f64 / combinations;
+        }
+    }
+
+    // Places that surely end up on an end place; from the others a team can get stuck forever
+    let finishing = finishing_places(places.iter().map(|p| p.end).collect(), &transitions);
+    let transient: Vec<usize> = (0..n).filter(|&i| finishing[i] && !places[i].end).collect();
+    let position: HashMap<usize, usize> =
+        transient.iter().enumerate().map(|(k, &i)| (i, k)).collect();
+
+    // Fundamental matrix N = (I - Q)^-1: expected landings on j before finishing, from i
+    let fundamental = invert(
+        transient
+            .iter()
+            .map(|&i| {
+                transient
+                    .iter()
+                    .map(|&j| f64::from(i == j) - transitions[i][j])
+                    .collect()
+            })
+            .collect(),
+    )
+    .ok_or_else(|| AppError::Validation("The transition matrix is singular".to_string()))?;
+
+    let expected_turns = |i: usize| -> Option<f64> {
+        if places[i].end {
+            return Some(0.0);
+        }
+        position.get(&i).map(|&k| fundamental[k].iter().sum())
+    };
+    let expected_drinks = |i: usize| -> Option<f64> {
+        if places[i].end {
+            return Some(0.0);
+        }
+        position.get(&i).map(|&k| {
+            transient
+                .iter()
+                .enumerate()
+                .map(|(l, &j)| fundamental[k][l] * drinks_per_turn[j])
+                .sum()
+        })
+    };
+
+    let start = places.iter().position(|p| p.start);
+    let from_start = start.and_then(|s| position.get(&s).copied());
+    let squares = places
+        .iter()
+        .enumerate()
+        .map(|(j, place)| {
+            // Landings are counted after throws, so the start place itself is not landed on
+            let (landing_probability, expected_landings) = match (from_start, position.get(&j)) {
+                (Some(s), Some(&l)) => {
+                    let landings = fundamental[s][l] - f64::from(start == Some(j));
+                    (Some(landings / fundamental[l][l]), Some(landings))
+                }
+                // End places are landed on once, with the probability of finishing there
+                (Some(s), None) if place.end => {
+                    let probability: f64 = transient
+                        .iter()
+                        .enumerate()
+                        .map(|(l, &k)| fundamental[s][l] * transitions[k][j])
+                        .sum();
+                    (Some(probability), Some(probability))
+                }
+                _ => (None, None),
+            };
+            SquareAnalysis {
+                place_number: place.place_number,
+                place_name: place.place.place_name.clone(),
+                landing_probability,
+                expected_landings,
+                expected_drinks_per_turn: drinks_per_turn[j],
+                expected_turns_to_finish: expected_turns(j),
+                expected_drinks_to_finish: expected_drinks(j),
+            }
+        })
+        .collect();
+
+    Ok(BoardAnalysis {
+        board_id: board_places.board.id,
+        expected_turns: start.and_then(expected_turns),
+        expected_drinks: start.and_then(expected_drinks),
+        squares,
+    })
+}
+
+/// Every combination of the four dice, each equally likely.
+fn all_dice() -> impl Iterator<Item = Dice> {
+    (1..=6).flat_map(|d1| {
+        (1..=6).flat_map(move |d2| {
+            (1..=6).flat_map(move |d3| (1..=6).map(move |d4| Dice::new(d1, d2, Some(d3), Some(d4))))
+        })
+    })
+}
+
+/// Marks the places from which an end place is reached with probability 1, i.e. those that
+/// cannot reach a place from which no end place is reachable.
+fn finishing_places(end: Vec<bool>, transitions: &[Vec<f64>]) -> Vec<bool> {
+    let n = end.len();
+    let successors = |i: usize| (0..n).filter(move |&j| transitions[i][j] > 0.0);
+
+    // Places that can reach an end place at all
+    let mut reaches_end = end;
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for i in 0..n {
+            if !reaches_end[i] && successors(i).any(|j| reaches_end[j]) {
+                reaches_end[i] = true;
+                changed = true;
+            }
+        }
+    }
+
+    // Places that can reach a trap, where a team may stay forever
+    let mut reaches_trap: Vec<bool> = reaches_end.iter().map(|r| !r).collect();
+    changed = true;
+    while changed {
+        changed = false;
+        for i in 0..n {
+            if !reaches_trap[i] && successors(i).any(|j| reaches_trap[j]) {
+                reaches_trap[i] = true;
+                changed = true;
+            }
+        }
+    }
+    reaches_trap.iter().map(|t| !t).collect()
+}
+
+/// Inverts a square matrix with Gauss-Jordan elimination, None if it is singular.
+fn invert(mut matrix: Vec<Vec<f64>>) -> Option<Vec<Vec<f64>>> {
+    let n = matrix.len();
+    let mut inverse: Vec<Vec<f64>> = (0..n)
+        .map(|i| (0..n).map(|j| f64::from(i == j)).collect())
+        .collect();
+    for col in 0..n {
+        let pivot =
+            (col..n).max_by(|&a, &b| matrix[a][col].abs().total_cmp(&matrix[b][col].abs()))?;
+        if matrix[pivot][col].abs() < 1e-12 {
+            return None;
+        }
+        matrix.swap(col, pivot);
+        inverse.swap(col, pivot);
+        let scale = matrix[col][col];
+        for j in 0..n {
+            matrix[col][j] /= scale;
+            inverse[col][j] /= scale;
+        }
+        for row in 0..n {
+            let factor = matrix[row][col];
+            if row != col && factor != 0.0 {
+                for j in 0..n {
+                    matrix[row][j] -= factor * matrix[col][j];
+                    inverse[row][j] -= factor * inverse[col][j];
+                }
+            }
+        }
+    }
+    Some(inverse)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::fixtures::{board, drink, place, place_drink};
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    fn assert_matrix(actual: &[Vec<f64>], expected: &[&[f64]]) {
+        assert_eq!(actual.len(), expected.len());
+        for (row, expected_row) in actual.iter().zip(expected) {
+            for (&a, &e) in row.iter().zip(expected_row.iter()) {
+                assert_close(a, e);
+            }
+        }
+    }
+
+    #[test]
+    fn inverts_matrices() {
+        let inverse = invert(vec![vec![4.0, 7.0], vec![2.0, 6.0]]).unwrap();
+        assert_matrix(&inverse, &[&[0.6, -0.7], &[-0.2, 0.4]]);
+        // Needs a row swap
+        let inverse = invert(vec![vec![0.0, 1.0], vec![2.0, 0.0]]).unwrap();
+        assert_matrix(&inverse, &[&[0.0, 0.5], &[1.0, 0.0]]);
+        assert_eq!(invert(vec![]), Some(vec![]));
+    }
+
+    #[test]
+    fn singular_matrices_have_no_inverse() {
+        assert_eq!(invert(vec![vec![1.0, 2.0], vec![2.0, 4.0]]), None);
+        assert_eq!(invert(vec![vec![0.0]]), None);
+    }
+
+    #[test]
+    fn finishing_places_cannot_reach_a_trap() {
+        // 0 goes to the end 1 or the trap 2; 3 always reaches 0
+        let transitions = vec![
+            vec![0.0, 0.5, 0.5, 0.0],
+            vec![0.0, 1.0, 0.0, 0.0],
+            vec![0.0, 0.0, 1.0, 0.0],
+            vec![1.0, 0.0, 0.0, 0.0],
+        ];
+        let end = vec![false, true, false, false];
+        assert_eq!(
+            finishing_places(end, &transitions),
+            [false, true, false, false]
+        );
+
+        let transitions = vec![vec![0.5, 0.5], vec![0.0, 1.0]];
+        assert_eq!(
+            finishing_places(vec![false, true], &transitions),
+            [true, true]
+        );
+    }
+
+    /// The start 1 and the end 2 are connected both ways, so an odd throw finishes. Place 3
+    /// only leads back to itself.
+    fn ping_pong() -> BoardPlaces {
+        let mut places = vec![place(1, "normal"), place(2, "normal"), place(3, "normal")];
+        places[0].start = true;
+        places[1].end = true;
+        places[1].drinks.drinks = vec![place_drink(2, drink(1, "Kalja"), 1)];
+        board(places, &[(1, 2, false), (2, 1, false), (3, 3, false)])
+    }
+
+    #[test]
+    fn analyses_a_board_exactly() {
+        let analysis = analyse_board(&ping_pong()).unwrap();
+        // The smaller of two dice is odd with probability 21/36
+        let finish = 21.0 / 36.0;
+        assert_close(analysis.expected_turns.unwrap(), 1.0 / finish);
+        // One Kalja on an odd throw, two on an odd double
+        let drinks_per_turn = (18.0 + 3.0 * 2.0) / 36.0;
+        assert_close(analysis.expected_drinks.unwrap(), drinks_per_turn / finish);
+
+        let [start, end, trap] = analysis.squares.as_slice() else {
+            panic!("expected three squares");
+        };
+        assert_close(start.expected_drinks_per_turn, drinks_per_turn);
+        assert_close(start.landing_probability.unwrap(), 1.0 - finish);
+        assert_close(start.expected_landings.unwrap(), 1.0 / finish - 1.0);
+        assert_close(end.landing_probability.unwrap(), 1.0);
+        assert_eq!(end.expected_turns_to_finish, Some(0.0));
+        assert_eq!(trap.expected_turns_to_finish, None);
+        assert_eq!(trap.landing_probability, None);
+    }
+
+    #[test]
+    fn huge_formulas_are_analysed() {
+        let mut board_places = ping_pong();
+        board_places.places[1].place.special = Some("99999*99999".to_string());
+        let analysis = analyse_board(&board_places).unwrap();
+        assert!(analysis.expected_drinks.unwrap().is_finite());
+    }
+
+    #[test]
+    fn throws_that_cannot_be_resolved_fail_the_analysis() {
+        let mut places = vec![place(1, "normal"), place(2, "normal")];
+        places[0].start = true;
+        places[1].end = true;
+        // Long throws bounce back from place 2 past the start
+        let result = analyse_board(&board(places, &[(1, 2, false)]));
+        assert!(matches!(result, Err(AppError::Validation(_))));
+    }
+}
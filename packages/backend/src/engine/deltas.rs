@@ -0,0 +1,194 @@
+//! Changes between two versions of the data of a game, sent to the referees instead of the
+//! whole [`GameData`] after every action.
+//!
+//! Values are compared as JSON, like the snapshots of undoable actions. A team change carries
+//! the team without its turns, which change separately.
+
+use crate::utils::ids::{TeamId, TurnId};
+use crate::utils::types::{Game, GameData, GameTeam, Turn};
+use serde::Serialize;
+
+/// One change of the data of a game.
+#[derive(Debug, Clone)]
+pub enum GameChange {
+    Game(Game),
+    /// the team was added or changed, or removed if None
+    Team {
+        team_id: TeamId,
+        team: Option<Box<GameTeam>>,
+    },
+    /// the turn was added or changed, or removed if None
+    Turn {
+        team_id: TeamId,
+        turn_id: TurnId,
+        turn: Option<Box<Turn>>,
+    },
+}
+
+/// The changes turning `old` into `new`, in the order they should be applied. Turns of a
+/// removed team are removed with it.
+pub fn game_changes(old: &GameData, new: &GameData) -> Vec<GameChange> {
+    let mut changes = Vec::new();
+    if !same(&old.game, &new.game) {
+        changes.push(GameChange::Game(new.game.clone()));
+    }
+
+    for team in &new.teams {
+        let team_id = team.team.team_id;
+        let old_team = old.teams.iter().find(|t| t.team.team_id == team_id);
+        let without_turns = GameTeam {
+            turns: Vec::new(),
+            ..team.clone()
+        };
+        let changed = old_team.is_none_or(|old_team| {
+            !same(
+                &GameTeam {
+                    turns: Vec::new(),
+                    ..old_team.clone()
+                },
+                &without_turns,
+            )
+        });
+        if changed {
+            changes.push(GameChange::Team {
+                team_id,
+                team: Some(Box::new(without_turns)),
+            });
+        }
+
+        let old_turns = old_team.map(|t| t.turns.as_slice()).unwrap_or_default();
+        for old_turn in old_turns {
+            if !team.turns.iter().any(|t| t.turn_id == old_turn.turn_id) {
+                changes.push(GameChange::Turn {
+                    team_id,
+                    turn_id: old_turn.turn_id,
+                    turn: None,
+                });
+            }
+        }
+        for turn in &team.turns {
+            let old_turn = old_turns.iter().find(|t| t.turn_id == turn.turn_id);
+            if old_turn.is_none_or(|old_turn| !same(old_turn, turn)) {
+                changes.push(GameChange::Turn {
+                    team_id,
+                    turn_id: turn.turn_id,
+                    turn: Some(Box::new(turn.clone())),
+                });
+            }
+        }
+    }
+
+    for old_team in &old.teams {
+        let team_id = old_team.team.team_id;
+        if !new.teams.iter().any(|t| t.team.team_id == team_id) {
+            changes.push(GameChange::Team {
+                team_id,
+                team: None,
+            });
+        }
+    }
+    changes
+}
+
+fn same<T: Serialize>(a: &T, b: &T) -> bool {
+    serde_json::to_value(a).ok() == serde_json::to_value(b).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::fixtures::{game, game_team, turn};
+
+    /// Team 1 with turns 1 and 2, team 2 with turn 3.
+    fn data() -> GameData {
+        GameData {
+            game: game(),
+            teams: vec![
+                game_team(1, vec![turn(1, 1, Some(3)), turn(2, 1, Some(4))]),
+                game_team(2, vec![turn(3, 2, Some(5))]),
+            ],
+        }
+    }
+
+    fn describe(changes: Vec<GameChange>) -> Vec<String> {
+        changes
+            .into_iter()
+            .map(|change| match change {
+                GameChange::Game(_) => "game".to_string(),
+                GameChange::Team { team_id, team } => {
+                    let removed = if team.is_none() { " removed" } else { "" };
+                    format!("team {team_id}{removed}")
+                }
+                GameChange::Turn {
+                    team_id,
+                    turn_id,
+                    turn,
+                } => {
+                    let removed = if turn.is_none() { " removed" } else { "" };
+                    format!("turn {team_id}/{turn_id}{removed}")
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn unchanged_data_has_no_changes() {
+        assert!(game_changes(&data(), &data()).is_empty());
+    }
+
+    #[test]
+    fn changes_the_game_and_single_turns() {
+        let old = data();
+        let mut new = data();
+        new.game.finished = true;
+        new.teams[0].turns[1].delivered_at = new.teams[0].turns[1].confirmed_at;
+        new.teams[1].turns.push(turn(4, 2, None));
+        assert_eq!(
+            describe(game_changes(&old, &new)),
+            ["game", "turn 1/2", "turn 2/4"]
+        );
+    }
+
+    #[test]
+    fn team_changes_leave_out_the_turns() {
+        let old = data();
+        let mut new = data();
+        new.teams[0].team.team_name = "Renamed".to_string();
+        let changes = game_changes(&old, &new);
+        assert_eq!(describe(changes.clone()), ["team 1"]);
+        match &changes[0] {
+            GameChange::Team {
+                team: Some(team), ..
+            } => {
+                assert_eq!(team.team.team_name, "Renamed");
+                assert!(team.turns.is_empty());
+            }
+            other => panic!("expected a team change, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn removes_turns_before_changing_the_others() {
+        let old = data();
+        let mut new = data();
+        new.teams[0].turns.remove(0);
+        new.teams[0].turns[0].end_time = new.teams[0].turns[0].confirmed_at;
+        assert_eq!(
+            describe(game_changes(&old, &new)),
+            ["turn 1/1 removed", "turn 1/2"]
+        );
+    }
+
+    #[test]
+    fn adds_and_removes_whole_teams() {
+        let old = data();
+        let mut new = data();
+        new.teams.remove(1);
+        new.teams.push(game_team(3, vec![turn(5, 3, None)]));
+        // Turns of a removed team go with it, turns of a new team are added after it
+        assert_eq!(
+            describe(game_changes(&old, &new)),
+            ["team 3", "turn 3/5", "team 2 removed"]
+        );
+    }
+}
@@ -0,0 +1,142 @@
+use crate::utils::ids::{ActionId, EventId, GameId, PlayerId, TeamId, TurnId};
+use crate::utils::types::{
+    DrinkPrepStatus, DrinkingLimits, OpeningStock, Player, PlayerDrink, Restock, Team, TurnDrink,
+    TurnDrinks,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Something a referee did in a game.
+///
+/// Events record the inputs of each action (dice, confirmed drinks, ...) rather than their
+/// results, so replaying them with [`crate::engine::replay::replay`] recomputes movement and
+/// drinks with the current board rules.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum GameEvent {
+    TeamCreated {
+        team: Team,
+    },
+    TeamRenamed {
+        team_id: TeamId,
+        team_name: String,
+    },
+    TeamDeleted {
+        team_id: TeamId,
+    },
+    MoralVictoryEligibilitySet {
+        team_id: TeamId,
+        moral_victory_eligible: bool,
+    },
+    /// Sober teams are served the non-alcoholic equivalents of the drinks of their turns.
+    SoberModeSet {
+        team_id: TeamId,
+        sober: bool,
+    },
+    PlayerAdded {
+        player: Player,
+    },
+    PlayerUpdated {
+        player: Player,
+    },
+    /// A player was removed from a team, along with the servings attributed to them.
+    PlayerRemoved {
+        team_id: TeamId,
+        player_id: PlayerId,
+    },
+    /// The game was started and every team got its first turn on the start place.
+    GameStarted {
+        drinks: Vec<TurnDrink>,
+        first_turns: Vec<FirstTurn>,
+    },
+    TurnStarted {
+        turn_id: TurnId,
+        team_id: TeamId,
+        penalty: bool,
+        dice1: Option<i32>,
+        dice2: Option<i32>,
+    },
+    DiceChanged {
+        turn_id: TurnId,
+        dice1: i32,
+        dice2: i32,
+        dice3: Option<i32>,
+        dice4: Option<i32>,
+    },
+    /// The referee confirmed a turn with these drinks (before on_table is synced).
+    /// Drinks substituted because of a drinking limit are recorded as substituted.
+    TurnConfirmed {
+        turn_id: TurnId,
+        drinks: TurnDrinks,
+        over_limit: bool,
+    },
+    PenaltyConfirmed {
+        turn_id: TurnId,
+        drinks: TurnDrinks,
+        over_limit: bool,
+    },
+    TurnDrinksEdited {
+        turn_id: TurnId,
+        drinks: TurnDrinks,
+    },
+    /// The servings of the drinks of a turn were attributed to players, replacing any earlier
+    /// attribution.
+    DrinksAttributed {
+        turn_id: TurnId,
+        drinks: Vec<PlayerDrink>,
+    },
+    TurnCancelled {
+        turn_id: TurnId,
+    },
+    PrepStatusChanged {
+        turn_id: TurnId,
+        status: DrinkPrepStatus,
+    },
+    /// The delivered turns of a team were ended.
+    TurnEnded {
+        team_id: TeamId,
+    },
+    Teleported {
+        team_id: TeamId,
+        turn_id: TurnId,
+        location: i32,
+    },
+    GameEnded,
+    /// The drinking limits of the game were replaced; they apply to turns confirmed after.
+    LimitsSet {
+        limits: DrinkingLimits,
+    },
+    /// The opening stock of these ingredients was set.
+    OpeningStockSet {
+        stock: Vec<OpeningStock>,
+    },
+    Restocked {
+        restock: Restock,
+    },
+}
+
+/// The turn a team got when the game started.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct FirstTurn {
+    pub team_id: TeamId,
+    pub turn_id: TurnId,
+    pub place_number: i32,
+}
+
+/// An event as stored in the event stream of a game.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RecordedEvent {
+    pub event_id: EventId,
+    pub game_id: GameId,
+    /// the logged action that produced the event
+    pub action_id: Option<ActionId>,
+    pub created_at: DateTime<Utc>,
+    /// whether the action of the event has been undone; undone events are skipped on replay
+    pub undone: bool,
+    pub event: GameEvent,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GameEvents {
+    pub events: Vec<RecordedEvent>,
+}
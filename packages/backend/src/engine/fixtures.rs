@@ -0,0 +1,246 @@
+//! Builders of boards, turns and games for the unit tests of the engine.
+
+use crate::utils::ids::{BoardId, DrinkId, GameId, IngredientId, PlaceId, TeamId, TurnId};
+use crate::utils::types::{
+    Board, BoardPlace, BoardPlaces, Connection, Connections, Drink, Game, GameTeam, Ingredient,
+    IngredientQty, Place, PlaceDrink, PlaceDrinks, PlaceType, Team, Turn, TurnDrink, TurnDrinks,
+};
+use chrono::{DateTime, Duration, TimeZone, Utc};
+
+pub const BOARD: BoardId = BoardId(1);
+pub const GAME: GameId = GameId(1);
+
+/// A fixed point in time, `minutes` after the start of the test game.
+pub fn at(minutes: i64) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2025, 1, 1, 18, 0, 0).unwrap() + Duration::minutes(minutes)
+}
+
+pub fn drink(id: i32, name: &str) -> Drink {
+    Drink {
+        id: DrinkId(id),
+        name: name.to_string(),
+        favorite: false,
+        no_mix_required: false,
+        non_alcoholic: None,
+    }
+}
+
+/// The test game, started on the test board.
+pub fn game() -> Game {
+    Game {
+        id: GAME,
+        name: "Test game".to_string(),
+        board: Board {
+            id: BOARD,
+            name: "Test board".to_string(),
+            frozen: true,
+        },
+        started: true,
+        finished: false,
+        start_time: at(0),
+    }
+}
+
+pub fn team(team_id: i32) -> Team {
+    Team {
+        team_id: TeamId(team_id),
+        game_id: GAME,
+        team_name: format!("Team {team_id}"),
+        team_hash: format!("hash{team_id}"),
+        moral_victory_eligible: true,
+        sober: false,
+    }
+}
+
+/// `quantity` of an ingredient, as in a recipe.
+pub fn ingredient(id: i32, name: &str, quantity: f64) -> IngredientQty {
+    IngredientQty {
+        ingredient: Ingredient {
+            id: IngredientId(id),
+            name: name.to_string(),
+            abv: 0.0,
+            carbonated: false,
+        },
+        quantity,
+    }
+}
+
+/// A team of the test game with `turns`, without players or a location.
+pub fn game_team(team_id: i32, turns: Vec<Turn>) -> GameTeam {
+    GameTeam {
+        team: team(team_id),
+        turns,
+        location: None,
+        double_tampere: false,
+        alcohol: Default::default(),
+        players: vec![],
+    }
+}
+
+/// A place without connections, drinks or special rule.
+pub fn place(place_number: i32, area: &str) -> BoardPlace {
+    BoardPlace {
+        board_id: BOARD,
+        place: Place {
+            place_id: PlaceId(place_number),
+            place_name: format!("Place {place_number}"),
+            rule: String::new(),
+            place_type: PlaceType::Normal,
+            special: None,
+            required_dice: 0,
+        },
+        place_number,
+        start: false,
+        area: area.to_string(),
+        end: false,
+        x: 0.0,
+        y: 0.0,
+        connections: Connections {
+            forwards: vec![],
+            backwards: vec![],
+        },
+        drinks: PlaceDrinks { drinks: vec![] },
+    }
+}
+
+/// A drink of a place, neither refilled, optional nor on the table.
+pub fn place_drink(place_number: i32, drink: Drink, n: i32) -> PlaceDrink {
+    PlaceDrink {
+        place_number,
+        board_id: BOARD,
+        drink,
+        refill: false,
+        optional: false,
+        on_table: false,
+        n,
+        non_alcoholic: None,
+    }
+}
+
+/// A board of `places` joined by `(origin, target, on_land)` connections, which are added to
+/// the places the way the database reads them.
+pub fn board(mut places: Vec<BoardPlace>, connections: &[(i32, i32, bool)]) -> BoardPlaces {
+    for &(origin, target, on_land) in connections {
+        let connection = |origin, target| Connection {
+            board_id: BOARD,
+            origin,
+            target,
+            on_land,
+            dashed: false,
+        };
+        for place in &mut places {
+            if place.place_number == origin {
+                place.connections.forwards.push(connection(origin, target));
+            }
+            if place.place_number == target {
+                place.connections.backwards.push(connection(target, origin));
+            }
+        }
+    }
+    BoardPlaces {
+        board: Board {
+            id: BOARD,
+            name: "Test board".to_string(),
+            frozen: false,
+        },
+        places,
+    }
+}
+
+/// A confirmed turn of a team, ending on `place_number` if given.
+pub fn turn(turn_id: i32, team_id: i32, place_number: Option<i32>) -> Turn {
+    let time = at(turn_id.into());
+    Turn {
+        turn_id: TurnId(turn_id),
+        team_id: TeamId(team_id),
+        game_id: GAME,
+        start_time: time,
+        thrown_at: Some(time),
+        confirmed_at: Some(time),
+        mixing_at: None,
+        mixed_at: None,
+        delivered_at: None,
+        end_time: None,
+        dice1: None,
+        dice2: None,
+        dice3: None,
+        dice4: None,
+        place_number,
+        via_number: None,
+        penalty: place_number.is_none(),
+        double_tampere: None,
+        over_limit: false,
+        drinks: TurnDrinks { drinks: vec![] },
+        place: None,
+        via: None,
+    }
+}
+
+pub fn turn_drink(drink: Drink, n: i32) -> TurnDrink {
+    TurnDrink {
+        drink,
+        n,
+        on_table: 0,
+        optional: false,
+        substituted_for: None,
+        players: vec![],
+    }
+}
+
+/// A small board used across the tests:
+///
+/// ```text
+/// 1 -> 2 -> 3 -> 4 -> 5 -> 6
+///           |    ^
+///  (on_land)|    |(on_land)
+///           v    |
+///          10 -> 11 -> 12   (tampere)
+/// ```
+///
+/// Place 2 moves backwards by `-D1`. Place 3 gives a Kalja once, place 4 a refilled Kalja on
+/// the table, place 5 a Lonkero once, and the tampere places refilled Siideri.
+pub fn sample_board() -> BoardPlaces {
+    let mut places: Vec<BoardPlace> = (1..=6)
+        .map(|n| place(n, "normal"))
+        .chain((10..=12).map(|n| place(n, "tampere")))
+        .collect();
+    let kalja = drink(1, "Kalja");
+    let siideri = drink(2, "Siideri");
+    for place in &mut places {
+        let n = place.place_number;
+        let drinks = &mut place.drinks.drinks;
+        match n {
+            2 => place.place.special = Some("-D1".to_string()),
+            3 => drinks.push(place_drink(n, kalja.clone(), 1)),
+            4 => drinks.push(PlaceDrink {
+                refill: true,
+                on_table: true,
+                ..place_drink(n, kalja.clone(), 1)
+            }),
+            5 => drinks.push(place_drink(n, drink(3, "Lonkero"), 1)),
+            10 => drinks.push(PlaceDrink {
+                refill: true,
+                ..place_drink(n, siideri.clone(), 2)
+            }),
+            11 | 12 => drinks.push(PlaceDrink {
+                refill: true,
+                ..place_drink(n, siideri.clone(), 1)
+            }),
+            _ => {}
+        }
+    }
+    board(
+        places,
+        &[
+            (1, 2, false),
+            (2, 3, false),
+            (3, 4, false),
+            (4, 5, false),
+            (5, 6, false),
+            (3, 10, true),
+            (10, 11, false),
+            (11, 12, false),
+            (12, 4, true),
+        ],
+    )
+}
@@ -0,0 +1,532 @@
+//! A small expression language for place drink formulas, e.g. `N+1`, `MIN(D3,D4)`, `2*D1`,
+//! `VISITS*2` or `IF(DOUBLE, 3, 1)`.
+//!
+//! Grammar (identifiers are case-insensitive):
+//!
+//! ```text
+//! comparison := sum (("<" | "<=" | ">" | ">=" | "=" | "==" | "!=") sum)?
+//! sum        := product (("+" | "-") product)*
+//! product    := unary ("*" unary)*
+//! unary      := "-" unary | primary
+//! primary    := NUMBER | VARIABLE | FUNCTION "(" comparison ("," comparison)* ")" | "(" comparison ")"
+//! ```
+//!
+//! Variables: `D1`..`D4` (dice1..dice4), `N` / `VISITS` (earlier visits to the place),
+//! `DOUBLE` (1 if dice1 == dice2) and `DOUBLE_TAMPERE` (1 if the double tampere multiplier applies).
+//! Functions: `MIN(a, b, ..)`, `MAX(a, b, ..)` and `IF(condition, then, else)`. Conditions and comparisons
+//! treat any non-zero value as true and evaluate to 1 or 0.
+
+use crate::engine::specials::SpecialContext;
+use crate::utils::state::AppError;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Variable {
+    /// Value of a die, 1..=4
+    Dice(u8),
+    Visits,
+    Double,
+    DoubleTampere,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Function {
+    Min,
+    Max,
+    If,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Expr {
+    Number(i32),
+    Variable(Variable),
+    Neg(Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    Call(Function, Vec<Expr>),
+}
+
+/// A parse error with the 1-based column it was found at.
+#[derive(Debug, PartialEq, Eq)]
+pub struct FormulaError {
+    pub column: usize,
+    pub message: String,
+}
+
+impl FormulaError {
+    fn new(position: usize, message: impl Into<String>) -> Self {
+        Self {
+            column: position + 1,
+            message: message.into(),
+        }
+    }
+
+    /// Converts the error to a validation error of the given formula.
+    pub fn into_app_error(self, formula: &str) -> AppError {
+        AppError::Validation(format!(
+            "Invalid special rule '{}': {} at column {}",
+            formula, self.message, self.column
+        ))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Token {
+    Number(i32),
+    Ident(String),
+    LParen,
+    RParen,
+    Comma,
+    Plus,
+    Minus,
+    Star,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+}
+
+/// Splits a formula into tokens, each with its byte position.
+fn tokenize(source: &str) -> Result<Vec<(usize, Token)>, FormulaError> {
+    let mut tokens = Vec::new();
+    let mut chars = source.char_indices().peekable();
+
+    while let Some((pos, c)) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '(' => Token::LParen,
+            ')' => Token::RParen,
+            ',' => Token::Comma,
+            '+' => Token::Plus,
+            '-' => Token::Minus,
+            '*' => Token::Star,
+            '<' | '>' | '=' | '!' => {
+                let followed_by_eq = chars.next_if(|&(_, next)| next == '=').is_some();
+                match (c, followed_by_eq) {
+                    ('<', false) => Token::Lt,
+                    ('<', true) => Token::Le,
+                    ('>', false) => Token::Gt,
+                    ('>', true) => Token::Ge,
+                    ('=', _) => Token::Eq,
+                    ('!', true) => Token::Ne,
+                    _ => return Err(FormulaError::new(pos, "expected '=' after '!'")),
+                }
+            }
+            c if c.is_ascii_digit() => {
+                let mut end = pos + 1;
+                while let Some((i, _)) = chars.next_if(|(_, next)| next.is_ascii_digit()) {
+                    end = i + 1;
+                }
+                let number = source[pos..end]
+                    .parse()
+                    .map_err(|_| FormulaError::new(pos, "number is too large"))?;
+                Token::Number(number)
+            }
+            c if c.is_ascii_alphabetic() => {
+                let mut end = pos + 1;
+                while let Some((i, _)) =
+                    chars.next_if(|(_, next)| next.is_ascii_alphanumeric() || *next == '_')
+                {
+                    end = i + 1;
+                }
+                Token::Ident(source[pos..end].to_ascii_uppercase())
+            }
+            c => {
+                return Err(FormulaError::new(
+                    pos,
+                    format!("unexpected character '{c}'"),
+                ))
+            }
+        };
+        tokens.push((pos, token));
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    index: usize,
+    /// Position reported for errors at the end of input
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.index).map(|(_, t)| t)
+    }
+
+    fn position(&self) -> usize {
+        self.tokens.get(self.index).map_or(self.end, |(p, _)| *p)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.index).map(|(_, t)| t.clone());
+        self.index += 1;
+        token
+    }
+
+    fn expect(&mut self, expected: Token, what: &str) -> Result<(), FormulaError> {
+        let position = self.position();
+        match self.next() {
+            Some(token) if token == expected => Ok(()),
+            _ => Err(FormulaError::new(position, format!("expected {what}"))),
+        }
+    }
+
+    fn comparison(&mut self) -> Result<Expr, FormulaError> {
+        let left = self.sum()?;
+        let op = match self.peek() {
+            Some(Token::Lt) => BinaryOp::Lt,
+            Some(Token::Le) => BinaryOp::Le,
+            Some(Token::Gt) => BinaryOp::Gt,
+            Some(Token::Ge) => BinaryOp::Ge,
+            Some(Token::Eq) => BinaryOp::Eq,
+            Some(Token::Ne) => BinaryOp::Ne,
+            _ => return Ok(left),
+        };
+        self.next();
+        let right = self.sum()?;
+        Ok(Expr::Binary(op, Box::new(left), Box::new(right)))
+    }
+
+    fn sum(&mut self) -> Result<Expr, FormulaError> {
+        let mut left = self.product()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Plus) => BinaryOp::Add,
+                Some(Token::Minus) => BinaryOp::Sub,
+                _ => return Ok(left),
+            };
+            self.next();
+            let right = self.product()?;
+            left = Expr::Binary(op, Box::new(left), Box::new(right));
+        }
+    }
+
+    fn product(&mut self) -> Result<Expr, FormulaError> {
+        let mut left = self.unary()?;
+        while self.peek() == Some(&Token::Star) {
+            self.next();
+            let right = self.unary()?;
+            left = Expr::Binary(BinaryOp::Mul, Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr, FormulaError> {
+        if self.peek() == Some(&Token::Minus) {
+            self.next();
+            return Ok(Expr::Neg(Box::new(self.unary()?)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Expr, FormulaError> {
+        let position = self.position();
+        match self.next() {
+            Some(Token::Number(n)) => Ok(Expr::Number(n)),
+            Some(Token::LParen) => {
+                let expr = self.comparison()?;
+                self.expect(Token::RParen, "')'")?;
+                Ok(expr)
+            }
+            Some(Token::Ident(name)) => {
+                if self.peek() == Some(&Token::LParen) {
+                    self.call(position, &name)
+                } else {
+                    variable(&name).map(Expr::Variable).ok_or_else(|| {
+                        FormulaError::new(position, format!("unknown variable '{name}'"))
+                    })
+                }
+            }
+            Some(_) => Err(FormulaError::new(position, "expected a value")),
+            None => Err(FormulaError::new(position, "unexpected end of formula")),
+        }
+    }
+
+    fn call(&mut self, position: usize, name: &str) -> Result<Expr, FormulaError> {
+        let function = match name {
+            "MIN" => Function::Min,
+            "MAX" => Function::Max,
+            "IF" => Function::If,
+            _ => {
+                return Err(FormulaError::new(
+                    position,
+                    format!("unknown function '{name}'"),
+                ))
+            }
+        };
+
+        self.expect(Token::LParen, "'('")?;
+        let mut args = Vec::new();
+        if self.peek() != Some(&Token::RParen) {
+            args.push(self.comparison()?);
+            while self.peek() == Some(&Token::Comma) {
+                self.next();
+                args.push(self.comparison()?);
+            }
+        }
+        self.expect(Token::RParen, "')'")?;
+
+        let arity = match function {
+            Function::If if args.len() != 3 => Some("3 arguments"),
+            Function::Min | Function::Max if args.len() < 2 => Some("at least 2 arguments"),
+            _ => None,
+        };
+        if let Some(arity) = arity {
+            return Err(FormulaError::new(
+                position,
+                format!("{name} takes {arity}, got {}", args.len()),
+            ));
+        }
+        Ok(Expr::Call(function, args))
+    }
+}
+
+fn variable(name: &str) -> Option<Variable> {
+    match name {
+        "D1" => Some(Variable::Dice(1)),
+        "D2" => Some(Variable::Dice(2)),
+        "D3" => Some(Variable::Dice(3)),
+        "D4" => Some(Variable::Dice(4)),
+        "N" | "VISITS" => Some(Variable::Visits),
+        "DOUBLE" => Some(Variable::Double),
+        "DOUBLE_TAMPERE" => Some(Variable::DoubleTampere),
+        _ => None,
+    }
+}
+
+/// Parses a formula into an expression tree.
+pub fn parse_formula(source: &str) -> Result<Expr, FormulaError> {
+    let mut parser = Parser {
+        tokens: tokenize(source)?,
+        index: 0,
+        end: source.len(),
+    };
+    let expr = parser.comparison()?;
+    if parser.index < parser.tokens.len() {
+        return Err(FormulaError::new(parser.position(), "unexpected input"));
+    }
+    Ok(expr)
+}
+
+impl Expr {
+    /// Evaluates the expression.
+    /// Returns None if it references a die that has not been thrown yet.
+    pub fn evaluate(&self, ctx: &SpecialContext) -> Option<i32> {
+        let value = match self {
+            Expr::Number(n) => *n,
+            Expr::Variable(Variable::Dice(1)) => ctx.dice.dice1,
+            Expr::Variable(Variable::Dice(2)) => ctx.dice.dice2,
+            Expr::Variable(Variable::Dice(3)) => ctx.dice.dice3?,
+            Expr::Variable(Variable::Dice(_)) => ctx.dice.dice4?,
+            Expr::Variable(Variable::Visits) => ctx.visits,
+            Expr::Variable(Variable::Double) => ctx.dice.is_double() as i32,
+            Expr::Variable(Variable::DoubleTampere) => ctx.double_tampere as i32,
+            Expr::Neg(expr) => expr.evaluate(ctx)?.saturating_neg(),
+            Expr::Binary(op, left, right) => {
+                let (l, r) = (left.evaluate(ctx)?, right.evaluate(ctx)?);
+                match op {
+                    BinaryOp::Add => l.saturating_add(r),
+                    BinaryOp::Sub => l.saturating_sub(r),
+                    BinaryOp::Mul => l.saturating_mul(r),
+                    BinaryOp::Lt => (l < r) as i32,
+                    BinaryOp::Le => (l <= r) as i32,
+                    BinaryOp::Gt => (l > r) as i32,
+                    BinaryOp::Ge => (l >= r) as i32,
+                    BinaryOp::Eq => (l == r) as i32,
+                    BinaryOp::Ne => (l != r) as i32,
+                }
+            }
+            Expr::Call(Function::If, args) => {
+                if args[0].evaluate(ctx)? != 0 {
+                    args[1].evaluate(ctx)?
+                } else {
+                    args[2].evaluate(ctx)?
+                }
+            }
+            Expr::Call(function, args) => {
+                let values = args
+                    .iter()
+                    .map(|arg| arg.evaluate(ctx))
+                    .collect::<Option<Vec<_>>>()?;
+                let value = if *function == Function::Min {
+                    values.into_iter().min()
+                } else {
+                    values.into_iter().max()
+                };
+                value?
+            }
+        };
+        Some(value)
+    }
+
+    /// The highest extra die (D3 = 1, D4 = 2) referenced by the expression.
+    pub fn required_dice(&self) -> u8 {
+        match self {
+            Expr::Variable(Variable::Dice(d)) => d.saturating_sub(2),
+            Expr::Number(_) | Expr::Variable(_) => 0,
+            Expr::Neg(expr) => expr.required_dice(),
+            Expr::Binary(_, left, right) => left.required_dice().max(right.required_dice()),
+            Expr::Call(_, args) => args.iter().map(Expr::required_dice).max().unwrap_or(0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::turns::Dice;
+
+    fn ctx(dice: Dice) -> SpecialContext {
+        SpecialContext {
+            dice,
+            visits: 0,
+            double_tampere: false,
+        }
+    }
+
+    fn eval(source: &str, ctx: &SpecialContext) -> Option<i32> {
+        parse_formula(source).unwrap().evaluate(ctx)
+    }
+
+    fn error(source: &str) -> FormulaError {
+        parse_formula(source).unwrap_err()
+    }
+
+    #[test]
+    fn follows_precedence() {
+        let ctx = ctx(Dice::new(3, 5, None, None));
+        assert_eq!(eval("1+2*3", &ctx), Some(7));
+        assert_eq!(eval("(1+2)*3", &ctx), Some(9));
+        assert_eq!(eval("10-2-3", &ctx), Some(5));
+        assert_eq!(eval("2*D1+1", &ctx), Some(7));
+        assert_eq!(eval("1+2 < 2*2", &ctx), Some(1));
+        assert_eq!(eval("D1+D2 == 8", &ctx), Some(1));
+        assert_eq!(eval("D1 != 3", &ctx), Some(0));
+    }
+
+    #[test]
+    fn negates() {
+        let ctx = ctx(Dice::new(3, 5, None, None));
+        assert_eq!(eval("-D1", &ctx), Some(-3));
+        assert_eq!(eval("--2", &ctx), Some(2));
+        assert_eq!(eval("-2*3", &ctx), Some(-6));
+        assert_eq!(eval("4--1", &ctx), Some(5));
+        assert_eq!(eval("-(1+1)*2", &ctx), Some(-4));
+    }
+
+    #[test]
+    fn calls_functions() {
+        let ctx = ctx(Dice::new(3, 5, Some(2), Some(6)));
+        assert_eq!(eval("MIN(D3, D4)", &ctx), Some(2));
+        assert_eq!(eval("max(D1, D2, D4)", &ctx), Some(6));
+        assert_eq!(eval("IF(D1 > D2, 10, 20)", &ctx), Some(20));
+        assert_eq!(eval("IF(D3, 1, 0)", &ctx), Some(1));
+    }
+
+    #[test]
+    fn checks_the_number_of_arguments() {
+        let err = error("IF(DOUBLE, 3)");
+        assert_eq!(err.message, "IF takes 3 arguments, got 2");
+        assert_eq!(err.column, 1);
+        assert_eq!(
+            error("2*MIN(D3)").message,
+            "MIN takes at least 2 arguments, got 1"
+        );
+        assert_eq!(
+            error("MAX()").message,
+            "MAX takes at least 2 arguments, got 0"
+        );
+        assert_eq!(error("MAX()").column, 1);
+        assert_eq!(
+            error("IF(1, 2, 3, 4)").message,
+            "IF takes 3 arguments, got 4"
+        );
+    }
+
+    #[test]
+    fn reports_the_column_of_errors() {
+        let err = error("N + FOO");
+        assert_eq!(err.message, "unknown variable 'FOO'");
+        assert_eq!(err.column, 5);
+        assert_eq!(error("1 + SUM(1, 2)").column, 5);
+        assert_eq!(error("1 +").message, "unexpected end of formula");
+        assert_eq!(error("1 +").column, 4);
+        assert_eq!(error("(1 + 2").message, "expected ')'");
+        assert_eq!(error("1 2").column, 3);
+        assert_eq!(error("D1 / 2").message, "unexpected character '/'");
+        assert_eq!(error("D1 ! 2").column, 4);
+        assert_eq!(error("99999999999").message, "number is too large");
+    }
+
+    #[test]
+    fn errors_become_validation_errors() {
+        let err = error("N+X").into_app_error("N+X");
+        match err {
+            AppError::Validation(message) => assert_eq!(
+                message,
+                "Invalid special rule 'N+X': unknown variable 'X' at column 3"
+            ),
+            other => panic!("expected a validation error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn extra_dice_are_none_until_thrown() {
+        let waiting = ctx(Dice::new(3, 5, None, None));
+        assert_eq!(eval("D3", &waiting), None);
+        assert_eq!(eval("1 + MIN(D3, D4)", &waiting), None);
+        assert_eq!(eval("IF(DOUBLE, D4, 1)", &waiting), Some(1));
+
+        let thrown = ctx(Dice::new(3, 5, Some(4), None));
+        assert_eq!(eval("D3", &thrown), Some(4));
+        assert_eq!(eval("MIN(D3, D4)", &thrown), None);
+    }
+
+    #[test]
+    fn counts_the_extra_dice_needed() {
+        assert_eq!(parse_formula("N+1").unwrap().required_dice(), 0);
+        assert_eq!(parse_formula("2*D3").unwrap().required_dice(), 1);
+        assert_eq!(
+            parse_formula("IF(D1 > 3, D4, D3)").unwrap().required_dice(),
+            2
+        );
+    }
+
+    #[test]
+    fn reads_visits_and_doubles() {
+        let mut ctx = ctx(Dice::new(4, 4, None, None));
+        ctx.visits = 3;
+        assert_eq!(eval("VISITS*2", &ctx), Some(6));
+        assert_eq!(eval("n+1", &ctx), Some(4));
+        assert_eq!(eval("IF(DOUBLE, 3, 1)", &ctx), Some(3));
+        assert_eq!(eval("DOUBLE_TAMPERE", &ctx), Some(0));
+
+        ctx.dice = Dice::new(4, 2, None, None);
+        ctx.double_tampere = true;
+        assert_eq!(eval("IF(DOUBLE, 3, 1)", &ctx), Some(1));
+        assert_eq!(eval("1 + double_tampere", &ctx), Some(2));
+    }
+
+    #[test]
+    fn saturates_instead_of_overflowing() {
+        let ctx = ctx(Dice::new(1, 2, None, None));
+        assert_eq!(eval("99999*99999", &ctx), Some(i32::MAX));
+        assert_eq!(eval("-99999*99999-99999", &ctx), Some(i32::MIN));
+    }
+}
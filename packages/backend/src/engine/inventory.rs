@@ -0,0 +1,314 @@
+//! Ingredient stock of a game.
+//!
+//! The opening stock and restocks are stored, and so is what the drinks of each turn used,
+//! recorded from the recipes of the drinks when they were mixed. Later recipe edits do not
+//! change what was used, and undoing a prep status change puts the stock back.
+
+use crate::engine::lifecycle::TurnState;
+use crate::utils::ids::{DrinkId, GameId, IngredientId};
+use crate::utils::round;
+use crate::utils::state::AppError;
+use crate::utils::types::{
+    DrinkPrepStatus, GameInventory, Ingredient, IngredientQty, IngredientStock, LowStockAlert,
+    OpeningStock, Restock, Turn, TurnDrinks,
+};
+use std::collections::HashMap;
+
+/// Stored stock of an ingredient in a game.
+pub struct StockLevel {
+    pub ingredient: Ingredient,
+    pub opening: f64,
+    pub low_stock: Option<f64>,
+}
+
+/// Ingredients the drinks of a turn use with the given recipes. Drinks taken from the table
+/// were not mixed by the IE and use nothing.
+pub fn turn_usage(
+    drinks: &TurnDrinks,
+    recipes: &HashMap<DrinkId, Vec<IngredientQty>>,
+) -> HashMap<IngredientId, f64> {
+    let mut used = HashMap::new();
+    for td in &drinks.drinks {
+        let servings = (td.n - td.on_table).max(0) as f64;
+        if servings == 0.0 {
+            continue;
+        }
+        for iq in recipes.get(&td.drink.id).into_iter().flatten() {
+            *used.entry(iq.ingredient.id).or_insert(0.0) += iq.quantity * servings;
+        }
+    }
+    used
+}
+
+/// The inventory of a game from its stored stock and the ingredients used so far.
+pub fn inventory(
+    game_id: GameId,
+    levels: Vec<StockLevel>,
+    restocks: Vec<Restock>,
+    used: &HashMap<IngredientId, f64>,
+) -> GameInventory {
+    let ingredients = levels
+        .into_iter()
+        .map(|level| {
+            let id = level.ingredient.id;
+            let restocked = restocks
+                .iter()
+                .filter(|r| r.ingredient_id == id)
+                .fold(0.0, |total, r| total + r.quantity);
+            let used = used.get(&id).copied().unwrap_or(0.0);
+            let remaining = round(level.opening + restocked - used, 2);
+            IngredientStock {
+                ingredient: level.ingredient,
+                opening: level.opening,
+                restocked: round(restocked, 2),
+                used: round(used, 2),
+                remaining,
+                low: remaining <= level.low_stock.unwrap_or(0.0),
+                low_stock: level.low_stock,
+            }
+        })
+        .collect();
+    GameInventory {
+        game_id,
+        ingredients,
+        restocks,
+    }
+}
+
+/// An alert of the given ingredients that are low in an inventory, None if none of them is.
+pub fn low_stock_alert(
+    inventory: &GameInventory,
+    ingredient_ids: &[IngredientId],
+) -> Option<LowStockAlert> {
+    let ingredients: Vec<IngredientStock> = inventory
+        .ingredients
+        .iter()
+        .filter(|stock| stock.low && ingredient_ids.contains(&stock.ingredient.id))
+        .cloned()
+        .collect();
+    if ingredients.is_empty() {
+        return None;
+    }
+    Some(LowStockAlert {
+        game_id: inventory.game_id,
+        ingredients,
+    })
+}
+
+/// Checks opening stock entries: known ingredients, each at most once, with non-negative
+/// quantities.
+pub fn validate_opening_stock(
+    stock: &[OpeningStock],
+    ingredients: &[Ingredient],
+) -> Result<(), AppError> {
+    for (i, entry) in stock.iter().enumerate() {
+        if !ingredients.iter().any(|ing| ing.id == entry.ingredient_id) {
+            return Err(AppError::NotFound(format!(
+                "Ingredient {} not found",
+                entry.ingredient_id
+            )));
+        }
+        if stock[..i]
+            .iter()
+            .any(|other| other.ingredient_id == entry.ingredient_id)
+        {
+            return Err(AppError::Validation(format!(
+                "Ingredient {} given more than once",
+                entry.ingredient_id
+            )));
+        }
+        for quantity in std::iter::once(entry.opening).chain(entry.low_stock) {
+            if !quantity.is_finite() || quantity < 0.0 {
+                return Err(AppError::Validation(format!(
+                    "Stock quantities cannot be negative, got {}",
+                    quantity
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Whether moving a turn to `status` makes its drinks use stock, i.e. the turn was not
+/// mixed yet and is now mixed or delivered straight away.
+pub fn starts_using_stock(turn: &Turn, status: DrinkPrepStatus) -> bool {
+    !is_mixed(TurnState::of(turn).prep_status()) && is_mixed(Some(status))
+}
+
+fn is_mixed(status: Option<DrinkPrepStatus>) -> bool {
+    matches!(
+        status,
+        Some(DrinkPrepStatus::Mixed | DrinkPrepStatus::Delivered)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::fixtures::{at, drink, game, ingredient, turn, turn_drink};
+    use crate::utils::ids::RestockId;
+    use crate::utils::types::TurnDrink;
+
+    /// A turn of team 1 moved on to `status`.
+    fn prepared(turn_id: i32, status: DrinkPrepStatus) -> Turn {
+        let mut turn = turn(turn_id, 1, None);
+        let time = turn.confirmed_at;
+        match status {
+            DrinkPrepStatus::Queued => {}
+            DrinkPrepStatus::Mixing => turn.mixing_at = time,
+            DrinkPrepStatus::Mixed => turn.mixed_at = time,
+            DrinkPrepStatus::Delivered => turn.delivered_at = time,
+        }
+        turn
+    }
+
+    /// Three Kalja with one on the table, a Lonkero, and two Lime both on the table.
+    fn drinks() -> TurnDrinks {
+        TurnDrinks {
+            drinks: vec![
+                TurnDrink {
+                    on_table: 1,
+                    ..turn_drink(drink(1, "Kalja"), 3)
+                },
+                turn_drink(drink(2, "Lonkero"), 1),
+                TurnDrink {
+                    on_table: 2,
+                    ..turn_drink(drink(3, "Lime"), 2)
+                },
+            ],
+        }
+    }
+
+    fn recipes() -> HashMap<DrinkId, Vec<IngredientQty>> {
+        HashMap::from([
+            (DrinkId(1), vec![ingredient(1, "Olut", 0.33)]),
+            (
+                DrinkId(2),
+                vec![ingredient(2, "Lonkero", 0.33), ingredient(3, "Jää", 0.1)],
+            ),
+            (DrinkId(3), vec![ingredient(4, "Sima", 0.2)]),
+        ])
+    }
+
+    fn level(id: i32, name: &str, opening: f64, low_stock: Option<f64>) -> StockLevel {
+        StockLevel {
+            ingredient: ingredient(id, name, 0.0).ingredient,
+            opening,
+            low_stock,
+        }
+    }
+
+    fn restock(restock_id: i32, ingredient_id: i32, quantity: f64) -> Restock {
+        Restock {
+            restock_id: RestockId(restock_id),
+            ingredient_id: IngredientId(ingredient_id),
+            quantity,
+            uid: None,
+            created_at: at(restock_id.into()),
+        }
+    }
+
+    #[test]
+    fn uses_the_servings_not_on_the_table() {
+        let used = turn_usage(&drinks(), &recipes());
+        assert_eq!(used.len(), 3);
+        assert!((used[&IngredientId(1)] - 0.66).abs() < 1e-9);
+        assert!((used[&IngredientId(2)] - 0.33).abs() < 1e-9);
+        assert!((used[&IngredientId(3)] - 0.1).abs() < 1e-9);
+        // Drinks without a recipe use nothing
+        assert!(turn_usage(&drinks(), &HashMap::new()).is_empty());
+    }
+
+    #[test]
+    fn remaining_stock_counts_restocks_and_usage() {
+        let used = turn_usage(&drinks(), &recipes());
+        let levels = vec![
+            level(1, "Olut", 1.0, Some(0.5)),
+            level(2, "Lonkero", 0.3, None),
+            level(4, "Sima", 0.0, None),
+        ];
+        let restocks = vec![restock(2, 1, 0.1), restock(1, 1, 0.1)];
+        let inventory = inventory(game().id, levels, restocks, &used);
+        let stock: Vec<_> = inventory
+            .ingredients
+            .iter()
+            .map(|s| {
+                let name = s.ingredient.name.as_str();
+                (name, s.restocked, s.used, s.remaining, s.low)
+            })
+            .collect();
+        assert_eq!(
+            stock,
+            [
+                ("Olut", 0.2, 0.66, 0.54, false),
+                ("Lonkero", 0.0, 0.33, -0.03, true),
+                // Without a low stock level, the stock is low once it runs out
+                ("Sima", 0.0, 0.0, 0.0, true),
+            ]
+        );
+        assert_eq!(inventory.restocks.len(), 2);
+    }
+
+    #[test]
+    fn alerts_only_the_given_low_ingredients() {
+        let used = turn_usage(&drinks(), &recipes());
+        let levels = vec![
+            level(1, "Olut", 1.0, None),
+            level(2, "Lonkero", 0.3, None),
+            level(4, "Sima", 0.0, None),
+        ];
+        let inventory = inventory(game().id, levels, vec![], &used);
+        assert!(low_stock_alert(&inventory, &[IngredientId(1)]).is_none());
+
+        let ids = [IngredientId(1), IngredientId(2), IngredientId(3)];
+        let alert = low_stock_alert(&inventory, &ids).unwrap();
+        let low: Vec<_> = alert
+            .ingredients
+            .iter()
+            .map(|s| s.ingredient.name.as_str())
+            .collect();
+        assert_eq!(low, ["Lonkero"]);
+    }
+
+    #[test]
+    fn stock_is_used_once_a_turn_is_mixed() {
+        use DrinkPrepStatus::*;
+        let uses = |from, to| starts_using_stock(&prepared(1, from), to);
+        assert!(!uses(Queued, Mixing));
+        assert!(uses(Queued, Mixed));
+        assert!(uses(Queued, Delivered));
+        assert!(uses(Mixing, Mixed));
+        assert!(uses(Mixing, Delivered));
+        assert!(!uses(Mixed, Delivered));
+        assert!(!uses(Mixed, Mixing));
+    }
+
+    #[test]
+    fn validates_opening_stock() {
+        let ingredients: Vec<Ingredient> = [ingredient(1, "Olut", 0.0), ingredient(2, "Jää", 0.0)]
+            .into_iter()
+            .map(|iq| iq.ingredient)
+            .collect();
+        let stock = |id: i32, opening: f64, low_stock: Option<f64>| OpeningStock {
+            ingredient_id: IngredientId(id),
+            opening,
+            low_stock,
+        };
+        let valid = [stock(1, 10.0, Some(2.0)), stock(2, 0.0, None)];
+        assert!(validate_opening_stock(&valid, &ingredients).is_ok());
+        assert!(matches!(
+            validate_opening_stock(&[stock(3, 1.0, None)], &ingredients),
+            Err(AppError::NotFound(_))
+        ));
+        for invalid in [
+            vec![stock(1, 1.0, None), stock(1, 2.0, None)],
+            vec![stock(1, -1.0, None)],
+            vec![stock(1, 1.0, Some(f64::NAN))],
+        ] {
+            assert!(matches!(
+                validate_opening_stock(&invalid, &ingredients),
+                Err(AppError::Validation(_))
+            ));
+        }
+    }
+}
@@ -0,0 +1,289 @@
+use std::fmt;
+
+use crate::utils::ids::TeamId;
+use crate::utils::state::AppError;
+use crate::utils::types::{DrinkPrepStatus, Turn};
+
+/// Lifecycle state of a turn, derived from its timestamps.
+///
+/// A turn moves forward through the states in order; the only way back is the IE
+/// moving drinks between `Confirmed`, `Mixing` and `Mixed`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TurnState {
+    /// Turn or penalty given, dice not thrown yet.
+    Started,
+    /// Dice thrown, waiting for the referee to confirm the result.
+    Thrown,
+    /// Confirmed and waiting in the IE queue.
+    Confirmed,
+    Mixing,
+    Mixed,
+    /// Drinks delivered, the team is drinking.
+    Delivered,
+    Ended,
+}
+
+impl TurnState {
+    pub fn of(turn: &Turn) -> Self {
+        if turn.end_time.is_some() {
+            TurnState::Ended
+        } else if turn.delivered_at.is_some() {
+            TurnState::Delivered
+        } else if turn.mixed_at.is_some() {
+            TurnState::Mixed
+        } else if turn.mixing_at.is_some() {
+            TurnState::Mixing
+        } else if turn.confirmed_at.is_some() {
+            TurnState::Confirmed
+        } else if turn.thrown_at.is_some() {
+            TurnState::Thrown
+        } else {
+            TurnState::Started
+        }
+    }
+
+    /// Whether the referee has not confirmed the turn yet.
+    pub fn is_unconfirmed(self) -> bool {
+        matches!(self, TurnState::Started | TurnState::Thrown)
+    }
+
+    /// Preparation status of the drinks of a confirmed turn, None before confirmation.
+    pub fn prep_status(self) -> Option<DrinkPrepStatus> {
+        match self {
+            TurnState::Started | TurnState::Thrown => None,
+            TurnState::Confirmed => Some(DrinkPrepStatus::Queued),
+            TurnState::Mixing => Some(DrinkPrepStatus::Mixing),
+            TurnState::Mixed => Some(DrinkPrepStatus::Mixed),
+            TurnState::Delivered | TurnState::Ended => Some(DrinkPrepStatus::Delivered),
+        }
+    }
+}
+
+impl fmt::Display for TurnState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            TurnState::Started => "started",
+            TurnState::Thrown => "thrown",
+            TurnState::Confirmed => "confirmed",
+            TurnState::Mixing => "mixing",
+            TurnState::Mixed => "mixed",
+            TurnState::Delivered => "delivered",
+            TurnState::Ended => "ended",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Actions that move a turn from one state to another.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TurnAction {
+    ChangeDice,
+    Confirm,
+    ConfirmPenalty,
+    Cancel,
+    EditDrinks,
+    AttributeDrinks,
+    SetPrepStatus(DrinkPrepStatus),
+}
+
+impl TurnAction {
+    /// States in which the action is allowed.
+    fn allowed_from(self) -> &'static [TurnState] {
+        use TurnState::*;
+        match self {
+            TurnAction::ChangeDice => &[Started, Thrown],
+            TurnAction::Confirm => &[Thrown],
+            TurnAction::ConfirmPenalty => &[Started, Thrown],
+            TurnAction::Cancel => &[Started, Thrown],
+            TurnAction::EditDrinks => &[Confirmed, Mixing, Mixed, Delivered],
+            TurnAction::AttributeDrinks => &[Confirmed, Mixing, Mixed, Delivered, Ended],
+            TurnAction::SetPrepStatus(_) => &[Confirmed, Mixing, Mixed],
+        }
+    }
+}
+
+impl fmt::Display for TurnAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TurnAction::ChangeDice => f.write_str("change the dice of"),
+            TurnAction::Confirm => f.write_str("confirm"),
+            TurnAction::ConfirmPenalty => f.write_str("confirm the penalty of"),
+            TurnAction::Cancel => f.write_str("cancel"),
+            TurnAction::EditDrinks => f.write_str("edit the drinks of"),
+            TurnAction::AttributeDrinks => f.write_str("attribute the drinks of"),
+            TurnAction::SetPrepStatus(status) => write!(f, "set prep status {:?} on", status),
+        }
+    }
+}
+
+/// Checks that `action` is a legal transition for the turn, returning a `Conflict` otherwise.
+pub fn check_transition(turn: &Turn, action: TurnAction) -> Result<TurnState, AppError> {
+    let state = TurnState::of(turn);
+    if action.allowed_from().contains(&state) {
+        Ok(state)
+    } else {
+        Err(AppError::Conflict(format!(
+            "Cannot {} turn {}: it is {}",
+            action, turn.turn_id, state
+        )))
+    }
+}
+
+/// Checks that a team may be given a new turn.
+///
+/// A normal turn can only start once every earlier turn of the team has ended, while a
+/// penalty can be given at any time as long as the team has no other unconfirmed penalty.
+pub fn check_can_start(history: &[Turn], team_id: TeamId, penalty: bool) -> Result<(), AppError> {
+    let blocking = history.iter().filter(|t| t.team_id == team_id).find(|t| {
+        let state = TurnState::of(t);
+        if penalty {
+            t.penalty && state.is_unconfirmed()
+        } else {
+            state != TurnState::Ended
+        }
+    });
+
+    match blocking {
+        None => Ok(()),
+        Some(turn) => Err(AppError::Conflict(format!(
+            "Team {} already has an active turn {} ({})",
+            team_id,
+            turn.turn_id,
+            TurnState::of(turn)
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::fixtures::{at, turn};
+
+    const ALL: [TurnState; 7] = [
+        TurnState::Started,
+        TurnState::Thrown,
+        TurnState::Confirmed,
+        TurnState::Mixing,
+        TurnState::Mixed,
+        TurnState::Delivered,
+        TurnState::Ended,
+    ];
+
+    /// A turn of team 1 with the timestamps of `state` set.
+    fn in_state(turn_id: i32, state: TurnState, penalty: bool) -> Turn {
+        let mut turn = turn(turn_id, 1, None);
+        turn.penalty = penalty;
+        let reached = |s: TurnState| {
+            let index = |s| ALL.iter().position(|&other| other == s);
+            (index(state) >= index(s)).then(|| at(turn_id.into()))
+        };
+        turn.thrown_at = reached(TurnState::Thrown);
+        turn.confirmed_at = reached(TurnState::Confirmed);
+        turn.mixing_at = reached(TurnState::Mixing);
+        turn.mixed_at = reached(TurnState::Mixed);
+        turn.delivered_at = reached(TurnState::Delivered);
+        turn.end_time = reached(TurnState::Ended);
+        turn
+    }
+
+    #[test]
+    fn state_is_the_latest_timestamp() {
+        for state in ALL {
+            assert_eq!(TurnState::of(&in_state(1, state, false)), state);
+        }
+        // Undoing mixing clears the later timestamp only
+        let mut turn = in_state(1, TurnState::Mixed, false);
+        turn.mixed_at = None;
+        assert_eq!(TurnState::of(&turn), TurnState::Mixing);
+    }
+
+    #[test]
+    fn prep_status_starts_at_confirmation() {
+        let statuses: Vec<_> = ALL.iter().map(|s| s.prep_status()).collect();
+        assert_eq!(
+            statuses,
+            [
+                None,
+                None,
+                Some(DrinkPrepStatus::Queued),
+                Some(DrinkPrepStatus::Mixing),
+                Some(DrinkPrepStatus::Mixed),
+                Some(DrinkPrepStatus::Delivered),
+                Some(DrinkPrepStatus::Delivered),
+            ]
+        );
+    }
+
+    #[test]
+    fn transitions_are_allowed_only_from_their_states() {
+        let allowed = |action: TurnAction| -> Vec<TurnState> {
+            ALL.into_iter()
+                .filter(|&state| check_transition(&in_state(1, state, false), action).is_ok())
+                .collect()
+        };
+        use TurnState::*;
+        assert_eq!(allowed(TurnAction::ChangeDice), [Started, Thrown]);
+        assert_eq!(allowed(TurnAction::Confirm), [Thrown]);
+        assert_eq!(allowed(TurnAction::ConfirmPenalty), [Started, Thrown]);
+        assert_eq!(allowed(TurnAction::Cancel), [Started, Thrown]);
+        assert_eq!(
+            allowed(TurnAction::EditDrinks),
+            [Confirmed, Mixing, Mixed, Delivered]
+        );
+        assert_eq!(
+            allowed(TurnAction::AttributeDrinks),
+            [Confirmed, Mixing, Mixed, Delivered, Ended]
+        );
+        assert_eq!(
+            allowed(TurnAction::SetPrepStatus(DrinkPrepStatus::Queued)),
+            [Confirmed, Mixing, Mixed]
+        );
+    }
+
+    #[test]
+    fn refused_transitions_are_conflicts() {
+        let turn = in_state(7, TurnState::Delivered, false);
+        match check_transition(&turn, TurnAction::SetPrepStatus(DrinkPrepStatus::Mixed)) {
+            Err(AppError::Conflict(message)) => assert_eq!(
+                message,
+                "Cannot set prep status Mixed on turn 7: it is delivered"
+            ),
+            other => panic!("expected a conflict, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn a_turn_starts_once_earlier_turns_have_ended() {
+        let team = TeamId(1);
+        assert!(check_can_start(&[], team, false).is_ok());
+        let history = [in_state(1, TurnState::Ended, false)];
+        assert!(check_can_start(&history, team, false).is_ok());
+
+        let history = [
+            in_state(1, TurnState::Ended, false),
+            in_state(2, TurnState::Delivered, false),
+        ];
+        assert!(matches!(
+            check_can_start(&history, team, false),
+            Err(AppError::Conflict(_))
+        ));
+        // Other teams are not blocked
+        assert!(check_can_start(&history, TeamId(2), false).is_ok());
+    }
+
+    #[test]
+    fn a_penalty_starts_unless_another_penalty_is_unconfirmed() {
+        let team = TeamId(1);
+        let history = [in_state(1, TurnState::Thrown, false)];
+        assert!(check_can_start(&history, team, true).is_ok());
+
+        let history = [in_state(1, TurnState::Mixing, true)];
+        assert!(check_can_start(&history, team, true).is_ok());
+
+        let history = [in_state(1, TurnState::Started, true)];
+        assert!(matches!(
+            check_can_start(&history, team, true),
+            Err(AppError::Conflict(_))
+        ));
+    }
+}
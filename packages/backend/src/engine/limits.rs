@@ -0,0 +1,346 @@
+//! Drinking limits of a game, checked when a turn or a penalty is confirmed.
+//!
+//! Limits count the standard drinks a team has been served in confirmed turns, as computed
+//! by [`crate::engine::alcohol`], plus the drinks of the turn being confirmed.
+
+use crate::engine::alcohol::{DrinkAlcohol, STANDARD_DRINK_GRAMS};
+use crate::utils::round;
+use crate::utils::state::AppError;
+use crate::utils::types::{
+    AlcoholIntake, Drink, DrinkingLimits, LimitBreach, LimitPeriod, TurnDrink, TurnDrinks,
+};
+use chrono::{DateTime, Duration, Utc};
+
+/// Checks the limits for a turn serving `turn_grams` of ethanol at `at` to a team that has
+/// already been served `intake`. Returns the limits that would be exceeded.
+pub fn check_limits(
+    limits: &DrinkingLimits,
+    intake: &AlcoholIntake,
+    turn_grams: f64,
+    at: DateTime<Utc>,
+) -> Vec<LimitBreach> {
+    if turn_grams <= 0.0 {
+        return vec![];
+    }
+    let hour_ago = at - Duration::hours(1);
+    let before_hour = intake
+        .timeline
+        .iter()
+        .rev()
+        .find(|p| p.at <= hour_ago)
+        .map_or(0.0, |p| p.ethanol_grams);
+
+    let counted = [
+        (LimitPeriod::Turn, limits.per_turn, turn_grams),
+        (
+            LimitPeriod::Hour,
+            limits.per_hour,
+            intake.ethanol_grams - before_hour + turn_grams,
+        ),
+        (
+            LimitPeriod::Game,
+            limits.per_game,
+            intake.ethanol_grams + turn_grams,
+        ),
+    ];
+    counted
+        .into_iter()
+        .filter_map(|(period, limit, grams)| {
+            let standard_drinks = round(grams / STANDARD_DRINK_GRAMS, 2);
+            limit
+                .filter(|&limit| standard_drinks > limit)
+                .map(|limit| LimitBreach {
+                    period,
+                    limit,
+                    standard_drinks,
+                })
+        })
+        .collect()
+}
+
+/// Replaces every alcoholic drink of the turn with its non-alcoholic equivalent from the
+/// catalog, or with `fallback` if it has none. Returns None if no drink could be replaced.
+pub fn substitute_drinks(
+    drinks: &TurnDrinks,
+    alcohol: &DrinkAlcohol,
+    catalog: &[Drink],
+    fallback: Option<&Drink>,
+) -> Option<TurnDrinks> {
+    let find = |id| catalog.iter().find(|d| d.id == id);
+    let mut replaced = false;
+    let mut substituted: Vec<TurnDrink> = Vec::new();
+    for td in &drinks.drinks {
+        let alcoholic = alcohol.get(&td.drink.id).is_some_and(|&grams| grams > 0.0);
+        let equivalent = find(td.drink.id)
+            .and_then(|d| d.non_alcoholic)
+            .and_then(find)
+            .or(fallback);
+        let drink = match equivalent.filter(|_| alcoholic) {
+            Some(equivalent) => {
+                replaced = true;
+                TurnDrink {
+                    drink: equivalent.clone(),
+                    // The substitute is not among the drinks picked up from the table
+                    on_table: 0,
+                    substituted_for: Some(td.drink.id),
+                    ..td.clone()
+                }
+            }
+            None => td.clone(),
+        };
+        match substituted
+            .iter_mut()
+            .find(|d| d.drink.id == drink.drink.id)
+        {
+            Some(existing) => {
+                existing.n += drink.n;
+                existing.on_table += drink.on_table;
+                existing.substituted_for = existing.substituted_for.or(drink.substituted_for);
+            }
+            None => substituted.push(drink),
+        }
+    }
+    replaced.then_some(TurnDrinks {
+        drinks: substituted,
+    })
+}
+
+/// Checks that the limits are positive and that the fallback substitute has no alcohol.
+pub fn validate_limits(limits: &DrinkingLimits, alcohol: &DrinkAlcohol) -> Result<(), AppError> {
+    for limit in [limits.per_hour, limits.per_game, limits.per_turn]
+        .into_iter()
+        .flatten()
+    {
+        if limit.is_nan() || limit <= 0.0 {
+            return Err(AppError::Validation(format!(
+                "Drinking limits must be positive, got {}",
+                limit
+            )));
+        }
+    }
+    match limits.substitute_drink {
+        Some(drink_id) if alcohol.get(&drink_id).is_some_and(|&grams| grams > 0.0) => Err(
+            AppError::Validation(format!("Substitute drink {} contains alcohol", drink_id)),
+        ),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::fixtures::{at, drink, turn_drink};
+    use crate::utils::ids::{DrinkId, TurnId};
+    use crate::utils::types::{AlcoholPoint, LimitAction};
+
+    fn limits(
+        per_hour: Option<f64>,
+        per_game: Option<f64>,
+        per_turn: Option<f64>,
+    ) -> DrinkingLimits {
+        DrinkingLimits {
+            per_hour,
+            per_game,
+            per_turn,
+            action: LimitAction::Flag,
+            substitute_drink: None,
+        }
+    }
+
+    /// An intake with running totals of `(minutes, grams)`.
+    fn intake(points: &[(i64, f64)]) -> AlcoholIntake {
+        let timeline: Vec<AlcoholPoint> = points
+            .iter()
+            .enumerate()
+            .map(|(i, &(minutes, grams))| AlcoholPoint {
+                turn_id: TurnId(i as i32 + 1),
+                at: at(minutes),
+                ethanol_grams: grams,
+                standard_drinks: grams / STANDARD_DRINK_GRAMS,
+            })
+            .collect();
+        let grams = timeline.last().map_or(0.0, |p| p.ethanol_grams);
+        AlcoholIntake {
+            ethanol_grams: grams,
+            standard_drinks: grams / STANDARD_DRINK_GRAMS,
+            timeline,
+        }
+    }
+
+    fn breaches(breaches: Vec<LimitBreach>) -> Vec<(LimitPeriod, f64)> {
+        breaches
+            .into_iter()
+            .map(|b| (b.period, b.standard_drinks))
+            .collect()
+    }
+
+    #[test]
+    fn counts_the_turn_being_confirmed() {
+        let limits = limits(None, None, Some(2.0));
+        let nothing = intake(&[]);
+        assert_eq!(breaches(check_limits(&limits, &nothing, 24.0, at(0))), []);
+        assert_eq!(
+            breaches(check_limits(&limits, &nothing, 36.0, at(0))),
+            [(LimitPeriod::Turn, 3.0)]
+        );
+    }
+
+    #[test]
+    fn counts_the_last_hour_and_the_whole_game() {
+        let limits = limits(Some(2.5), Some(4.0), None);
+        let served = intake(&[(0, 24.0), (30, 48.0)]);
+        // An hour before minute 70 only the first turn had been served
+        assert_eq!(
+            breaches(check_limits(&limits, &served, 12.0, at(70))),
+            [(LimitPeriod::Hour, 3.0), (LimitPeriod::Game, 5.0)]
+        );
+        // By minute 100 both turns are over an hour old
+        assert_eq!(
+            breaches(check_limits(&limits, &served, 12.0, at(100))),
+            [(LimitPeriod::Game, 5.0)]
+        );
+        // Within the first hour, everything counts
+        assert_eq!(
+            breaches(check_limits(&limits, &served, 6.0, at(45))),
+            [(LimitPeriod::Hour, 4.5), (LimitPeriod::Game, 4.5)]
+        );
+    }
+
+    #[test]
+    fn reaching_a_limit_does_not_exceed_it() {
+        let limits = limits(Some(1.0), Some(1.0), Some(1.0));
+        assert_eq!(
+            breaches(check_limits(&limits, &intake(&[]), 12.0, at(0))),
+            []
+        );
+    }
+
+    #[test]
+    fn turns_without_alcohol_never_exceed_limits() {
+        let limits = limits(Some(1.0), Some(1.0), Some(1.0));
+        let served = intake(&[(0, 120.0)]);
+        assert_eq!(breaches(check_limits(&limits, &served, 0.0, at(1))), []);
+    }
+
+    #[test]
+    fn validates_limits() {
+        let alcohol = DrinkAlcohol::from([(DrinkId(1), 12.0), (DrinkId(2), 0.0)]);
+        assert!(validate_limits(&limits(Some(2.0), None, Some(0.5)), &alcohol).is_ok());
+        for invalid in [0.0, -1.0, f64::NAN] {
+            assert!(matches!(
+                validate_limits(&limits(None, Some(invalid), None), &alcohol),
+                Err(AppError::Validation(_))
+            ));
+        }
+
+        let mut substituted = limits(Some(2.0), None, None);
+        substituted.substitute_drink = Some(DrinkId(2));
+        assert!(validate_limits(&substituted, &alcohol).is_ok());
+        substituted.substitute_drink = Some(DrinkId(1));
+        assert!(matches!(
+            validate_limits(&substituted, &alcohol),
+            Err(AppError::Validation(_))
+        ));
+    }
+
+    /// Kalja with a non-alcoholic equivalent, Lonkero without one, and the alcohol-free
+    /// Vesi and Alkoholiton.
+    fn catalog() -> (Vec<Drink>, DrinkAlcohol) {
+        let kalja = Drink {
+            non_alcoholic: Some(DrinkId(4)),
+            ..drink(1, "Kalja")
+        };
+        let catalog = vec![
+            kalja,
+            drink(2, "Lonkero"),
+            drink(3, "Vesi"),
+            drink(4, "Alkoholiton"),
+        ];
+        let alcohol = DrinkAlcohol::from([
+            (DrinkId(1), 12.0),
+            (DrinkId(2), 15.0),
+            (DrinkId(3), 0.0),
+            (DrinkId(4), 0.0),
+        ]);
+        (catalog, alcohol)
+    }
+
+    fn served(drinks: &TurnDrinks) -> Vec<(i32, i32, i32, Option<DrinkId>)> {
+        drinks
+            .drinks
+            .iter()
+            .map(|d| (d.drink.id.0, d.n, d.on_table, d.substituted_for))
+            .collect()
+    }
+
+    #[test]
+    fn substitutes_equivalents_before_the_fallback() {
+        let (catalog, alcohol) = catalog();
+        let fallback = &catalog[2];
+        let drinks = TurnDrinks {
+            drinks: vec![
+                TurnDrink {
+                    on_table: 1,
+                    ..turn_drink(catalog[0].clone(), 2)
+                },
+                turn_drink(catalog[1].clone(), 1),
+            ],
+        };
+        let substituted = substitute_drinks(&drinks, &alcohol, &catalog, Some(fallback)).unwrap();
+        assert_eq!(
+            served(&substituted),
+            [(4, 2, 0, Some(DrinkId(1))), (3, 1, 0, Some(DrinkId(2)))]
+        );
+        assert_eq!(substituted.drinks[0].drink.name, "Alkoholiton");
+    }
+
+    #[test]
+    fn merges_drinks_substituted_by_the_same_drink() {
+        let (catalog, alcohol) = catalog();
+        let drinks = TurnDrinks {
+            drinks: vec![
+                turn_drink(catalog[1].clone(), 1),
+                turn_drink(catalog[2].clone(), 2),
+            ],
+        };
+        let substituted =
+            substitute_drinks(&drinks, &alcohol, &catalog, Some(&catalog[2])).unwrap();
+        assert_eq!(served(&substituted), [(3, 3, 0, Some(DrinkId(2)))]);
+    }
+
+    #[test]
+    fn keeps_drinks_that_cannot_be_substituted() {
+        let (catalog, alcohol) = catalog();
+        let drinks = TurnDrinks {
+            drinks: vec![
+                turn_drink(catalog[0].clone(), 1),
+                turn_drink(catalog[1].clone(), 1),
+                turn_drink(catalog[2].clone(), 1),
+            ],
+        };
+        // Without a fallback only Kalja has an equivalent
+        let substituted = substitute_drinks(&drinks, &alcohol, &catalog, None).unwrap();
+        assert_eq!(
+            served(&substituted),
+            [
+                (4, 1, 0, Some(DrinkId(1))),
+                (2, 1, 0, None),
+                (3, 1, 0, None)
+            ]
+        );
+    }
+
+    #[test]
+    fn nothing_to_substitute_returns_none() {
+        let (catalog, alcohol) = catalog();
+        let lonkero = TurnDrinks {
+            drinks: vec![turn_drink(catalog[1].clone(), 1)],
+        };
+        assert!(substitute_drinks(&lonkero, &alcohol, &catalog, None).is_none());
+        // Non-alcoholic drinks are never replaced, even by the fallback
+        let vesi = TurnDrinks {
+            drinks: vec![turn_drink(catalog[2].clone(), 1)],
+        };
+        assert!(substitute_drinks(&vesi, &alcohol, &catalog, Some(&catalog[3])).is_none());
+    }
+}
@@ -0,0 +1,19 @@
+pub mod alcohol;
+pub mod analysis;
+pub mod deltas;
+pub mod events;
+#[cfg(test)]
+pub mod fixtures;
+pub mod formula;
+pub mod inventory;
+pub mod lifecycle;
+pub mod limits;
+pub mod movement;
+pub mod players;
+pub mod replay;
+pub mod simulation;
+pub mod specials;
+pub mod stats;
+pub mod turns;
+pub mod validation;
+pub mod views;
@@ -0,0 +1,187 @@
+use crate::engine::specials::place_rule;
+use crate::engine::turns::Dice;
+use crate::utils::state::AppError;
+use crate::utils::types::{BoardPlace, BoardPlaces, Connection};
+
+/// Moves a team forward on the board by the throw of the given dice.
+/// Returns (final_place, via_place) where via_place is set when the team passes through
+/// an intermediate place (on_land connection or a special rule that moves backwards).
+pub fn move_forwards<'a>(
+    mut current_place: &'a BoardPlace,
+    board_places: &'a BoardPlaces,
+    dice: &Dice,
+) -> Result<(&'a BoardPlace, Option<&'a BoardPlace>), AppError> {
+    let throw = dice.throw();
+    for step in 0..throw {
+        let conns = &current_place.connections;
+
+        enum Choice<'a> {
+            Forward(&'a Connection),
+            OnLand,
+            Backward,
+        }
+
+        // Pick a forward, non-on-land connection if available
+        let chosen = conns
+            .forwards
+            .iter()
+            .find(|c| !c.on_land)
+            .map(Choice::Forward)
+            // Otherwise, pick a forward, on-land connection if available (e.g. returning from Tampere)
+            .or_else(|| conns.forwards.first().map(|_| Choice::OnLand))
+            // Otherwise, fall back to a backward non-on-land connection
+            // (never traverse on-land backwards connections)
+            .or_else(|| {
+                conns
+                    .backwards
+                    .iter()
+                    .find(|c| !c.on_land)
+                    .map(|_| Choice::Backward)
+            });
+
+        match chosen {
+            Some(Choice::Backward) => {
+                // If there's only a backwards connection, reverse direction for the remaining steps
+                // Used at end of board
+                current_place = move_backwards(current_place, board_places, throw - step)?;
+                break;
+            }
+            Some(Choice::OnLand) => {
+                // If the only forward connection is on_land, stop here
+                // The code after the loop will take this connection
+                // Used when returning from Tampere
+                break;
+            }
+            Some(Choice::Forward(chosen)) => {
+                // Take the chosen forward connection
+                current_place = board_places
+                    .find_place(chosen.target)
+                    .unwrap_or(current_place);
+            }
+            None => {
+                // If there are no connections at all, stop here
+                // Should never happen if the board is properly designed
+                tracing::info!("No more connections, stopping movement.");
+                break;
+            }
+        }
+    }
+
+    // If the special rule of the landing place moves backwards (-D1), do so from there
+    if let Some(steps) = place_rule(&current_place.place)?.and_then(|r| r.backward_steps(dice)) {
+        let dest = move_backwards(current_place, board_places, steps)?;
+        return Ok((dest, Some(current_place)));
+    }
+
+    // If there are any forward on_land connections in the final resting spot, take the first one
+    // Used to go to Tampere and Raide-Jokeri
+    if let Some(on_land) = current_place
+        .connections
+        .forwards
+        .iter()
+        .find(|c| c.on_land)
+    {
+        let dest = board_places
+            .find_place(on_land.target)
+            .unwrap_or(current_place);
+        return Ok((dest, Some(current_place)));
+    }
+
+    Ok((current_place, None))
+}
+
+/// Moves a team backward on the board by the given throw amount.
+pub fn move_backwards<'a>(
+    mut current_place: &'a BoardPlace,
+    board_places: &'a BoardPlaces,
+    throw: i8,
+) -> Result<&'a BoardPlace, AppError> {
+    for _ in 0..throw {
+        // Pick a non-on-land backwards connection if available
+        let Some(conn) = &current_place
+            .connections
+            .backwards
+            .iter()
+            .find(|c| !c.on_land)
+        else {
+            return Err(AppError::NotFound(
+                "Not enough backwards connections found".to_string(),
+            ));
+        };
+        current_place = board_places
+            .find_place(conn.target)
+            .unwrap_or(current_place);
+    }
+    Ok(current_place)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::fixtures::sample_board;
+
+    fn dice(dice1: i32, dice2: i32, dice3: Option<i32>) -> Dice {
+        Dice::new(dice1, dice2, dice3, None)
+    }
+
+    /// Moves from `from` and returns the numbers of the (final, via) places.
+    fn forwards(from: i32, dice: Dice) -> (i32, Option<i32>) {
+        let board = sample_board();
+        let start = board.find_place(from).unwrap();
+        let (end, via) = move_forwards(start, &board, &dice).unwrap();
+        (end.place_number, via.map(|p| p.place_number))
+    }
+
+    #[test]
+    fn moves_by_the_smaller_die() {
+        assert_eq!(forwards(3, dice(2, 5, None)), (5, None));
+        assert_eq!(forwards(4, dice(6, 1, None)), (5, None));
+    }
+
+    #[test]
+    fn passing_an_on_land_connection_does_not_take_it() {
+        assert_eq!(forwards(1, dice(3, 3, None)), (4, None));
+    }
+
+    #[test]
+    fn landing_on_an_on_land_connection_takes_it() {
+        assert_eq!(forwards(1, dice(2, 4, None)), (10, Some(3)));
+    }
+
+    #[test]
+    fn an_only_on_land_connection_ends_the_move() {
+        // Returning from tampere: the remaining steps are lost
+        assert_eq!(forwards(10, dice(3, 4, None)), (4, Some(12)));
+        assert_eq!(forwards(11, dice(1, 1, None)), (4, Some(12)));
+    }
+
+    #[test]
+    fn turns_back_at_the_end_of_the_board() {
+        assert_eq!(forwards(5, dice(3, 3, None)), (4, None));
+        assert_eq!(forwards(6, dice(2, 6, None)), (4, None));
+    }
+
+    #[test]
+    fn backwards_rule_moves_back_from_the_landing_place() {
+        assert_eq!(forwards(1, dice(1, 2, Some(1))), (1, Some(2)));
+        // Waiting for the extra die, the team stays on the place
+        assert_eq!(forwards(1, dice(1, 2, None)), (2, None));
+    }
+
+    #[test]
+    fn backwards_rule_is_doubled_on_a_double() {
+        let board = sample_board();
+        let start = board.find_place(1).unwrap();
+        // Two steps back from place 2 run past the start of the board
+        let err = move_forwards(start, &board, &dice(1, 1, Some(1)));
+        assert!(matches!(err, Err(AppError::NotFound(_))));
+    }
+
+    #[test]
+    fn moving_backwards_never_takes_on_land_connections() {
+        let board = sample_board();
+        let start = board.find_place(4).unwrap();
+        let end = move_backwards(start, &board, 2).unwrap();
+        assert_eq!(end.place_number, 2);
+    }
+}
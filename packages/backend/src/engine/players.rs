@@ -0,0 +1,194 @@
+//! Attribution of the drinks of a turn to the players of its team.
+//!
+//! A referee may attribute any part of the servings of a turn drink to players; servings left
+//! unattributed still count for the team. Sober players cannot be given alcoholic drinks.
+
+use crate::engine::alcohol::DrinkAlcohol;
+use crate::utils::state::AppError;
+use crate::utils::types::{Player, PlayerDrink, Turn};
+use std::collections::HashSet;
+
+/// Checks an attribution of the drinks of `turn` to `players`, the players of its team.
+pub fn validate_attribution(
+    turn: &Turn,
+    players: &[Player],
+    drinks: &[PlayerDrink],
+    alcohol: &DrinkAlcohol,
+) -> Result<(), AppError> {
+    let mut seen = HashSet::new();
+    for pd in drinks {
+        let player = players
+            .iter()
+            .find(|p| p.player_id == pd.player_id)
+            .ok_or_else(|| {
+                AppError::Validation(format!(
+                    "Player {} is not in team {}",
+                    pd.player_id, turn.team_id
+                ))
+            })?;
+        let td = turn
+            .drinks
+            .drinks
+            .iter()
+            .find(|td| td.drink.id == pd.drink_id)
+            .ok_or_else(|| {
+                AppError::Validation(format!(
+                    "Turn {} has no drink {}",
+                    turn.turn_id, pd.drink_id
+                ))
+            })?;
+        if pd.n <= 0 {
+            return Err(AppError::Validation(format!(
+                "Player {} must be given a positive number of {}",
+                player.player_name, td.drink.name
+            )));
+        }
+        if !seen.insert((pd.drink_id, pd.player_id)) {
+            return Err(AppError::Validation(format!(
+                "{} is attributed to player {} more than once",
+                td.drink.name, player.player_name
+            )));
+        }
+        if player.sober && alcohol.get(&pd.drink_id).is_some_and(|&grams| grams > 0.0) {
+            return Err(AppError::Validation(format!(
+                "Player {} is sober and cannot drink {}",
+                player.player_name, td.drink.name
+            )));
+        }
+    }
+
+    for td in &turn.drinks.drinks {
+        let attributed: i32 = drinks
+            .iter()
+            .filter(|pd| pd.drink_id == td.drink.id)
+            .map(|pd| pd.n)
+            .sum();
+        if attributed > td.n {
+            return Err(AppError::Validation(format!(
+                "{} servings of {} attributed, but turn {} has only {}",
+                attributed, td.drink.name, turn.turn_id, td.n
+            )));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::fixtures::{drink, turn, turn_drink};
+    use crate::utils::ids::{DrinkId, PlayerId, TeamId};
+
+    fn player(player_id: i32, sober: bool) -> Player {
+        Player {
+            player_id: PlayerId(player_id),
+            team_id: TeamId(1),
+            player_name: format!("Player {player_id}"),
+            sober,
+        }
+    }
+
+    fn given(player_id: i32, drink_id: i32, n: i32) -> PlayerDrink {
+        PlayerDrink {
+            drink_id: DrinkId(drink_id),
+            player_id: PlayerId(player_id),
+            n,
+        }
+    }
+
+    /// Turn 5 of team 1 serving two Kalja and a Vesi, to a drinking and a sober player.
+    fn setup() -> (Turn, Vec<Player>, DrinkAlcohol) {
+        let mut turn = turn(5, 1, Some(3));
+        turn.drinks.drinks = vec![
+            turn_drink(drink(1, "Kalja"), 2),
+            turn_drink(drink(2, "Vesi"), 1),
+        ];
+        let players = vec![player(1, false), player(2, true)];
+        let alcohol = DrinkAlcohol::from([(DrinkId(1), 12.0), (DrinkId(2), 0.0)]);
+        (turn, players, alcohol)
+    }
+
+    fn error(result: Result<(), AppError>) -> String {
+        match result {
+            Err(AppError::Validation(message)) => message,
+            other => panic!("expected a validation error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn accepts_partial_attributions() {
+        let (turn, players, alcohol) = setup();
+        assert!(validate_attribution(&turn, &players, &[], &alcohol).is_ok());
+        let drinks = [given(1, 1, 1), given(2, 2, 1)];
+        assert!(validate_attribution(&turn, &players, &drinks, &alcohol).is_ok());
+        let drinks = [given(1, 1, 2)];
+        assert!(validate_attribution(&turn, &players, &drinks, &alcohol).is_ok());
+    }
+
+    #[test]
+    fn refuses_unknown_players_and_drinks() {
+        let (turn, players, alcohol) = setup();
+        assert_eq!(
+            error(validate_attribution(
+                &turn,
+                &players,
+                &[given(3, 1, 1)],
+                &alcohol
+            )),
+            "Player 3 is not in team 1"
+        );
+        assert_eq!(
+            error(validate_attribution(
+                &turn,
+                &players,
+                &[given(1, 9, 1)],
+                &alcohol
+            )),
+            "Turn 5 has no drink 9"
+        );
+    }
+
+    #[test]
+    fn refuses_empty_and_repeated_servings() {
+        let (turn, players, alcohol) = setup();
+        assert_eq!(
+            error(validate_attribution(
+                &turn,
+                &players,
+                &[given(1, 1, 0)],
+                &alcohol
+            )),
+            "Player Player 1 must be given a positive number of Kalja"
+        );
+        let drinks = [given(1, 1, 1), given(1, 1, 1)];
+        assert_eq!(
+            error(validate_attribution(&turn, &players, &drinks, &alcohol)),
+            "Kalja is attributed to player Player 1 more than once"
+        );
+    }
+
+    #[test]
+    fn refuses_more_servings_than_the_turn_has() {
+        let (turn, players, alcohol) = setup();
+        let drinks = [given(1, 2, 1), given(2, 2, 1)];
+        assert_eq!(
+            error(validate_attribution(&turn, &players, &drinks, &alcohol)),
+            "2 servings of Vesi attributed, but turn 5 has only 1"
+        );
+    }
+
+    #[test]
+    fn sober_players_get_no_alcohol() {
+        let (turn, players, alcohol) = setup();
+        assert_eq!(
+            error(validate_attribution(
+                &turn,
+                &players,
+                &[given(2, 1, 1)],
+                &alcohol
+            )),
+            "Player Player 2 is sober and cannot drink Kalja"
+        );
+        assert!(validate_attribution(&turn, &players, &[given(2, 2, 1)], &alcohol).is_ok());
+    }
+}
@@ -0,0 +1,655 @@
+//! Rebuilds the state of a game from its event stream.
+//!
+//! Movement and drinks of thrown turns are recomputed with [`resolve_turn`] on the given
+//! board, so an old game can be replayed against changed board rules. Referee decisions
+//! (confirmed drinks, prep statuses, the end of the game) are taken from the events as is.
+//! Timestamps come from the events, which are stored in the same transaction as the change.
+//! Like `get_full_game_data`, the returned turns show drinks as they are in the drink catalog.
+
+use crate::engine::events::{GameEvent, RecordedEvent};
+use crate::engine::turns::{
+    confirm_outcome, penalty_outcome, resolve_turn, sync_drinks_on_table, ConfirmOutcome, Dice,
+};
+use crate::utils::ids::{PlayerId, TeamId, TurnId};
+use crate::utils::state::AppError;
+use crate::utils::types::{
+    AlcoholIntake, BoardPlace, BoardPlaces, Connections, DrinkPrepStatus, Drinks, Game, GameData,
+    GamePlayer, GameTeam, PlaceDrinks, Player, PlayerServing, Team, Turn, TurnDrink, TurnDrinks,
+};
+use chrono::{DateTime, Utc};
+
+/// Replays the events of a game, oldest first, on top of an empty game.
+/// Events of undone actions are skipped.
+pub fn replay(
+    game: &Game,
+    board_places: &BoardPlaces,
+    catalog: &Drinks,
+    events: &[RecordedEvent],
+) -> Result<GameData, AppError> {
+    let mut state = ReplayState {
+        board_places,
+        game: Game {
+            started: false,
+            finished: false,
+            ..game.clone()
+        },
+        teams: Vec::new(),
+        players: Vec::new(),
+        turns: Vec::new(),
+    };
+
+    for recorded in events.iter().filter(|e| !e.undone) {
+        state
+            .apply(&recorded.event, recorded.created_at)
+            .map_err(|e| {
+                AppError::Validation(format!(
+                    "Replay failed at event {}: {}",
+                    recorded.event_id, e
+                ))
+            })?;
+    }
+
+    for turn in state.turns.iter_mut() {
+        for td in turn.drinks.drinks.iter_mut() {
+            if let Some(drink) = catalog.drinks.iter().find(|d| d.id == td.drink.id) {
+                td.drink = drink.clone();
+            }
+        }
+    }
+
+    Ok(state.into_game_data())
+}
+
+struct ReplayState<'a> {
+    board_places: &'a BoardPlaces,
+    game: Game,
+    teams: Vec<Team>,
+    players: Vec<Player>,
+    turns: Vec<Turn>,
+}
+
+impl ReplayState<'_> {
+    fn apply(&mut self, event: &GameEvent, at: DateTime<Utc>) -> Result<(), AppError> {
+        match event {
+            GameEvent::TeamCreated { team } => {
+                self.teams.push(team.clone());
+            }
+            GameEvent::TeamRenamed { team_id, team_name } => {
+                self.team(*team_id)?.team_name = team_name.clone();
+            }
+            GameEvent::TeamDeleted { team_id } => {
+                self.teams.retain(|t| t.team_id != *team_id);
+                self.players.retain(|p| p.team_id != *team_id);
+                self.turns.retain(|t| t.team_id != *team_id);
+            }
+            GameEvent::MoralVictoryEligibilitySet {
+                team_id,
+                moral_victory_eligible,
+            } => {
+                self.team(*team_id)?.moral_victory_eligible = *moral_victory_eligible;
+            }
+            GameEvent::SoberModeSet { team_id, sober } => {
+                self.team(*team_id)?.sober = *sober;
+            }
+            GameEvent::PlayerAdded { player } => {
+                self.players.push(player.clone());
+            }
+            GameEvent::PlayerUpdated { player } => {
+                *self.player(player.player_id)? = player.clone();
+            }
+            GameEvent::PlayerRemoved { player_id, .. } => {
+                self.player(*player_id)?;
+                self.players.retain(|p| p.player_id != *player_id);
+                for td in self
+                    .turns
+                    .iter_mut()
+                    .flat_map(|t| t.drinks.drinks.iter_mut())
+                {
+                    td.players.retain(|s| s.player_id != *player_id);
+                }
+            }
+            GameEvent::GameStarted {
+                drinks,
+                first_turns,
+            } => {
+                self.game.started = true;
+                self.game.start_time = at;
+                for first in first_turns {
+                    let mut turn = self.new_turn(first.turn_id, first.team_id, false, at);
+                    turn.place_number = Some(first.place_number);
+                    turn.thrown_at = Some(at);
+                    turn.confirmed_at = Some(at);
+                    turn.mixing_at = Some(at);
+                    turn.mixed_at = Some(at);
+                    turn.delivered_at = Some(at);
+                    turn.drinks.drinks = drinks
+                        .iter()
+                        .map(|d| TurnDrink {
+                            drink: d.drink.clone(),
+                            n: d.n.max(1),
+                            on_table: 0,
+                            optional: false,
+                            substituted_for: None,
+                            players: Vec::new(),
+                        })
+                        .collect();
+                    self.turns.push(turn);
+                }
+            }
+            GameEvent::TurnStarted {
+                turn_id,
+                team_id,
+                penalty,
+                dice1,
+                dice2,
+            } => {
+                let mut turn = self.new_turn(*turn_id, *team_id, *penalty, at);
+                turn.dice1 = *dice1;
+                turn.dice2 = *dice2;
+                if dice1.is_some() && dice2.is_some() {
+                    turn.thrown_at = Some(at);
+                }
+                self.turns.push(turn);
+                if let (Some(dice1), Some(dice2)) = (dice1, dice2) {
+                    self.resolve(*turn_id, Dice::new(*dice1, *dice2, None, None))?;
+                }
+            }
+            GameEvent::DiceChanged {
+                turn_id,
+                dice1,
+                dice2,
+                dice3,
+                dice4,
+            } => {
+                let turn = self.turn(*turn_id)?;
+                turn.dice1 = Some(*dice1);
+                turn.dice2 = Some(*dice2);
+                turn.dice3 = *dice3;
+                turn.dice4 = *dice4;
+                turn.thrown_at = Some(at);
+                self.resolve(*turn_id, Dice::new(*dice1, *dice2, *dice3, *dice4))?;
+            }
+            GameEvent::TurnConfirmed {
+                turn_id,
+                drinks,
+                over_limit,
+            } => {
+                let mut drinks = drinks.clone();
+                let place_number = self.turn(*turn_id)?.place_number;
+                let via_number = self.turn(*turn_id)?.via_number;
+                let end_place = place_number
+                    .and_then(|n| self.board_places.find_place(n))
+                    .ok_or_else(|| {
+                        AppError::Validation(format!("Turn {} has no location", turn_id))
+                    })?;
+                let via_place = via_number.and_then(|n| self.board_places.find_place(n));
+                sync_drinks_on_table(Some(end_place), via_place, &mut drinks);
+                let outcome = confirm_outcome(end_place, &drinks);
+
+                let turn = self.turn(*turn_id)?;
+                turn.drinks = drinks;
+                turn.over_limit = *over_limit;
+                confirm(turn, at);
+                match outcome {
+                    // The game itself ends with the GameEnded event recorded with the confirm
+                    ConfirmOutcome::EndGame | ConfirmOutcome::Queued => {}
+                    ConfirmOutcome::EndTurn => end_turn(turn, at),
+                    ConfirmOutcome::Delivered => {
+                        set_prep_status(turn, DrinkPrepStatus::Delivered, at)
+                    }
+                    ConfirmOutcome::Mixing => set_prep_status(turn, DrinkPrepStatus::Mixing, at),
+                }
+            }
+            GameEvent::PenaltyConfirmed {
+                turn_id,
+                drinks,
+                over_limit,
+            } => {
+                let mut drinks = drinks.clone();
+                let outcome = penalty_outcome(&mut drinks);
+                let turn = self.turn(*turn_id)?;
+                turn.drinks = drinks;
+                turn.over_limit = *over_limit;
+                confirm(turn, at);
+                if outcome == ConfirmOutcome::Mixing {
+                    set_prep_status(turn, DrinkPrepStatus::Mixing, at);
+                }
+            }
+            GameEvent::TurnDrinksEdited { turn_id, drinks } => {
+                // Like the handler, sync against the places carried by the turn itself
+                let mut drinks = drinks.clone();
+                let turn = self.turn(*turn_id)?;
+                sync_drinks_on_table(turn.place.as_ref(), turn.via.as_ref(), &mut drinks);
+                turn.drinks = drinks;
+            }
+            GameEvent::DrinksAttributed { turn_id, drinks } => {
+                let turn = self.turn(*turn_id)?;
+                for td in turn.drinks.drinks.iter_mut() {
+                    td.players = drinks
+                        .iter()
+                        .filter(|d| d.drink_id == td.drink.id)
+                        .map(|d| PlayerServing {
+                            player_id: d.player_id,
+                            n: d.n,
+                        })
+                        .collect();
+                    td.players.sort_by_key(|s| s.player_id);
+                }
+            }
+            GameEvent::TurnCancelled { turn_id } => {
+                self.turns.retain(|t| t.turn_id != *turn_id);
+            }
+            GameEvent::PrepStatusChanged { turn_id, status } => {
+                set_prep_status(self.turn(*turn_id)?, *status, at);
+            }
+            GameEvent::TurnEnded { team_id } => {
+                self.turns
+                    .iter_mut()
+                    .filter(|t| t.team_id == *team_id && t.end_time.is_none())
+                    .filter(|t| t.confirmed_at.is_some() && t.delivered_at.is_some())
+                    .for_each(|t| end_turn(t, at));
+            }
+            GameEvent::Teleported {
+                team_id,
+                turn_id,
+                location,
+            } => {
+                let mut turn = self.new_turn(*turn_id, *team_id, true, at);
+                turn.place_number = Some(*location);
+                turn.thrown_at = Some(at);
+                turn.confirmed_at = Some(at);
+                end_turn(&mut turn, at);
+                self.turns.push(turn);
+            }
+            GameEvent::GameEnded => {
+                self.game.finished = true;
+            }
+            // Limits only shape the drinks the confirm events already record, and the stock
+            // is not part of the game data
+            GameEvent::LimitsSet { .. }
+            | GameEvent::OpeningStockSet { .. }
+            | GameEvent::Restocked { .. } => {}
+        }
+        Ok(())
+    }
+
+    fn team(&mut self, team_id: TeamId) -> Result<&mut Team, AppError> {
+        self.teams
+            .iter_mut()
+            .find(|t| t.team_id == team_id)
+            .ok_or_else(|| AppError::NotFound(format!("Team {} not found", team_id)))
+    }
+
+    fn player(&mut self, player_id: PlayerId) -> Result<&mut Player, AppError> {
+        self.players
+            .iter_mut()
+            .find(|p| p.player_id == player_id)
+            .ok_or_else(|| AppError::NotFound(format!("Player {} not found", player_id)))
+    }
+
+    fn turn(&mut self, turn_id: TurnId) -> Result<&mut Turn, AppError> {
+        self.turns
+            .iter_mut()
+            .find(|t| t.turn_id == turn_id)
+            .ok_or_else(|| AppError::NotFound(format!("Turn {} not found", turn_id)))
+    }
+
+    fn new_turn(&self, turn_id: TurnId, team_id: TeamId, penalty: bool, at: DateTime<Utc>) -> Turn {
+        Turn {
+            turn_id,
+            team_id,
+            game_id: self.game.id,
+            start_time: at,
+            thrown_at: None,
+            confirmed_at: None,
+            mixing_at: None,
+            mixed_at: None,
+            delivered_at: None,
+            end_time: None,
+            dice1: None,
+            dice2: None,
+            dice3: None,
+            dice4: None,
+            place_number: None,
+            via_number: None,
+            penalty,
+            double_tampere: None,
+            over_limit: false,
+            drinks: TurnDrinks { drinks: vec![] },
+            place: None,
+            via: None,
+        }
+    }
+
+    /// Moves a thrown turn like the turn handlers do, using the turns replayed so far.
+    fn resolve(&mut self, turn_id: TurnId, dice: Dice) -> Result<(), AppError> {
+        let team_id = self.turn(turn_id)?.team_id;
+        let sober = self.team(team_id)?.sober;
+        let result = resolve_turn(self.board_places, &self.turns, team_id, sober, dice)?;
+        let turn = self.turn(turn_id)?;
+        turn.place_number = Some(result.end.place_number);
+        turn.via_number = result.via.map(|p| p.place_number);
+        turn.drinks = result.turn_drinks;
+        turn.double_tampere = Some(result.double_tampere);
+        Ok(())
+    }
+
+    /// Groups the replayed turns by team, the same way `get_full_game_data` does.
+    fn into_game_data(mut self) -> GameData {
+        self.teams.sort_by_key(|t| t.team_id.0);
+        self.players.sort_by_key(|p| p.player_id.0);
+        self.turns.sort_by_key(|t| t.turn_id.0);
+        let board_places = self.board_places;
+
+        let teams = self
+            .teams
+            .into_iter()
+            .map(|team| {
+                let turns: Vec<Turn> = self
+                    .turns
+                    .iter()
+                    .filter(|t| t.team_id == team.team_id)
+                    .map(|t| Turn {
+                        place: t.place_number.and_then(|n| summary_place(board_places, n)),
+                        via: t.via_number.and_then(|n| summary_place(board_places, n)),
+                        ..t.clone()
+                    })
+                    .collect();
+                let location = turns.iter().rev().find_map(|t| t.place.clone());
+                let double_tampere = turns
+                    .iter()
+                    .rev()
+                    .find_map(|t| t.double_tampere)
+                    .unwrap_or(false);
+                let players = self
+                    .players
+                    .iter()
+                    .filter(|p| p.team_id == team.team_id)
+                    .map(|player| GamePlayer {
+                        player: player.clone(),
+                        alcohol: AlcoholIntake::default(),
+                    })
+                    .collect();
+                GameTeam {
+                    team,
+                    turns,
+                    location,
+                    double_tampere,
+                    alcohol: AlcoholIntake::default(),
+                    players,
+                }
+            })
+            .collect();
+
+        GameData {
+            game: self.game,
+            teams,
+        }
+    }
+}
+
+/// A board place without its connections and drinks, as turns carry it in `GameData`.
+fn summary_place(board_places: &BoardPlaces, place_number: i32) -> Option<BoardPlace> {
+    board_places.find_place(place_number).map(|p| BoardPlace {
+        connections: Connections {
+            forwards: vec![],
+            backwards: vec![],
+        },
+        drinks: PlaceDrinks { drinks: vec![] },
+        ..p.clone()
+    })
+}
+
+fn confirm(turn: &mut Turn, at: DateTime<Utc>) {
+    turn.confirmed_at = Some(at);
+    turn.thrown_at.get_or_insert(at);
+}
+
+fn end_turn(turn: &mut Turn, at: DateTime<Utc>) {
+    turn.end_time = Some(at);
+    turn.mixing_at.get_or_insert(at);
+    turn.mixed_at.get_or_insert(at);
+    turn.delivered_at.get_or_insert(at);
+}
+
+/// Mirrors `database::turns::set_drink_prep_status`.
+fn set_prep_status(turn: &mut Turn, status: DrinkPrepStatus, at: DateTime<Utc>) {
+    match status {
+        DrinkPrepStatus::Queued => {
+            turn.mixing_at = None;
+            turn.mixed_at = None;
+            turn.delivered_at = None;
+        }
+        DrinkPrepStatus::Mixing => {
+            turn.mixing_at = Some(at);
+            turn.mixed_at = None;
+            turn.delivered_at = None;
+        }
+        DrinkPrepStatus::Mixed => {
+            turn.mixing_at.get_or_insert(at);
+            turn.mixed_at = Some(at);
+            turn.delivered_at = None;
+        }
+        DrinkPrepStatus::Delivered => {
+            turn.mixing_at.get_or_insert(at);
+            turn.mixed_at.get_or_insert(at);
+            turn.delivered_at = Some(at);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::events::FirstTurn;
+    use crate::engine::fixtures::{at, drink, game, sample_board, team, turn_drink};
+    use crate::engine::lifecycle::TurnState;
+    use crate::utils::ids::{EventId, GameId};
+
+    /// Records `events` a minute apart, none of them undone.
+    fn recorded(events: Vec<GameEvent>) -> Vec<RecordedEvent> {
+        events
+            .into_iter()
+            .enumerate()
+            .map(|(i, event)| RecordedEvent {
+                event_id: EventId(i as i32 + 1),
+                game_id: GameId(1),
+                action_id: None,
+                created_at: at(i as i64 + 1),
+                undone: false,
+                event,
+            })
+            .collect()
+    }
+
+    fn catalog() -> Drinks {
+        Drinks {
+            drinks: vec![drink(1, "Kalja"), drink(2, "Siideri"), drink(3, "Lonkero")],
+        }
+    }
+
+    fn run(events: &[RecordedEvent]) -> Result<GameData, AppError> {
+        replay(&game(), &sample_board(), &catalog(), events)
+    }
+
+    /// Two teams started on place 1.
+    fn started() -> Vec<GameEvent> {
+        vec![
+            GameEvent::TeamCreated { team: team(1) },
+            GameEvent::TeamCreated { team: team(2) },
+            GameEvent::GameStarted {
+                drinks: vec![turn_drink(drink(1, "x"), 1)],
+                first_turns: vec![
+                    FirstTurn {
+                        team_id: TeamId(1),
+                        turn_id: TurnId(1),
+                        place_number: 1,
+                    },
+                    FirstTurn {
+                        team_id: TeamId(2),
+                        turn_id: TurnId(2),
+                        place_number: 1,
+                    },
+                ],
+            },
+            GameEvent::TurnEnded { team_id: TeamId(1) },
+        ]
+    }
+
+    fn thrown(turn_id: i32, dice1: i32, dice2: i32) -> GameEvent {
+        GameEvent::TurnStarted {
+            turn_id: TurnId(turn_id),
+            team_id: TeamId(1),
+            penalty: false,
+            dice1: Some(dice1),
+            dice2: Some(dice2),
+        }
+    }
+
+    fn turn(data: &GameData, turn_id: i32) -> &Turn {
+        data.teams
+            .iter()
+            .flat_map(|t| &t.turns)
+            .find(|t| t.turn_id == TurnId(turn_id))
+            .unwrap()
+    }
+
+    #[test]
+    fn starting_gives_delivered_first_turns() {
+        let data = run(&recorded(started())).unwrap();
+        assert!(data.game.started);
+        assert_eq!(data.game.start_time, at(3));
+        assert_eq!(data.teams.len(), 2);
+        let first = turn(&data, 1);
+        assert_eq!(first.place_number, Some(1));
+        assert_eq!(first.drinks.drinks[0].drink.name, "Kalja");
+        assert_eq!(first.end_time, Some(at(4)));
+        assert_eq!(TurnState::of(turn(&data, 2)), TurnState::Delivered);
+        assert_eq!(data.teams[0].location.as_ref().unwrap().place_number, 1);
+    }
+
+    #[test]
+    fn thrown_turns_move_with_the_board_rules() {
+        let mut events = started();
+        events.push(thrown(3, 2, 4));
+        let data = run(&recorded(events)).unwrap();
+        let turn = turn(&data, 3);
+        assert_eq!((turn.place_number, turn.via_number), (Some(10), Some(3)));
+        assert_eq!(turn.drinks.drinks.len(), 2);
+        assert_eq!(turn.double_tampere, Some(false));
+        // Turns carry their places without connections or drinks
+        assert!(turn.place.as_ref().unwrap().drinks.drinks.is_empty());
+        assert_eq!(data.teams[0].location.as_ref().unwrap().place_number, 10);
+    }
+
+    #[test]
+    fn confirmed_drinks_go_through_the_queue() {
+        let mut events = started();
+        events.push(thrown(3, 3, 4));
+        events.push(GameEvent::TurnConfirmed {
+            turn_id: TurnId(3),
+            drinks: TurnDrinks {
+                drinks: vec![turn_drink(drink(1, "Kalja"), 3)],
+            },
+            over_limit: true,
+        });
+        let data = run(&recorded(events.clone())).unwrap();
+        let confirmed = turn(&data, 3);
+        assert_eq!(TurnState::of(confirmed), TurnState::Confirmed);
+        assert!(confirmed.over_limit);
+        // The Kalja of place 4 is on the table
+        assert_eq!(confirmed.drinks.drinks[0].on_table, 1);
+
+        events.push(GameEvent::PrepStatusChanged {
+            turn_id: TurnId(3),
+            status: DrinkPrepStatus::Mixed,
+        });
+        events.push(GameEvent::PrepStatusChanged {
+            turn_id: TurnId(3),
+            status: DrinkPrepStatus::Delivered,
+        });
+        let data = run(&recorded(events.clone())).unwrap();
+        let delivered = turn(&data, 3);
+        assert_eq!(TurnState::of(delivered), TurnState::Delivered);
+        assert_eq!(delivered.mixing_at, Some(at(7)));
+
+        events.push(GameEvent::TurnEnded { team_id: TeamId(1) });
+        let data = run(&recorded(events)).unwrap();
+        assert_eq!(TurnState::of(turn(&data, 3)), TurnState::Ended);
+    }
+
+    #[test]
+    fn turns_without_drinks_end_on_confirmation() {
+        let mut events = started();
+        events.push(thrown(3, 3, 4));
+        events.push(GameEvent::TurnConfirmed {
+            turn_id: TurnId(3),
+            drinks: TurnDrinks { drinks: vec![] },
+            over_limit: false,
+        });
+        let data = run(&recorded(events)).unwrap();
+        assert_eq!(TurnState::of(turn(&data, 3)), TurnState::Ended);
+    }
+
+    #[test]
+    fn undone_events_are_skipped() {
+        let mut events = started();
+        events.push(thrown(3, 2, 4));
+        events.push(GameEvent::TeamRenamed {
+            team_id: TeamId(2),
+            team_name: "Renamed".to_string(),
+        });
+        let mut events = recorded(events);
+        events[4].undone = true;
+        events[5].undone = true;
+        let data = run(&events).unwrap();
+        assert_eq!(data.teams[0].turns.len(), 1);
+        assert_eq!(data.teams[1].team.team_name, "Team 2");
+    }
+
+    #[test]
+    fn cancelled_turns_and_deleted_teams_disappear() {
+        let mut events = started();
+        events.push(thrown(3, 2, 4));
+        events.push(GameEvent::TurnCancelled { turn_id: TurnId(3) });
+        events.push(GameEvent::TeamDeleted { team_id: TeamId(2) });
+        let data = run(&recorded(events)).unwrap();
+        assert_eq!(data.teams.len(), 1);
+        assert_eq!(data.teams[0].turns.len(), 1);
+    }
+
+    #[test]
+    fn teleports_and_the_end_of_the_game() {
+        let mut events = started();
+        events.push(GameEvent::Teleported {
+            team_id: TeamId(1),
+            turn_id: TurnId(3),
+            location: 6,
+        });
+        events.push(GameEvent::GameEnded);
+        let data = run(&recorded(events)).unwrap();
+        assert!(data.game.finished);
+        let teleport = turn(&data, 3);
+        assert!(teleport.penalty);
+        assert_eq!(TurnState::of(teleport), TurnState::Ended);
+        assert_eq!(data.teams[0].location.as_ref().unwrap().place_number, 6);
+    }
+
+    #[test]
+    fn a_broken_stream_names_the_failing_event() {
+        let mut events = started();
+        events.push(GameEvent::PrepStatusChanged {
+            turn_id: TurnId(9),
+            status: DrinkPrepStatus::Mixed,
+        });
+        match run(&recorded(events)) {
+            Err(AppError::Validation(message)) => {
+                assert_eq!(
+                    message,
+                    "Replay failed at event 5: not found: Turn 9 not found"
+                )
+            }
+            other => panic!("expected a validation error, got {other:?}"),
+        }
+    }
+}
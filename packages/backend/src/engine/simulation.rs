@@ -0,0 +1,361 @@
+//! Monte Carlo simulation of games on a board, for balancing a board before the event.
+//!
+//! Every team throws each round and moves with [`resolve_turn`], so movement and drinks follow
+//! the real board rules (specials, refills, doubles, double tampere). A game ends when a team
+//! lands on an end place. Penalties and drinks decided by the referee or IE are not simulated.
+
+use crate::engine::turns::{confirmed_turn, resolve_turn, Dice};
+use crate::utils::ids::TeamId;
+use crate::utils::state::AppError;
+use crate::utils::types::{BoardPlaces, Turn, TurnDrinks};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+/// Area of the places that count as Tampere.
+const TAMPERE_AREA: &str = "tampere";
+
+#[derive(Clone, Copy, Debug)]
+pub struct SimulationConfig {
+    pub games: usize,
+    pub teams: usize,
+    /// Rounds after which a game is given up as unfinished.
+    pub max_rounds: usize,
+    /// Seed of the dice, for reproducible runs.
+    pub seed: Option<u64>,
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        Self {
+            games: 1000,
+            teams: 10,
+            max_rounds: 500,
+            seed: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SimulationReport {
+    pub games: usize,
+    pub teams: usize,
+    /// Games in which a team reached an end place within `max_rounds`.
+    pub finished_games: usize,
+    /// Rounds played until a team reached an end place, over finished games.
+    pub game_length: Distribution,
+    /// Drinks each team got during a game.
+    pub drinks_per_team: Distribution,
+    /// Share of games in which at least one team visited Tampere.
+    pub tampere_share: f64,
+    pub place_visits: Vec<PlaceVisits>,
+}
+
+/// Summary of a sampled integer quantity.
+#[derive(Clone, Debug, Serialize)]
+pub struct Distribution {
+    pub mean: f64,
+    pub min: i32,
+    pub p10: i32,
+    pub median: i32,
+    pub p90: i32,
+    pub max: i32,
+    pub histogram: Vec<HistogramBucket>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct HistogramBucket {
+    pub value: i32,
+    pub count: usize,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct PlaceVisits {
+    pub place_number: i32,
+    pub place_name: String,
+    /// Average number of times a team stopped on or passed via the place per game.
+    pub visits_per_game: f64,
+}
+
+/// Plays `config.games` games on the board and summarizes them.
+pub fn simulate(
+    board_places: &BoardPlaces,
+    config: &SimulationConfig,
+) -> Result<SimulationReport, AppError> {
+    if config.teams == 0 {
+        return Err(AppError::Validation(
+            "A simulation needs at least one team".to_string(),
+        ));
+    }
+    let start = board_places
+        .places
+        .iter()
+        .find(|p| p.start)
+        .ok_or_else(|| AppError::Validation("The board has no start place".to_string()))?
+        .place_number;
+    let mut rng = match config.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_os_rng(),
+    };
+
+    let mut game_lengths = Vec::new();
+    let mut drinks_per_team = Vec::with_capacity(config.games * config.teams);
+    let mut tampere_games = 0;
+    let mut visits: HashMap<i32, usize> = HashMap::new();
+
+    for _ in 0..config.games {
+        let game = play_game(board_places, config, start, &mut rng)?;
+        if let Some(rounds) = game.rounds {
+            game_lengths.push(rounds);
+        }
+        drinks_per_team.extend(game.drinks);
+        if game.visited_tampere {
+            tampere_games += 1;
+        }
+        for (place_number, count) in game.visits {
+            *visits.entry(place_number).or_default() += count;
+        }
+    }
+
+    let place_visits = board_places
+        .places
+        .iter()
+        .map(|p| PlaceVisits {
+            place_number: p.place_number,
+            place_name: p.place.place_name.clone(),
+            visits_per_game: visits.get(&p.place_number).copied().unwrap_or(0) as f64
+                / config.games.max(1) as f64,
+        })
+        .collect();
+
+    Ok(SimulationReport {
+        games: config.games,
+        teams: config.teams,
+        finished_games: game_lengths.len(),
+        game_length: Distribution::of(game_lengths),
+        drinks_per_team: Distribution::of(drinks_per_team),
+        tampere_share: tampere_games as f64 / config.games.max(1) as f64,
+        place_visits,
+    })
+}
+
+struct SimulatedGame {
+    /// Rounds until a team finished, None if no team did within the limit.
+    rounds: Option<i32>,
+    drinks: Vec<i32>,
+    visited_tampere: bool,
+    visits: HashMap<i32, usize>,
+}
+
+fn play_game(
+    board_places: &BoardPlaces,
+    config: &SimulationConfig,
+    start: i32,
+    rng: &mut StdRng,
+) -> Result<SimulatedGame, AppError> {
+    let teams: Vec<TeamId> = (1..=config.teams as i32).map(TeamId).collect();
+    let mut history: Vec<Turn> = teams
+        .iter()
+        .enumerate()
+        .map(|(i, &team_id)| confirmed_turn(i as i32, team_id, start, false))
+        .collect();
+    let mut game = SimulatedGame {
+        rounds: None,
+        drinks: vec![0; teams.len()],
+        visited_tampere: false,
+        visits: HashMap::new(),
+    };
+
+    'rounds: for round in 1..=config.max_rounds {
+        for (i, &team_id) in teams.iter().enumerate() {
+            let dice = Dice::new(
+                rng.random_range(1..=6),
+                rng.random_range(1..=6),
+                Some(rng.random_range(1..=6)),
+                Some(rng.random_range(1..=6)),
+            );
+            let result = resolve_turn(board_places, &history, team_id, false, dice)?;
+
+            game.drinks[i] += total_drinks(&result.turn_drinks);
+            for place in result.via.iter().chain([&result.end]) {
+                *game.visits.entry(place.place_number).or_default() += 1;
+                game.visited_tampere |= place.area == TAMPERE_AREA;
+            }
+
+            let mut turn = confirmed_turn(
+                history.len() as i32,
+                team_id,
+                result.end.place_number,
+                result.double_tampere,
+            );
+            turn.via_number = result.via.as_ref().map(|p| p.place_number);
+            history.push(turn);
+
+            if result.end.end {
+                game.rounds = Some(round as i32);
+                break 'rounds;
+            }
+        }
+    }
+    Ok(game)
+}
+
+fn total_drinks(drinks: &TurnDrinks) -> i32 {
+    drinks.drinks.iter().map(|d| d.n).sum()
+}
+
+impl Distribution {
+    fn of(mut samples: Vec<i32>) -> Self {
+        samples.sort_unstable();
+        let percentile = |p: usize| {
+            if samples.is_empty() {
+                0
+            } else {
+                samples[(samples.len() - 1) * p / 100]
+            }
+        };
+        let mut histogram: BTreeMap<i32, usize> = BTreeMap::new();
+        for &sample in &samples {
+            *histogram.entry(sample).or_default() += 1;
+        }
+        Distribution {
+            mean: samples.iter().map(|&s| s as f64).sum::<f64>() / samples.len().max(1) as f64,
+            min: samples.first().copied().unwrap_or(0),
+            p10: percentile(10),
+            median: percentile(50),
+            p90: percentile(90),
+            max: samples.last().copied().unwrap_or(0),
+            histogram: histogram
+                .into_iter()
+                .map(|(value, count)| HistogramBucket { value, count })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::fixtures::{board, place, sample_board};
+    use crate::utils::types::BoardPlace;
+
+    /// The sample board, starting from place 1 and ending on place 6. Place 2 does not move
+    /// backwards, since there is nothing behind the start to move to.
+    fn finite_board() -> BoardPlaces {
+        let mut board_places = sample_board();
+        for place in &mut board_places.places {
+            place.start = place.place_number == 1;
+            place.end = place.place_number == 6;
+            place.place.special = None;
+        }
+        board_places
+    }
+
+    /// A start place whose only connection lands every team on an end place in Tampere.
+    fn trivial_board() -> BoardPlaces {
+        let start = BoardPlace {
+            start: true,
+            ..place(1, "normal")
+        };
+        let end = BoardPlace {
+            end: true,
+            ..place(2, TAMPERE_AREA)
+        };
+        board(vec![start, end], &[(1, 2, true)])
+    }
+
+    fn config(games: usize, teams: usize, seed: u64) -> SimulationConfig {
+        SimulationConfig {
+            games,
+            teams,
+            max_rounds: 50,
+            seed: Some(seed),
+        }
+    }
+
+    #[test]
+    fn distribution_of_samples() {
+        let distribution = Distribution::of(vec![4, 1, 3, 3, 2, 10, 3, 2, 1, 5]);
+        assert!((distribution.mean - 3.4).abs() < 1e-9);
+        let summary = (
+            distribution.min,
+            distribution.p10,
+            distribution.median,
+            distribution.p90,
+            distribution.max,
+        );
+        assert_eq!(summary, (1, 1, 3, 5, 10));
+        let histogram: Vec<_> = distribution
+            .histogram
+            .iter()
+            .map(|b| (b.value, b.count))
+            .collect();
+        assert_eq!(histogram, [(1, 2), (2, 2), (3, 3), (4, 1), (5, 1), (10, 1)]);
+    }
+
+    #[test]
+    fn distribution_of_no_samples_is_zero() {
+        let distribution = Distribution::of(vec![]);
+        assert_eq!(distribution.mean, 0.0);
+        let summary = (
+            distribution.min,
+            distribution.p10,
+            distribution.median,
+            distribution.p90,
+            distribution.max,
+        );
+        assert_eq!(summary, (0, 0, 0, 0, 0));
+        assert!(distribution.histogram.is_empty());
+    }
+
+    #[test]
+    fn seeded_runs_are_deterministic() {
+        let run = |seed| {
+            let report = simulate(&finite_board(), &config(20, 4, seed)).unwrap();
+            serde_json::to_value(report).unwrap()
+        };
+        assert_eq!(run(7), run(7));
+        assert_ne!(run(7), run(8));
+    }
+
+    #[test]
+    fn every_game_on_a_trivial_board_ends_in_tampere() {
+        let report = simulate(&trivial_board(), &config(5, 3, 1)).unwrap();
+        assert_eq!(report.finished_games, 5);
+        assert_eq!(report.tampere_share, 1.0);
+        // The first team finishes in the first round, passing via the start place
+        assert_eq!(report.game_length.max, 1);
+        assert_eq!(report.game_length.histogram.len(), 1);
+        let visits: Vec<_> = report
+            .place_visits
+            .iter()
+            .map(|p| (p.place_number, p.visits_per_game))
+            .collect();
+        assert_eq!(visits, [(1, 1.0), (2, 1.0)]);
+        assert_eq!(report.drinks_per_team.histogram[0].count, 5 * 3);
+    }
+
+    #[test]
+    fn games_without_an_end_place_are_unfinished() {
+        let mut board_places = trivial_board();
+        board_places.places[1].end = false;
+        let report = simulate(&board_places, &config(3, 2, 1)).unwrap();
+        assert_eq!(report.finished_games, 0);
+        assert!(report.game_length.histogram.is_empty());
+    }
+
+    #[test]
+    fn needs_a_start_place_and_a_team() {
+        let mut board_places = trivial_board();
+        assert!(matches!(
+            simulate(&board_places, &config(1, 0, 1)),
+            Err(AppError::Validation(_))
+        ));
+        board_places.places[0].start = false;
+        assert!(matches!(
+            simulate(&board_places, &config(1, 1, 1)),
+            Err(AppError::Validation(_))
+        ));
+    }
+}
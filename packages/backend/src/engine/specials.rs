@@ -0,0 +1,216 @@
+use crate::engine::formula::{parse_formula, Expr};
+use crate::engine::turns::Dice;
+use crate::utils::state::AppError;
+use crate::utils::types::Place;
+
+/// What a special rule can look at when the drinks of a turn are computed.
+pub struct SpecialContext {
+    pub dice: Dice,
+    /// How many confirmed turns have already ended on the place.
+    pub visits: i32,
+    /// Whether the double tampere multiplier applied when the turn started.
+    pub double_tampere: bool,
+}
+
+/// Behaviour of a `Place.special` expression.
+///
+/// Every method has a neutral default, so a rule only implements the parts of a turn it changes.
+/// New rules are added by implementing this trait and registering a parser in [`REGISTRY`].
+/// Anything that is not a registered keyword is parsed as a [`Formula`].
+pub trait SpecialRule: Send + Sync {
+    /// The canonical expression of the rule, as stored in `Place.special`.
+    fn expression(&self) -> String;
+
+    /// How many extra dice (dice3, dice4) the referee has to throw when landing on the place.
+    fn required_dice(&self) -> u8 {
+        0
+    }
+
+    /// Steps to move backwards after landing on the place, if any.
+    fn backward_steps(&self, _dice: &Dice) -> Option<i8> {
+        None
+    }
+
+    /// Multiplier applied to the drinks of the place.
+    fn drink_multiplier(&self, _ctx: &SpecialContext) -> i32 {
+        1
+    }
+}
+
+type RuleParser = fn(&str) -> Option<Box<dyn SpecialRule>>;
+
+/// Parsers of the keyword rules, tried in order before falling back to a formula.
+const REGISTRY: &[RuleParser] = &[
+    MinOfTwoDice::parse,
+    BackwardsOneDie::parse,
+    IeDecides::parse,
+];
+
+/// Parses a `Place.special` expression into its rule.
+pub fn parse_special(special: &str) -> Result<Box<dyn SpecialRule>, AppError> {
+    let special = special.trim();
+    if let Some(rule) = REGISTRY.iter().find_map(|parse| parse(special)) {
+        return Ok(rule);
+    }
+    let expr = parse_formula(special).map_err(|e| e.into_app_error(special))?;
+    Ok(Box::new(Formula {
+        source: special.to_string(),
+        expr,
+    }))
+}
+
+/// Parses the special rule of a place, if it has one.
+pub fn place_rule(place: &Place) -> Result<Option<Box<dyn SpecialRule>>, AppError> {
+    match place.special.as_deref() {
+        None | Some("") => Ok(None),
+        Some(special) => parse_special(special).map(Some),
+    }
+}
+
+/// Number of extra dice needed by a special expression; unknown expressions need none.
+pub fn required_dice(special: Option<&str>) -> u8 {
+    special
+        .and_then(|s| parse_special(s).ok())
+        .map_or(0, |rule| rule.required_dice())
+}
+
+/// `MIN(D2)`: two extra dice are thrown and drinks are multiplied by the smaller one.
+struct MinOfTwoDice;
+
+impl MinOfTwoDice {
+    fn parse(special: &str) -> Option<Box<dyn SpecialRule>> {
+        (special == "MIN(D2)").then(|| Box::new(MinOfTwoDice) as Box<dyn SpecialRule>)
+    }
+}
+
+impl SpecialRule for MinOfTwoDice {
+    fn expression(&self) -> String {
+        "MIN(D2)".to_string()
+    }
+
+    fn required_dice(&self) -> u8 {
+        2
+    }
+
+    fn drink_multiplier(&self, ctx: &SpecialContext) -> i32 {
+        match (ctx.dice.dice3, ctx.dice.dice4) {
+            (Some(dice3), Some(dice4)) => dice3.min(dice4),
+            _ => 1,
+        }
+    }
+}
+
+/// `-D1`: an extra die is thrown and the team moves backwards by it (doubled on a double throw).
+struct BackwardsOneDie;
+
+impl BackwardsOneDie {
+    fn parse(special: &str) -> Option<Box<dyn SpecialRule>> {
+        (special == "-D1").then(|| Box::new(BackwardsOneDie) as Box<dyn SpecialRule>)
+    }
+}
+
+impl SpecialRule for BackwardsOneDie {
+    fn expression(&self) -> String {
+        "-D1".to_string()
+    }
+
+    fn required_dice(&self) -> u8 {
+        1
+    }
+
+    fn backward_steps(&self, dice: &Dice) -> Option<i8> {
+        let multiplier = if dice.is_double() { 2 } else { 1 };
+        dice.dice3.map(|d| (multiplier * d) as i8)
+    }
+}
+
+/// `IE`: IE decides the drinks after the turn is confirmed (PUT /turns/{turn_id}/drinks).
+struct IeDecides;
+
+impl IeDecides {
+    fn parse(special: &str) -> Option<Box<dyn SpecialRule>> {
+        (special == "IE").then(|| Box::new(IeDecides) as Box<dyn SpecialRule>)
+    }
+}
+
+impl SpecialRule for IeDecides {
+    fn expression(&self) -> String {
+        "IE".to_string()
+    }
+}
+
+/// The largest drink multiplier a formula can give. Formula values saturate instead of
+/// overflowing, so without a bound `99999*99999` would still overflow the drink counts.
+pub const MAX_FORMULA_MULTIPLIER: i32 = 100;
+
+/// A drink formula such as `N+1` or `IF(DOUBLE, 3, 1)`; see [`crate::engine::formula`].
+/// Drinks are multiplied by its value, clamped to 0..=[`MAX_FORMULA_MULTIPLIER`], or by 1
+/// while a die it needs has not been thrown.
+struct Formula {
+    source: String,
+    expr: Expr,
+}
+
+impl SpecialRule for Formula {
+    fn expression(&self) -> String {
+        self.source.clone()
+    }
+
+    fn required_dice(&self) -> u8 {
+        self.expr.required_dice()
+    }
+
+    fn drink_multiplier(&self, ctx: &SpecialContext) -> i32 {
+        self.expr
+            .evaluate(ctx)
+            .map_or(1, |value| value.clamp(0, MAX_FORMULA_MULTIPLIER))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ctx(dice: Dice) -> SpecialContext {
+        SpecialContext {
+            dice,
+            visits: 0,
+            double_tampere: false,
+        }
+    }
+
+    fn multiplier(special: &str, dice: Dice) -> i32 {
+        parse_special(special).unwrap().drink_multiplier(&ctx(dice))
+    }
+
+    #[test]
+    fn keywords_take_precedence_over_formulas() {
+        let dice = Dice::new(3, 3, Some(5), Some(2));
+        assert_eq!(parse_special(" MIN(D2) ").unwrap().expression(), "MIN(D2)");
+        assert_eq!(multiplier("MIN(D2)", dice), 2);
+        assert_eq!(
+            parse_special("-D1").unwrap().backward_steps(&dice),
+            Some(10)
+        );
+        assert_eq!(parse_special("-D1").unwrap().required_dice(), 1);
+        assert_eq!(parse_special("IE").unwrap().drink_multiplier(&ctx(dice)), 1);
+    }
+
+    #[test]
+    fn formula_multipliers_are_clamped() {
+        let dice = Dice::new(3, 4, None, None);
+        assert_eq!(multiplier("D1+1", dice), 4);
+        assert_eq!(multiplier("1-D2", dice), 0);
+        assert_eq!(multiplier("99999*99999", dice), MAX_FORMULA_MULTIPLIER);
+        // Waiting for an extra die
+        assert_eq!(multiplier("D3*D4", dice), 1);
+    }
+
+    #[test]
+    fn invalid_formulas_are_validation_errors() {
+        assert!(matches!(parse_special("D5"), Err(AppError::Validation(_))));
+        assert_eq!(required_dice(Some("D5")), 0);
+        assert_eq!(required_dice(Some("MIN(D3, D4)")), 2);
+        assert_eq!(required_dice(None), 0);
+    }
+}
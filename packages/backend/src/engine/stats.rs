@@ -0,0 +1,312 @@
+//! Statistics and the current ranking of a game, served by GET /games/{id}/stats.
+//!
+//! Only confirmed turns are counted. Teams are ranked by how many forward steps they are from
+//! the nearest end place, which ignores dice and specials but is stable between throws.
+//! Alcohol comes from the intake already computed into the game data. Players count only the
+//! servings attributed to them.
+
+use crate::utils::round;
+use crate::utils::types::{
+    BoardPlaces, DrinkCount, GameData, GamePlayer, GameStats, GameTeam, PlayerStats, TeamStats,
+    Turn, TurnDrink,
+};
+use chrono::{DateTime, Utc};
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, VecDeque};
+
+/// Computes the statistics of every team of the game as of `now`.
+pub fn game_stats(
+    game_data: &GameData,
+    board_places: &BoardPlaces,
+    now: DateTime<Utc>,
+) -> GameStats {
+    let steps = steps_to_end(board_places);
+    // Rates are per hour of play, at least a minute to avoid spikes right after the start
+    let hours = ((now - game_data.game.start_time).num_seconds().max(60) as f64) / 3600.0;
+    let mut teams: Vec<TeamStats> = game_data
+        .teams
+        .iter()
+        .map(|team| team_stats(team, &steps, hours))
+        .collect();
+
+    // Teams without a place or a way to the end are ranked last
+    teams.sort_by_key(|t| (t.steps_to_end.is_none(), t.steps_to_end, t.team_id));
+    for i in 0..teams.len() {
+        teams[i].rank = if i > 0 && teams[i].steps_to_end == teams[i - 1].steps_to_end {
+            teams[i - 1].rank
+        } else {
+            i as i32 + 1
+        };
+    }
+
+    GameStats {
+        game_id: game_data.game.id,
+        teams,
+    }
+}
+
+fn team_stats(team: &GameTeam, steps: &HashMap<i32, i32>, hours: f64) -> TeamStats {
+    let confirmed: Vec<&Turn> = team
+        .turns
+        .iter()
+        .filter(|t| t.confirmed_at.is_some())
+        .collect();
+
+    let drinks = drink_counts(
+        confirmed
+            .iter()
+            .flat_map(|t| &t.drinks.drinks)
+            .map(|td| (td, td.n)),
+    );
+
+    // Latencies of turns whose drinks went through IE
+    let prepared: Vec<&Turn> = confirmed
+        .iter()
+        .copied()
+        .filter(|t| t.drinks.drinks.iter().any(|d| d.n > d.on_table))
+        .collect();
+
+    let place_number = team.location.as_ref().map(|p| p.place_number);
+    TeamStats {
+        team_id: team.team.team_id,
+        team_name: team.team.team_name.clone(),
+        rank: 0,
+        place_number,
+        steps_to_end: place_number.and_then(|n| steps.get(&n).copied()),
+        turns: confirmed.iter().filter(|t| !t.penalty).count() as i32,
+        penalties: confirmed.iter().filter(|t| t.penalty).count() as i32,
+        total_drinks: drinks.iter().map(|d| d.n).sum(),
+        drinks,
+        ethanol_grams: team.alcohol.ethanol_grams,
+        standard_drinks: team.alcohol.standard_drinks,
+        standard_drinks_per_hour: round(team.alcohol.standard_drinks / hours, 2),
+        avg_queue_secs: average_secs(&prepared, |t| (t.confirmed_at, t.mixing_at)),
+        avg_mixing_secs: average_secs(&prepared, |t| (t.mixing_at, t.mixed_at)),
+        avg_delivery_secs: average_secs(&prepared, |t| (t.mixed_at, t.delivered_at)),
+        players: team
+            .players
+            .iter()
+            .map(|p| player_stats(p, &confirmed))
+            .collect(),
+    }
+}
+
+fn player_stats(player: &GamePlayer, confirmed: &[&Turn]) -> PlayerStats {
+    let player_id = player.player.player_id;
+    let drinks = drink_counts(
+        confirmed
+            .iter()
+            .flat_map(|t| &t.drinks.drinks)
+            .flat_map(|td| {
+                td.players
+                    .iter()
+                    .filter(move |s| s.player_id == player_id)
+                    .map(move |s| (td, s.n))
+            }),
+    );
+    PlayerStats {
+        player_id,
+        player_name: player.player.player_name.clone(),
+        total_drinks: drinks.iter().map(|d| d.n).sum(),
+        drinks,
+        ethanol_grams: player.alcohol.ethanol_grams,
+        standard_drinks: player.alcohol.standard_drinks,
+    }
+}
+
+/// Sums servings by drink, leaving out drinks with none.
+fn drink_counts<'a>(servings: impl Iterator<Item = (&'a TurnDrink, i32)>) -> Vec<DrinkCount> {
+    let mut drinks: Vec<DrinkCount> = Vec::new();
+    for (td, n) in servings {
+        match drinks.iter_mut().find(|d| d.drink.id == td.drink.id) {
+            Some(count) => count.n += n,
+            None => drinks.push(DrinkCount {
+                drink: td.drink.clone(),
+                n,
+            }),
+        }
+    }
+    drinks.retain(|d| d.n > 0);
+    drinks.sort_by_key(|d| d.drink.id);
+    drinks
+}
+
+/// Average length in seconds of the intervals that have both ends, None if none has.
+fn average_secs(
+    turns: &[&Turn],
+    interval: impl Fn(&Turn) -> (Option<DateTime<Utc>>, Option<DateTime<Utc>>),
+) -> Option<f64> {
+    let secs: Vec<f64> = turns
+        .iter()
+        .filter_map(|t| match interval(t) {
+            (Some(from), Some(to)) => Some((to - from).num_milliseconds() as f64 / 1000.0),
+            _ => None,
+        })
+        .collect();
+    (!secs.is_empty()).then(|| secs.iter().sum::<f64>() / secs.len() as f64)
+}
+
+/// Fewest forward steps from each place to an end place, for places that can reach one.
+fn steps_to_end(board_places: &BoardPlaces) -> HashMap<i32, i32> {
+    let mut predecessors: HashMap<i32, Vec<i32>> = HashMap::new();
+    for place in &board_places.places {
+        for conn in &place.connections.forwards {
+            predecessors
+                .entry(conn.target)
+                .or_default()
+                .push(place.place_number);
+        }
+    }
+
+    let mut steps: HashMap<i32, i32> = HashMap::new();
+    let mut queue = VecDeque::new();
+    for place in board_places.places.iter().filter(|p| p.end) {
+        steps.insert(place.place_number, 0);
+        queue.push_back(place.place_number);
+    }
+    while let Some(number) = queue.pop_front() {
+        let distance = steps[&number] + 1;
+        for &previous in predecessors.get(&number).into_iter().flatten() {
+            if let Entry::Vacant(entry) = steps.entry(previous) {
+                entry.insert(distance);
+                queue.push_back(previous);
+            }
+        }
+    }
+    steps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::fixtures::{
+        at, drink, game, game_team, place, sample_board, turn, turn_drink,
+    };
+    use crate::utils::types::TurnDrinks;
+    use chrono::Duration;
+
+    /// The sample board ending on place 6, with a place 20 that leads nowhere.
+    fn board_places() -> BoardPlaces {
+        let mut board_places = sample_board();
+        for place in &mut board_places.places {
+            place.end = place.place_number == 6;
+        }
+        board_places.places.push(place(20, "normal"));
+        board_places
+    }
+
+    /// A team standing on `place_number`, if given.
+    fn located(team_id: i32, place_number: Option<i32>) -> GameTeam {
+        GameTeam {
+            location: place_number.map(|n| place(n, "normal")),
+            ..game_team(team_id, vec![])
+        }
+    }
+
+    /// A turn of team 1 queued for `queue` minutes and mixed for `mixing` minutes, with
+    /// `on_table` of its two drinks already on the table.
+    fn prepared(turn_id: i32, queue: i64, mixing: i64, on_table: i32) -> Turn {
+        let mut turn = turn(turn_id, 1, Some(3));
+        let confirmed = turn.confirmed_at.unwrap();
+        turn.mixing_at = Some(confirmed + Duration::minutes(queue));
+        turn.mixed_at = Some(confirmed + Duration::minutes(queue + mixing));
+        turn.drinks = TurnDrinks {
+            drinks: vec![TurnDrink {
+                on_table,
+                ..turn_drink(drink(1, "Kalja"), 2)
+            }],
+        };
+        turn
+    }
+
+    #[test]
+    fn counts_forward_steps_to_the_nearest_end() {
+        let steps = steps_to_end(&board_places());
+        let mut steps: Vec<_> = steps.into_iter().collect();
+        steps.sort();
+        assert_eq!(
+            steps,
+            [
+                (1, 5),
+                (2, 4),
+                (3, 3),
+                (4, 2),
+                (5, 1),
+                (6, 0),
+                // Tampere returns to place 4 on land
+                (10, 5),
+                (11, 4),
+                (12, 3),
+            ]
+        );
+    }
+
+    #[test]
+    fn teams_equally_far_from_the_end_share_a_rank() {
+        let game_data = GameData {
+            game: game(),
+            teams: vec![
+                located(1, None),
+                located(2, Some(12)),
+                located(3, Some(5)),
+                located(4, Some(3)),
+                located(5, Some(20)),
+            ],
+        };
+        let stats = game_stats(&game_data, &board_places(), at(60));
+        let ranks: Vec<_> = stats
+            .teams
+            .iter()
+            .map(|t| (t.team_id.0, t.rank, t.steps_to_end))
+            .collect();
+        assert_eq!(
+            ranks,
+            [
+                (3, 1, Some(1)),
+                (2, 2, Some(3)),
+                (4, 2, Some(3)),
+                // Teams without a place or a way to the end are last
+                (1, 4, None),
+                (5, 4, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn averages_the_intervals_with_both_ends() {
+        let turns = [
+            prepared(1, 1, 3, 0),
+            prepared(2, 2, 0, 0),
+            turn(3, 1, Some(3)),
+        ];
+        let turns: Vec<&Turn> = turns.iter().collect();
+        let queue = average_secs(&turns, |t| (t.confirmed_at, t.mixing_at));
+        let mixing = average_secs(&turns, |t| (t.mixing_at, t.mixed_at));
+        let delivery = average_secs(&turns, |t| (t.mixed_at, t.delivered_at));
+        assert_eq!(queue, Some(90.0));
+        assert_eq!(mixing, Some(90.0));
+        assert_eq!(delivery, None);
+    }
+
+    #[test]
+    fn latencies_leave_out_turns_served_from_the_table() {
+        let mut unconfirmed = prepared(4, 60, 60, 0);
+        unconfirmed.confirmed_at = None;
+        let turns = vec![
+            prepared(1, 2, 1, 1),
+            prepared(2, 10, 10, 2),
+            prepared(3, 4, 3, 0),
+            unconfirmed,
+        ];
+        let game_data = GameData {
+            game: game(),
+            teams: vec![game_team(1, turns)],
+        };
+        let stats = game_stats(&game_data, &board_places(), at(60));
+        let team = &stats.teams[0];
+        assert_eq!(team.turns, 3);
+        assert_eq!(team.total_drinks, 6);
+        assert_eq!(team.avg_queue_secs, Some(180.0));
+        assert_eq!(team.avg_mixing_secs, Some(120.0));
+    }
+}
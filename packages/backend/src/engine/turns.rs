@@ -0,0 +1,528 @@
+use chrono::Utc;
+use std::cmp::min;
+
+use crate::engine::movement::move_forwards;
+use crate::engine::specials::{place_rule, SpecialContext};
+use crate::utils::ids::{GameId, TeamId, TurnId};
+use crate::utils::state::AppError;
+use crate::utils::types::{BoardPlace, BoardPlaces, Turn, TurnDrinks};
+
+/// Dice values of a single throw.
+/// `dice3` and `dice4` are only thrown when a special place requires them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Dice {
+    pub dice1: i32,
+    pub dice2: i32,
+    pub dice3: Option<i32>,
+    pub dice4: Option<i32>,
+}
+
+impl Dice {
+    pub fn new(dice1: i32, dice2: i32, dice3: Option<i32>, dice4: Option<i32>) -> Self {
+        Self {
+            dice1,
+            dice2,
+            dice3,
+            dice4,
+        }
+    }
+
+    /// Whether the two movement dice show the same value.
+    pub fn is_double(&self) -> bool {
+        self.dice1 == self.dice2
+    }
+
+    /// Number of steps moved: the smaller of the two movement dice.
+    pub fn throw(&self) -> i8 {
+        min(self.dice1, self.dice2) as i8
+    }
+}
+
+/// Result of computing turn movement and drinks.
+pub struct TurnComputeResult {
+    /// The final place after movement.
+    pub end: BoardPlace,
+    /// The intermediate place before an on_land connection was taken (if applicable).
+    pub via: Option<BoardPlace>,
+    pub turn_drinks: TurnDrinks,
+    /// Whether double tampere applies after this turn.
+    pub double_tampere: bool,
+}
+
+/// Where a team currently stands, derived from its turn history.
+pub struct TeamPosition<'a> {
+    pub place: &'a BoardPlace,
+    pub double_tampere: bool,
+}
+
+/// Finds the current position of a team from the turn history of its game.
+///
+/// The position is the place of the team's latest confirmed turn that has a location,
+/// so penalty turns without a location are skipped.
+pub fn team_position<'a>(
+    board_places: &'a BoardPlaces,
+    history: &[Turn],
+    team_id: TeamId,
+) -> Result<TeamPosition<'a>, AppError> {
+    let latest_turn = history
+        .iter()
+        .filter(|t| t.team_id == team_id && t.confirmed_at.is_some())
+        .filter(|t| t.place_number.is_some())
+        .max_by_key(|t| t.turn_id.0);
+
+    let place = latest_turn
+        .and_then(|t| t.place_number)
+        .and_then(|place_number| board_places.find_place(place_number))
+        .ok_or_else(|| {
+            AppError::NotFound(format!(
+                "Team {} has no current location - cannot compute movement",
+                team_id
+            ))
+        })?;
+
+    Ok(TeamPosition {
+        place,
+        double_tampere: latest_turn.and_then(|t| t.double_tampere).unwrap_or(false),
+    })
+}
+
+/// Counts how many confirmed turns of the game have ended on a place.
+pub fn count_visits(history: &[Turn], place_number: i32) -> i32 {
+    history
+        .iter()
+        .filter(|t| t.confirmed_at.is_some() && t.place_number == Some(place_number))
+        .count() as i32
+}
+
+/// Computes the destination and drinks for a team's turn based on dice values.
+/// A `sober` team gets the non-alcoholic equivalents of the drinks.
+///
+/// Works purely on a board snapshot and the turn history of the game, so it has no
+/// side effects (end_game etc.) - those happen on confirm.
+pub fn resolve_turn(
+    board_places: &BoardPlaces,
+    history: &[Turn],
+    team_id: TeamId,
+    sober: bool,
+    dice: Dice,
+) -> Result<TurnComputeResult, AppError> {
+    let position = team_position(board_places, history, team_id)?;
+    let current_place = position.place;
+
+    let is_double = dice.is_double();
+    let double_multiplier = if is_double { 2 } else { 1 };
+    let double_tampere_multiplier = if position.double_tampere { 2 } else { 1 };
+
+    let (end, via) = move_forwards(current_place, board_places, &dice)?;
+
+    let visited = count_visits(history, end.place_number);
+
+    // Multiply drinks of the final destination by its special rule (e.g. MIN(D2), N+1)
+    let ctx = SpecialContext {
+        dice,
+        visits: visited,
+        double_tampere: position.double_tampere,
+    };
+    let extra_multiplier = place_rule(&end.place)?.map_or(1, |r| r.drink_multiplier(&ctx));
+
+    let base_multiplier: i32 = double_multiplier * double_tampere_multiplier;
+    let end_multiplier = base_multiplier.saturating_mul(extra_multiplier);
+
+    // Collect drinks from the via place (if we moved after the usual dice throw)
+    let mut all_drinks = if let Some(via_place) = via {
+        let via_visited = count_visits(history, via_place.place_number);
+        via_place
+            .drinks
+            .to_turn_drinks(via_visited, base_multiplier, sober)
+            .drinks
+    } else {
+        vec![]
+    };
+
+    // Add drinks from the final destination (with special formula multiplier)
+    let end_drinks = end
+        .drinks
+        .to_turn_drinks(visited, end_multiplier, sober)
+        .drinks;
+    for drink in end_drinks {
+        // Merge duplicate drinks (same drink id) by summing n and on_table
+        if let Some(existing) = all_drinks.iter_mut().find(|d| d.drink.id == drink.drink.id) {
+            existing.n = existing.n.saturating_add(drink.n);
+            existing.on_table = existing.on_table.saturating_add(drink.on_table);
+            existing.optional = existing.optional || drink.optional;
+            existing.substituted_for = existing.substituted_for.or(drink.substituted_for);
+        } else {
+            all_drinks.push(drink);
+        }
+    }
+
+    let turn_drinks = TurnDrinks { drinks: all_drinks };
+
+    // Compute new double_tampere state based on movement
+    let double_tampere = if current_place.area == "normal" && end.area == "tampere" && is_double {
+        true
+    } else if end.area == "normal" {
+        false
+    } else {
+        position.double_tampere
+    };
+
+    Ok(TurnComputeResult {
+        end: end.clone(),
+        via: via.cloned(),
+        turn_drinks,
+        double_tampere,
+    })
+}
+
+/// A confirmed turn ending on `place_number`, as far as [`resolve_turn`] looks at it.
+pub fn confirmed_turn(id: i32, team_id: TeamId, place_number: i32, double_tampere: bool) -> Turn {
+    let now = Utc::now();
+    Turn {
+        turn_id: TurnId(id),
+        team_id,
+        game_id: GameId(0),
+        start_time: now,
+        thrown_at: Some(now),
+        confirmed_at: Some(now),
+        mixing_at: None,
+        mixed_at: None,
+        delivered_at: None,
+        end_time: None,
+        dice1: None,
+        dice2: None,
+        dice3: None,
+        dice4: None,
+        place_number: Some(place_number),
+        via_number: None,
+        penalty: false,
+        double_tampere: Some(double_tampere),
+        over_limit: false,
+        drinks: TurnDrinks { drinks: vec![] },
+        place: None,
+        via: None,
+    }
+}
+
+/// Updates the on_table values of the drinks in a turn based on the place's drinks.
+/// Checks both the final place and the via place (if applicable), since drinks can come from either.
+pub fn sync_drinks_on_table(
+    place: Option<&BoardPlace>,
+    via: Option<&BoardPlace>,
+    drinks: &mut TurnDrinks,
+) {
+    let places: Vec<&BoardPlace> = [place, via].into_iter().flatten().collect();
+
+    for td in drinks.drinks.iter_mut() {
+        // Sum on_table contributions from all places (final + via)
+        let on_table_total: i32 = places
+            .iter()
+            .filter_map(|p| {
+                p.drinks
+                    .drinks
+                    .iter()
+                    .find(|pd| pd.drink.id == td.drink.id && pd.on_table)
+                    .map(|pd| pd.n)
+            })
+            .sum();
+
+        td.on_table = min(td.n, on_table_total);
+    }
+}
+
+/// What happens to a turn right after it is confirmed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConfirmOutcome {
+    /// The turn ended on an end place, so the game is over.
+    EndGame,
+    /// No drinks were awarded, so the turn ends immediately.
+    EndTurn,
+    /// All drinks were on the table (no IE drinks), so they count as delivered.
+    Delivered,
+    /// No drink needs mixing, so the IE queue is skipped.
+    Mixing,
+    /// The drinks go through the IE queue (on_table drinks are delivered with the IE drinks).
+    Queued,
+}
+
+/// Decides the outcome of confirming a turn that ended on `end_place` with `drinks`.
+pub fn confirm_outcome(end_place: &BoardPlace, drinks: &TurnDrinks) -> ConfirmOutcome {
+    if end_place.end {
+        return ConfirmOutcome::EndGame;
+    }
+    if drinks.drinks.is_empty() {
+        return ConfirmOutcome::EndTurn;
+    }
+
+    let has_ie_drinks = drinks.drinks.iter().any(|d| d.on_table < d.n);
+    let needs_mixing = drinks
+        .drinks
+        .iter()
+        .any(|d| d.on_table < d.n && !d.drink.no_mix_required);
+
+    if !has_ie_drinks {
+        ConfirmOutcome::Delivered
+    } else if !needs_mixing {
+        ConfirmOutcome::Mixing
+    } else {
+        ConfirmOutcome::Queued
+    }
+}
+
+/// Decides the outcome of confirming a penalty turn.
+/// Penalty drinks all go through the IE queue, so their on_table counts are cleared.
+pub fn penalty_outcome(drinks: &mut TurnDrinks) -> ConfirmOutcome {
+    for drink in &mut drinks.drinks {
+        drink.on_table = 0;
+    }
+
+    if drinks.drinks.iter().any(|d| !d.drink.no_mix_required) {
+        ConfirmOutcome::Queued
+    } else {
+        ConfirmOutcome::Mixing
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::fixtures::{drink, place_drink, sample_board, turn, turn_drink};
+    use crate::utils::types::{PlaceDrink, TurnDrink};
+
+    const TEAM: TeamId = TeamId(1);
+
+    fn dice(dice1: i32, dice2: i32) -> Dice {
+        Dice::new(dice1, dice2, None, None)
+    }
+
+    /// A history where the team stands on `place_number`.
+    fn standing_on(place_number: i32) -> Vec<Turn> {
+        vec![turn(1, TEAM.0, Some(place_number))]
+    }
+
+    /// (drink name, n, on_table) of the drinks of a turn.
+    fn drinks(result: &TurnComputeResult) -> Vec<(String, i32, i32)> {
+        result
+            .turn_drinks
+            .drinks
+            .iter()
+            .map(|td| (td.drink.name.clone(), td.n, td.on_table))
+            .collect()
+    }
+
+    fn resolve(history: &[Turn], dice: Dice) -> TurnComputeResult {
+        resolve_turn(&sample_board(), history, TEAM, false, dice).unwrap()
+    }
+
+    #[test]
+    fn position_is_the_latest_confirmed_turn_with_a_location() {
+        let board = sample_board();
+        let mut unconfirmed = turn(4, TEAM.0, Some(5));
+        unconfirmed.confirmed_at = None;
+        let history = vec![
+            turn(1, TEAM.0, Some(1)),
+            turn(2, TEAM.0, Some(3)),
+            turn(3, TEAM.0, None),
+            unconfirmed,
+            turn(5, 2, Some(6)),
+        ];
+        let position = team_position(&board, &history, TEAM).unwrap();
+        assert_eq!(position.place.place_number, 3);
+        assert!(!position.double_tampere);
+    }
+
+    #[test]
+    fn position_without_a_location_is_an_error() {
+        let history = vec![turn(1, TEAM.0, None)];
+        let board = sample_board();
+        let result = team_position(&board, &history, TEAM);
+        assert!(matches!(result, Err(AppError::NotFound(_))));
+    }
+
+    #[test]
+    fn visits_count_confirmed_turns_of_all_teams() {
+        let mut unconfirmed = turn(3, TEAM.0, Some(5));
+        unconfirmed.confirmed_at = None;
+        let history = vec![turn(1, TEAM.0, Some(5)), turn(2, 2, Some(5)), unconfirmed];
+        assert_eq!(count_visits(&history, 5), 2);
+        assert_eq!(count_visits(&history, 4), 0);
+    }
+
+    #[test]
+    fn drinks_are_given_on_the_first_visit_unless_refilled() {
+        let result = resolve(&standing_on(4), dice(1, 3));
+        assert_eq!(result.end.place_number, 5);
+        assert_eq!(drinks(&result), [("Lonkero".to_string(), 1, 0)]);
+
+        let mut history = standing_on(4);
+        history.push(turn(2, 2, Some(5)));
+        assert!(resolve(&history, dice(1, 3)).turn_drinks.drinks.is_empty());
+
+        let mut history = standing_on(3);
+        history.push(turn(2, 2, Some(4)));
+        assert_eq!(
+            drinks(&resolve(&history, dice(1, 3))),
+            [("Kalja".to_string(), 1, 1)]
+        );
+    }
+
+    #[test]
+    fn a_double_doubles_the_drinks() {
+        let result = resolve(&standing_on(4), dice(1, 1));
+        assert_eq!(drinks(&result), [("Lonkero".to_string(), 2, 0)]);
+        // Only the drinks of the place are on the table
+        let result = resolve(&standing_on(3), dice(1, 1));
+        assert_eq!(drinks(&result), [("Kalja".to_string(), 2, 1)]);
+    }
+
+    #[test]
+    fn on_land_connections_give_the_drinks_of_both_places() {
+        let result = resolve(&standing_on(1), dice(2, 5));
+        assert_eq!(result.end.place_number, 10);
+        assert_eq!(result.via.as_ref().map(|p| p.place_number), Some(3));
+        assert_eq!(
+            drinks(&result),
+            [("Kalja".to_string(), 1, 0), ("Siideri".to_string(), 2, 0)]
+        );
+        assert!(!result.double_tampere);
+    }
+
+    #[test]
+    fn a_double_into_tampere_starts_double_tampere() {
+        let result = resolve(&standing_on(1), dice(2, 2));
+        assert!(result.double_tampere);
+        assert_eq!(
+            drinks(&result),
+            [("Kalja".to_string(), 2, 0), ("Siideri".to_string(), 4, 0)]
+        );
+    }
+
+    #[test]
+    fn double_tampere_doubles_drinks_until_leaving_tampere() {
+        let mut history = standing_on(10);
+        history[0].double_tampere = Some(true);
+        let result = resolve(&history, dice(1, 4));
+        assert_eq!(result.end.place_number, 11);
+        assert!(result.double_tampere);
+        assert_eq!(drinks(&result), [("Siideri".to_string(), 2, 0)]);
+
+        let mut history = standing_on(11);
+        history[0].double_tampere = Some(true);
+        let result = resolve(&history, dice(2, 2));
+        assert_eq!(result.end.place_number, 4);
+        assert_eq!(result.via.as_ref().map(|p| p.place_number), Some(12));
+        assert!(!result.double_tampere);
+        assert_eq!(
+            drinks(&result),
+            [("Siideri".to_string(), 4, 0), ("Kalja".to_string(), 4, 1)]
+        );
+    }
+
+    #[test]
+    fn drinks_of_the_same_kind_are_merged() {
+        let mut board = sample_board();
+        let kalja = drink(1, "Kalja");
+        let place = board.places.iter_mut().find(|p| p.place_number == 10);
+        place.unwrap().drinks.drinks = vec![PlaceDrink {
+            refill: true,
+            ..place_drink(10, kalja, 2)
+        }];
+        let result = resolve_turn(&board, &standing_on(1), TEAM, false, dice(2, 3)).unwrap();
+        assert_eq!(drinks(&result), [("Kalja".to_string(), 3, 0)]);
+    }
+
+    #[test]
+    fn huge_formulas_do_not_overflow_drink_counts() {
+        let mut board = sample_board();
+        let place = board.places.iter_mut().find(|p| p.place_number == 12);
+        place.unwrap().place.special = Some("99999*99999".to_string());
+        let mut history = standing_on(11);
+        history[0].double_tampere = Some(true);
+        let result = resolve_turn(&board, &history, TEAM, false, dice(1, 1)).unwrap();
+        assert_eq!(result.end.place_number, 4);
+        // The formula of the via place does not apply; only the end place's would
+        assert_eq!(
+            drinks(&result),
+            [("Siideri".to_string(), 4, 0), ("Kalja".to_string(), 4, 1)]
+        );
+
+        let mut board = sample_board();
+        let place = board.places.iter_mut().find(|p| p.place_number == 5);
+        place.unwrap().place.special = Some("99999*99999".to_string());
+        let result = resolve_turn(&board, &standing_on(4), TEAM, false, dice(1, 1)).unwrap();
+        assert_eq!(drinks(&result), [("Lonkero".to_string(), 200, 0)]);
+    }
+
+    #[test]
+    fn on_table_counts_come_from_the_place_and_the_via_place() {
+        let board = sample_board();
+        let place = board.find_place(4).unwrap();
+        let mut via = board.find_place(12).unwrap().clone();
+        via.drinks.drinks[0].drink = drink(1, "Kalja");
+        via.drinks.drinks[0].on_table = true;
+
+        let mut turn_drinks = TurnDrinks {
+            drinks: vec![
+                turn_drink(drink(1, "Kalja"), 3),
+                turn_drink(drink(3, "Lonkero"), 1),
+            ],
+        };
+        sync_drinks_on_table(Some(place), None, &mut turn_drinks);
+        assert_eq!(turn_drinks.drinks[0].on_table, 1);
+        sync_drinks_on_table(Some(place), Some(&via), &mut turn_drinks);
+        assert_eq!(turn_drinks.drinks[0].on_table, 2);
+        assert_eq!(turn_drinks.drinks[1].on_table, 0);
+
+        turn_drinks.drinks[0].n = 1;
+        sync_drinks_on_table(Some(place), Some(&via), &mut turn_drinks);
+        assert_eq!(turn_drinks.drinks[0].on_table, 1);
+    }
+
+    #[test]
+    fn confirm_outcome_follows_the_drinks() {
+        let board = sample_board();
+        let place = board.find_place(4).unwrap();
+        let mut kalja = turn_drink(drink(1, "Kalja"), 2);
+        let mut beer = drink(4, "Olut");
+        beer.no_mix_required = true;
+
+        let mut end = place.clone();
+        end.end = true;
+        let with = |drinks: Vec<TurnDrink>| TurnDrinks { drinks };
+        assert_eq!(
+            confirm_outcome(&end, &with(vec![kalja.clone()])),
+            ConfirmOutcome::EndGame
+        );
+        assert_eq!(
+            confirm_outcome(place, &with(vec![])),
+            ConfirmOutcome::EndTurn
+        );
+        assert_eq!(
+            confirm_outcome(place, &with(vec![kalja.clone()])),
+            ConfirmOutcome::Queued
+        );
+        assert_eq!(
+            confirm_outcome(place, &with(vec![turn_drink(beer, 1)])),
+            ConfirmOutcome::Mixing
+        );
+        kalja.on_table = 2;
+        assert_eq!(
+            confirm_outcome(place, &with(vec![kalja])),
+            ConfirmOutcome::Delivered
+        );
+    }
+
+    #[test]
+    fn penalty_drinks_always_go_through_the_queue() {
+        let mut kalja = turn_drink(drink(1, "Kalja"), 2);
+        kalja.on_table = 2;
+        let mut drinks = TurnDrinks {
+            drinks: vec![kalja],
+        };
+        assert_eq!(penalty_outcome(&mut drinks), ConfirmOutcome::Queued);
+        assert_eq!(drinks.drinks[0].on_table, 0);
+
+        drinks.drinks[0].drink.no_mix_required = true;
+        assert_eq!(penalty_outcome(&mut drinks), ConfirmOutcome::Mixing);
+    }
+}
@@ -0,0 +1,456 @@
+//! Static checks of a board graph, run by GET /boards/{id}/validate.
+//!
+//! The checks follow the movement rules of [`crate::engine::movement`]: teams move along
+//! forward connections, bounce back along non-on_land backward connections where the forward
+//! connections run out, and take the first on_land connection of the place they land on.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::engine::specials::place_rule;
+use crate::engine::turns::Dice;
+use crate::utils::state::AppError;
+use crate::utils::types::{
+    BoardDiagnostic, BoardPlace, BoardPlaces, DiagnosticCode, DiagnosticSeverity, Drinks,
+};
+
+/// The largest number of steps a single throw can move forwards.
+const MAX_THROW: i8 = 6;
+
+/// Runs every check on the board, returning the findings in place order.
+pub fn validate_board(board_places: &BoardPlaces, catalog: &Drinks) -> Vec<BoardDiagnostic> {
+    let mut places: Vec<&BoardPlace> = board_places.places.iter().collect();
+    places.sort_by_key(|p| p.place_number);
+    let by_number: HashMap<i32, &BoardPlace> =
+        places.iter().map(|p| (p.place_number, *p)).collect();
+
+    let mut diagnostics = Vec::new();
+    check_start_and_end(&places, &by_number, &mut diagnostics);
+    for place in &places {
+        check_connections(place, &by_number, &mut diagnostics);
+        check_special(place, &by_number, &mut diagnostics);
+        check_on_land(place, &by_number, &mut diagnostics);
+        check_drinks(place, catalog, &mut diagnostics);
+    }
+    diagnostics
+}
+
+fn error(code: DiagnosticCode, place_number: Option<i32>, message: String) -> BoardDiagnostic {
+    BoardDiagnostic {
+        severity: DiagnosticSeverity::Error,
+        code,
+        place_number,
+        message,
+    }
+}
+
+fn warning(code: DiagnosticCode, place_number: Option<i32>, message: String) -> BoardDiagnostic {
+    BoardDiagnostic {
+        severity: DiagnosticSeverity::Warning,
+        code,
+        place_number,
+        message,
+    }
+}
+
+/// Non-on_land backward connections, the only ones traversed when moving backwards.
+fn backward_targets(place: &BoardPlace) -> impl Iterator<Item = i32> + '_ {
+    place
+        .connections
+        .backwards
+        .iter()
+        .filter(|c| !c.on_land)
+        .map(|c| c.target)
+}
+
+/// The most steps a team can be moved backwards from the place by its special rule.
+fn max_backward_steps(place: &BoardPlace) -> Option<i8> {
+    let dice = Dice::new(6, 6, Some(6), Some(6));
+    place_rule(&place.place)
+        .ok()
+        .flatten()
+        .and_then(|rule| rule.backward_steps(&dice))
+}
+
+/// Whether movement can turn backwards on the place: at the end of the forward connections,
+/// or by a special rule such as `-D1`.
+fn moves_backwards(place: &BoardPlace) -> bool {
+    place.connections.forwards.is_empty() || max_backward_steps(place).is_some()
+}
+
+fn check_start_and_end(
+    places: &[&BoardPlace],
+    by_number: &HashMap<i32, &BoardPlace>,
+    diagnostics: &mut Vec<BoardDiagnostic>,
+) {
+    let starts: Vec<i32> = places
+        .iter()
+        .filter(|p| p.start)
+        .map(|p| p.place_number)
+        .collect();
+    match starts.as_slice() {
+        [] => diagnostics.push(error(
+            DiagnosticCode::NoStart,
+            None,
+            "The board has no start place".to_string(),
+        )),
+        [_] => {}
+        _ => diagnostics.push(error(
+            DiagnosticCode::MultipleStarts,
+            None,
+            format!("The board has several start places: {:?}", starts),
+        )),
+    }
+
+    if !places.iter().any(|p| p.end) {
+        diagnostics.push(error(
+            DiagnosticCode::NoEnd,
+            None,
+            "The board has no end place".to_string(),
+        ));
+    }
+
+    let Some(&start) = starts.first() else {
+        return;
+    };
+
+    // Places a team can reach from the start by any move
+    let mut reached = HashSet::from([start]);
+    let mut queue = VecDeque::from([start]);
+    while let Some(number) = queue.pop_front() {
+        let Some(place) = by_number.get(&number) else {
+            continue;
+        };
+        let forwards = place.connections.forwards.iter().map(|c| c.target);
+        let backwards = moves_backwards(place)
+            .then(|| backward_targets(place))
+            .into_iter()
+            .flatten();
+        for target in forwards.chain(backwards) {
+            if by_number.contains_key(&target) && reached.insert(target) {
+                queue.push_back(target);
+            }
+        }
+    }
+
+    if places.iter().any(|p| p.end)
+        && !places
+            .iter()
+            .any(|p| p.end && reached.contains(&p.place_number))
+    {
+        diagnostics.push(error(
+            DiagnosticCode::EndUnreachable,
+            Some(start),
+            format!("No end place can be reached from the start place {}", start),
+        ));
+    }
+    for place in places.iter().filter(|p| !reached.contains(&p.place_number)) {
+        diagnostics.push(warning(
+            DiagnosticCode::Unreachable,
+            Some(place.place_number),
+            format!(
+                "Place {} cannot be reached from the start place {}",
+                place.place_number, start
+            ),
+        ));
+    }
+}
+
+fn check_connections(
+    place: &BoardPlace,
+    by_number: &HashMap<i32, &BoardPlace>,
+    diagnostics: &mut Vec<BoardDiagnostic>,
+) {
+    let conns = &place.connections;
+    for conn in conns.forwards.iter().chain(&conns.backwards) {
+        if !by_number.contains_key(&conn.target) {
+            diagnostics.push(error(
+                DiagnosticCode::MissingTarget,
+                Some(place.place_number),
+                format!(
+                    "Place {} is connected to place {}, which is not on the board",
+                    place.place_number, conn.target
+                ),
+            ));
+        }
+    }
+
+    if place.end || !conns.forwards.is_empty() {
+        return;
+    }
+    if backward_targets(place).next().is_none() {
+        diagnostics.push(error(
+            DiagnosticCode::DeadEnd,
+            Some(place.place_number),
+            format!(
+                "Place {} is not an end place but has no way forward or back",
+                place.place_number
+            ),
+        ));
+    } else {
+        // The remaining steps of a throw bounce back from here
+        check_backwards_depth(place, MAX_THROW - 1, by_number, diagnostics);
+    }
+}
+
+fn check_special(
+    place: &BoardPlace,
+    by_number: &HashMap<i32, &BoardPlace>,
+    diagnostics: &mut Vec<BoardDiagnostic>,
+) {
+    if let Err(e) = place_rule(&place.place) {
+        let reason = match e {
+            AppError::Validation(message) => message,
+            e => e.to_string(),
+        };
+        diagnostics.push(error(
+            DiagnosticCode::InvalidSpecial,
+            Some(place.place_number),
+            format!(
+                "Place {} has an invalid special rule: {}",
+                place.place_number, reason
+            ),
+        ));
+    }
+    if let Some(steps) = max_backward_steps(place) {
+        check_backwards_depth(place, steps, by_number, diagnostics);
+    }
+}
+
+/// Checks that a team can move `steps` backwards from the place without running out of
+/// backward connections, which fails the move in [`crate::engine::movement::move_backwards`].
+fn check_backwards_depth(
+    place: &BoardPlace,
+    steps: i8,
+    by_number: &HashMap<i32, &BoardPlace>,
+    diagnostics: &mut Vec<BoardDiagnostic>,
+) {
+    let mut current = place;
+    for taken in 0..steps {
+        let next = backward_targets(current)
+            .next()
+            .and_then(|target| by_number.get(&target));
+        match next {
+            Some(next) => current = next,
+            None => {
+                diagnostics.push(error(
+                    DiagnosticCode::BackwardsTooShort,
+                    Some(place.place_number),
+                    format!(
+                        "A team may move {} steps back from place {}, but place {} has no way back after {} steps",
+                        steps, place.place_number, current.place_number, taken
+                    ),
+                ));
+                return;
+            }
+        }
+    }
+}
+
+fn check_on_land(
+    place: &BoardPlace,
+    by_number: &HashMap<i32, &BoardPlace>,
+    diagnostics: &mut Vec<BoardDiagnostic>,
+) {
+    let on_land: Vec<i32> = place
+        .connections
+        .forwards
+        .iter()
+        .filter(|c| c.on_land)
+        .map(|c| c.target)
+        .collect();
+    let Some(&first) = on_land.first() else {
+        return;
+    };
+    if on_land.len() > 1 {
+        diagnostics.push(warning(
+            DiagnosticCode::MultipleOnLand,
+            Some(place.place_number),
+            format!(
+                "Place {} has several on_land connections {:?}; only the first one is taken",
+                place.place_number, on_land
+            ),
+        ));
+    }
+
+    // Follow the chain of first on_land connections until it ends or comes back around
+    let mut chain = vec![place.place_number];
+    let mut next = first;
+    while let Some(target) = by_number.get(&next) {
+        if chain.contains(&target.place_number) {
+            chain.push(target.place_number);
+            diagnostics.push(error(
+                DiagnosticCode::OnLandLoop,
+                Some(place.place_number),
+                format!("The on_land connections {:?} form a loop", chain),
+            ));
+            return;
+        }
+        chain.push(target.place_number);
+        match target.connections.forwards.iter().find(|c| c.on_land) {
+            Some(conn) => next = conn.target,
+            None => break,
+        }
+    }
+    if chain.len() > 2 {
+        diagnostics.push(warning(
+            DiagnosticCode::ChainedOnLand,
+            Some(place.place_number),
+            format!(
+                "The on_land connections {:?} are chained; a move takes only the first one",
+                chain
+            ),
+        ));
+    }
+}
+
+fn check_drinks(place: &BoardPlace, catalog: &Drinks, diagnostics: &mut Vec<BoardDiagnostic>) {
+    for place_drink in &place.drinks.drinks {
+        let drink = &place_drink.drink;
+        if !catalog.drinks.iter().any(|d| d.id == drink.id) {
+            diagnostics.push(error(
+                DiagnosticCode::UnknownDrink,
+                Some(place.place_number),
+                format!(
+                    "Place {} has drink {} ({}), which does not exist",
+                    place.place_number, drink.id, drink.name
+                ),
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::fixtures::{board, drink, place, place_drink, sample_board};
+    use DiagnosticCode::*;
+
+    /// `places` numbered from 1, starting on the first and ending on the last.
+    fn line(places: i32, connections: &[(i32, i32, bool)]) -> BoardPlaces {
+        let places = (1..=places)
+            .map(|n| {
+                let mut place = place(n, "normal");
+                place.start = n == 1;
+                place.end = n == places;
+                place
+            })
+            .collect();
+        board(places, connections)
+    }
+
+    fn findings(board_places: &BoardPlaces) -> Vec<(DiagnosticCode, Option<i32>)> {
+        let catalog = Drinks {
+            drinks: vec![drink(1, "Kalja"), drink(2, "Siideri"), drink(3, "Lonkero")],
+        };
+        validate_board(board_places, &catalog)
+            .into_iter()
+            .map(|d| (d.code, d.place_number))
+            .collect()
+    }
+
+    #[test]
+    fn a_playable_board_has_no_findings() {
+        assert_eq!(findings(&line(3, &[(1, 2, false), (2, 3, false)])), []);
+
+        let mut sample = sample_board();
+        for place in &mut sample.places {
+            place.start = place.place_number == 1;
+            place.end = place.place_number == 6;
+        }
+        // -D1 on place 2 could move a team back 12 steps
+        assert_eq!(findings(&sample), [(BackwardsTooShort, Some(2))]);
+        sample.places[1].place.special = None;
+        assert_eq!(findings(&sample), []);
+    }
+
+    #[test]
+    fn needs_one_start_and_an_end() {
+        let mut board_places = line(2, &[(1, 2, false)]);
+        board_places.places[0].start = false;
+        board_places.places[1].end = false;
+        assert_eq!(
+            findings(&board_places),
+            [
+                (NoStart, None),
+                (NoEnd, None),
+                // Moves bounce back from place 2, but only one step fits
+                (BackwardsTooShort, Some(2)),
+            ]
+        );
+
+        let mut board_places = line(2, &[(1, 2, false)]);
+        board_places.places[1].start = true;
+        assert_eq!(findings(&board_places), [(MultipleStarts, None)]);
+    }
+
+    #[test]
+    fn finds_places_off_the_route() {
+        let board_places = line(4, &[(1, 2, false), (2, 4, false), (3, 2, false)]);
+        assert_eq!(findings(&board_places), [(Unreachable, Some(3))]);
+
+        let board_places = line(3, &[(1, 2, false)]);
+        assert_eq!(
+            findings(&board_places),
+            [
+                (EndUnreachable, Some(1)),
+                (Unreachable, Some(3)),
+                (BackwardsTooShort, Some(2)),
+            ]
+        );
+    }
+
+    #[test]
+    fn finds_broken_connections() {
+        let board_places = line(3, &[(1, 2, false), (2, 3, false), (2, 9, false)]);
+        assert_eq!(findings(&board_places), [(MissingTarget, Some(2))]);
+
+        // On_land connections are never taken back, so place 3 is stuck
+        let board_places = line(4, &[(1, 2, false), (2, 4, false), (1, 3, true)]);
+        assert_eq!(findings(&board_places), [(DeadEnd, Some(3))]);
+    }
+
+    #[test]
+    fn checks_special_rules() {
+        let mut board_places = line(3, &[(1, 2, false), (2, 3, false)]);
+        board_places.places[1].place.special = Some("2*D9".to_string());
+        assert_eq!(findings(&board_places), [(InvalidSpecial, Some(2))]);
+
+        board_places.places[1].place.special = Some("-D1".to_string());
+        assert_eq!(findings(&board_places), [(BackwardsTooShort, Some(2))]);
+    }
+
+    #[test]
+    fn checks_on_land_connections() {
+        let board_places = line(
+            4,
+            &[(1, 2, false), (2, 3, true), (2, 4, true), (3, 4, false)],
+        );
+        assert_eq!(findings(&board_places), [(MultipleOnLand, Some(2))]);
+
+        let board_places = line(4, &[(1, 2, false), (2, 3, true), (3, 4, true)]);
+        assert_eq!(findings(&board_places), [(ChainedOnLand, Some(2))]);
+
+        let board_places = line(
+            4,
+            &[(1, 2, false), (2, 3, true), (3, 2, true), (2, 4, false)],
+        );
+        assert_eq!(
+            findings(&board_places),
+            [(OnLandLoop, Some(2)), (OnLandLoop, Some(3))]
+        );
+    }
+
+    #[test]
+    fn finds_unknown_drinks() {
+        let mut board_places = line(2, &[(1, 2, false)]);
+        board_places.places[1].drinks.drinks = vec![place_drink(2, drink(9, "Absintti"), 1)];
+        let diagnostics = validate_board(&board_places, &Drinks { drinks: vec![] });
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, UnknownDrink);
+        assert_eq!(diagnostics[0].severity, DiagnosticSeverity::Error);
+        assert_eq!(
+            diagnostics[0].message,
+            "Place 2 has drink 9 (Absintti), which does not exist"
+        );
+    }
+}
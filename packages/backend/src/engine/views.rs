@@ -0,0 +1,312 @@
+//! Read-only projections of [`GameData`] for clients other than the referees.
+//!
+//! Teams and spectators are only shown confirmed turns, since a turn can still change or be
+//! cancelled before the referee confirms it.
+
+use crate::engine::lifecycle::TurnState;
+use crate::utils::ids::{DrinkId, IngredientId, TeamId};
+use crate::utils::types::{
+    BoardPlace, DrinkCount, DrinkPrepStatus, DrinkQueue, GameData, GameTeam, IngredientQty,
+    PendingDrinks, QueuedDrink, QueuedTurn, SpectatorGame, SpectatorTeam, TeamTurn, TeamView,
+    TeamWait, Turn, TurnLog, TurnLogEntry,
+};
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, HashMap};
+
+/// The view of a team of its own game, None if the team is not in the game.
+pub fn team_view(game_data: &GameData, team_id: TeamId) -> Option<TeamView> {
+    let team = game_data.teams.iter().find(|t| t.team.team_id == team_id)?;
+    let confirmed = confirmed_turns(team);
+
+    let pending = confirmed
+        .iter()
+        .filter_map(|turn| match TurnState::of(turn).prep_status() {
+            Some(DrinkPrepStatus::Delivered) | None => None,
+            Some(status) => Some(PendingDrinks {
+                turn_id: turn.turn_id,
+                status,
+                drinks: served_drinks(turn),
+            }),
+        })
+        .filter(|p| !p.drinks.is_empty())
+        .collect();
+
+    Some(TeamView {
+        game_id: game_data.game.id,
+        game_name: game_data.game.name.clone(),
+        started: game_data.game.started,
+        finished: game_data.game.finished,
+        team_id,
+        team_name: team.team.team_name.clone(),
+        location: location(&confirmed),
+        double_tampere: double_tampere(&confirmed),
+        pending,
+        turns: confirmed.iter().map(|turn| team_turn(turn)).collect(),
+    })
+}
+
+/// The view of spectators of a game.
+pub fn spectator_view(game_data: &GameData) -> SpectatorGame {
+    SpectatorGame {
+        game: game_data.game.clone(),
+        teams: game_data
+            .teams
+            .iter()
+            .map(|team| {
+                let confirmed = confirmed_turns(team);
+                SpectatorTeam {
+                    team_id: team.team.team_id,
+                    team_name: team.team.team_name.clone(),
+                    location: location(&confirmed),
+                    double_tampere: double_tampere(&confirmed),
+                    turns: confirmed.iter().map(|turn| team_turn(turn)).collect(),
+                }
+            })
+            .collect(),
+    }
+}
+
+/// The drink queue of the IE at `now`, with the ingredients of each drink taken from
+/// `recipes`.
+pub fn drink_queue(
+    game_data: &GameData,
+    recipes: &HashMap<DrinkId, Vec<IngredientQty>>,
+    now: DateTime<Utc>,
+) -> DrinkQueue {
+    let mut turns: Vec<QueuedTurn> = queued_turns(game_data)
+        .map(|(team, turn, status, drinks)| QueuedTurn {
+            turn_id: turn.turn_id,
+            team_id: team.team.team_id,
+            team_name: team.team.team_name.clone(),
+            status,
+            confirmed_at: turn.confirmed_at.unwrap_or(turn.start_time),
+            mixing_at: turn.mixing_at,
+            mixed_at: turn.mixed_at,
+            drinks: drinks
+                .into_iter()
+                .map(|dc| QueuedDrink {
+                    ingredients: recipes.get(&dc.drink.id).cloned().unwrap_or_default(),
+                    drink: dc.drink,
+                    n: dc.n,
+                })
+                .collect(),
+        })
+        .collect();
+    turns.sort_by_key(|t| (t.confirmed_at, t.turn_id));
+
+    // Drinks already mixed no longer need their ingredients
+    let mut totals: BTreeMap<IngredientId, IngredientQty> = BTreeMap::new();
+    for turn in turns.iter().filter(|t| t.status != DrinkPrepStatus::Mixed) {
+        for drink in &turn.drinks {
+            for iq in &drink.ingredients {
+                totals
+                    .entry(iq.ingredient.id)
+                    .or_insert_with(|| IngredientQty {
+                        ingredient: iq.ingredient.clone(),
+                        quantity: 0.0,
+                    })
+                    .quantity += iq.quantity * drink.n as f64;
+            }
+        }
+    }
+
+    // Turns are in order of confirmation, so the first turn of a team is its longest wait
+    let mut teams: Vec<TeamWait> = Vec::new();
+    for turn in &turns {
+        match teams.iter_mut().find(|t| t.team_id == turn.team_id) {
+            Some(wait) => wait.turns += 1,
+            None => teams.push(TeamWait {
+                team_id: turn.team_id,
+                team_name: turn.team_name.clone(),
+                turns: 1,
+                waiting_since: turn.confirmed_at,
+                waiting_seconds: (now - turn.confirmed_at).num_seconds().max(0),
+            }),
+        }
+    }
+
+    DrinkQueue {
+        game_id: game_data.game.id,
+        turns,
+        ingredient_totals: totals.into_values().collect(),
+        teams,
+    }
+}
+
+/// The drinks waiting in the drink queue of the IE.
+pub fn queued_drinks(game_data: &GameData) -> Vec<DrinkId> {
+    let mut ids: Vec<DrinkId> = queued_turns(game_data)
+        .flat_map(|(_, _, _, drinks)| drinks.into_iter().map(|d| d.drink.id))
+        .collect();
+    ids.sort();
+    ids.dedup();
+    ids
+}
+
+/// Confirmed turns with drinks to prepare that have not been delivered yet.
+fn queued_turns(
+    game_data: &GameData,
+) -> impl Iterator<Item = (&GameTeam, &Turn, DrinkPrepStatus, Vec<DrinkCount>)> {
+    game_data
+        .teams
+        .iter()
+        .flat_map(|team| team.turns.iter().map(move |turn| (team, turn)))
+        .filter_map(|(team, turn)| match TurnState::of(turn).prep_status() {
+            Some(DrinkPrepStatus::Delivered) | None => None,
+            Some(status) => Some((team, turn, status, drinks_to_mix(turn))),
+        })
+        .filter(|(_, _, _, drinks)| !drinks.is_empty())
+}
+
+/// The turn log of the secretary.
+pub fn turn_log(game_data: &GameData) -> TurnLog {
+    let mut turns: Vec<TurnLogEntry> = game_data
+        .teams
+        .iter()
+        .flat_map(|team| {
+            team.turns.iter().map(|turn| TurnLogEntry {
+                turn_id: turn.turn_id,
+                team_id: team.team.team_id,
+                team_name: team.team.team_name.clone(),
+                penalty: turn.penalty,
+                start_time: turn.start_time,
+                thrown_at: turn.thrown_at,
+                confirmed_at: turn.confirmed_at,
+                delivered_at: turn.delivered_at,
+                end_time: turn.end_time,
+                dice1: turn.dice1,
+                dice2: turn.dice2,
+                dice3: turn.dice3,
+                dice4: turn.dice4,
+                place_number: turn.place_number,
+                place_name: turn.place.as_ref().map(|p| p.place.place_name.clone()),
+                drinks: served_drinks(turn),
+            })
+        })
+        .collect();
+    turns.sort_by_key(|t| std::cmp::Reverse((t.start_time, t.turn_id)));
+    TurnLog {
+        game_id: game_data.game.id,
+        turns,
+    }
+}
+
+fn confirmed_turns(team: &GameTeam) -> Vec<&Turn> {
+    team.turns
+        .iter()
+        .filter(|t| t.confirmed_at.is_some())
+        .collect()
+}
+
+fn location(confirmed: &[&Turn]) -> Option<BoardPlace> {
+    confirmed.iter().rev().find_map(|t| t.place.clone())
+}
+
+fn double_tampere(confirmed: &[&Turn]) -> bool {
+    confirmed
+        .iter()
+        .rev()
+        .find_map(|t| t.double_tampere)
+        .unwrap_or(false)
+}
+
+fn team_turn(turn: &Turn) -> TeamTurn {
+    TeamTurn {
+        turn_id: turn.turn_id,
+        penalty: turn.penalty,
+        start_time: turn.start_time,
+        confirmed_at: turn.confirmed_at,
+        delivered_at: turn.delivered_at,
+        end_time: turn.end_time,
+        dice1: turn.dice1,
+        dice2: turn.dice2,
+        place_number: turn.place_number,
+        place_name: turn.place.as_ref().map(|p| p.place.place_name.clone()),
+        drinks: served_drinks(turn),
+    }
+}
+
+/// The drinks of a turn that the IE still has to prepare: the servings already on the
+/// table are picked up from there.
+fn drinks_to_mix(turn: &Turn) -> Vec<DrinkCount> {
+    turn.drinks
+        .drinks
+        .iter()
+        .map(|td| DrinkCount {
+            drink: td.drink.clone(),
+            n: td.n - td.on_table,
+        })
+        .filter(|dc| dc.n > 0)
+        .collect()
+}
+
+/// The drinks of a turn that are actually served.
+fn served_drinks(turn: &Turn) -> Vec<DrinkCount> {
+    turn.drinks
+        .drinks
+        .iter()
+        .filter(|td| td.n > 0)
+        .map(|td| DrinkCount {
+            drink: td.drink.clone(),
+            n: td.n,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::fixtures::{at, drink, game, game_team, ingredient, turn, turn_drink};
+    use crate::utils::types::TurnDrink;
+
+    #[test]
+    fn servings_on_the_table_are_not_queued() {
+        let kalja = drink(1, "Kalja");
+        let lonkero = drink(2, "Lonkero");
+        let mut queued = turn(1, 1, Some(4));
+        queued.drinks.drinks = vec![
+            TurnDrink {
+                on_table: 1,
+                ..turn_drink(kalja.clone(), 3)
+            },
+            TurnDrink {
+                on_table: 1,
+                ..turn_drink(lonkero.clone(), 1)
+            },
+        ];
+        let mut on_table = turn(2, 2, Some(4));
+        on_table.drinks.drinks = vec![TurnDrink {
+            on_table: 1,
+            ..turn_drink(kalja.clone(), 1)
+        }];
+        let data = GameData {
+            game: game(),
+            teams: vec![game_team(1, vec![queued]), game_team(2, vec![on_table])],
+        };
+        let recipes = HashMap::from([
+            (kalja.id, vec![ingredient(1, "Olut", 0.33)]),
+            (lonkero.id, vec![ingredient(2, "Lonkero", 0.33)]),
+        ]);
+
+        assert_eq!(queued_drinks(&data), [kalja.id]);
+        let queue = drink_queue(&data, &recipes, at(5));
+        let turns: Vec<_> = queue
+            .turns
+            .iter()
+            .map(|t| {
+                (
+                    t.turn_id.0,
+                    t.drinks.iter().map(|d| d.n).collect::<Vec<_>>(),
+                )
+            })
+            .collect();
+        assert_eq!(turns, [(1, vec![2])]);
+        let totals: Vec<_> = queue
+            .ingredient_totals
+            .iter()
+            .map(|iq| (iq.ingredient.name.as_str(), iq.quantity))
+            .collect();
+        assert_eq!(totals, [("Olut", 0.66)]);
+        assert_eq!(queue.teams.len(), 1);
+    }
+}
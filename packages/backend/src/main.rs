@@ -1,12 +1,23 @@
 #![allow(unreachable_code)]
 
 mod api;
+mod cli;
 mod database;
+mod engine;
 mod login;
 mod server;
 mod utils;
 
 #[tokio::main]
 async fn main() {
-    server::start().await.unwrap()
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("simulate") => {
+            if let Err(e) = cli::run_simulate(&args[1..]).await {
+                eprintln!("{:#}", e);
+                std::process::exit(1);
+            }
+        }
+        _ => server::start().await.unwrap(),
+    }
 }
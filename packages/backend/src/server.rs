@@ -11,7 +11,9 @@ use axum::{middleware, routing::get, Router};
 use socketioxide::SocketIo;
 use tracing_subscriber::FmtSubscriber;
 
+use crate::utils::socket::GameVersions;
 use crate::utils::state::{all_middleware, AppState, SocketState};
+use std::sync::Arc;
 
 pub async fn start() -> anyhow::Result<()> {
     tracing::subscriber::set_global_default(FmtSubscriber::default())?;
@@ -53,8 +55,11 @@ pub async fn start() -> anyhow::Result<()> {
     let bind = format!("0.0.0.0:{}", port);
     println!("\nServer started at port {}", port);
 
+    // Versions of the games sent to referees, shared by websocket handlers and REST endpoints
+    let versions = Arc::new(GameVersions::default());
+
     // Create SocketState for websocket handlers (avoids circular dependency)
-    let socket_state = SocketState::new(pool);
+    let socket_state = SocketState::new(pool, versions.clone());
 
     // Build SocketIo with SocketState
     let (layer, io) = SocketIo::builder().with_state(socket_state).build_layer();
@@ -63,10 +68,13 @@ pub async fn start() -> anyhow::Result<()> {
     let state = AppState::new(
         make_pool(&db_url).expect("Failed to create pool for app state"),
         io.clone(),
+        versions,
     );
 
-    // Register websocket namespace
+    // Register websocket namespaces
     io.ns("/referee", websocket::referee::referee_on_connect);
+    io.ns("/team", websocket::team::team_on_connect);
+    io.ns("/spectate", websocket::spectate::spectate_on_connect);
 
     let app = Router::new()
         .route(
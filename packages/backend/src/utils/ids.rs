@@ -3,7 +3,7 @@ use tokio_postgres::types::{FromSql, IsNull, ToSql, Type};
 
 macro_rules! define_id {
     ($name:ident) => {
-        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
         pub struct $name(pub i32);
 
         impl Serialize for $name {
@@ -69,3 +69,7 @@ define_id!(BoardId);
 define_id!(PlaceId);
 define_id!(DrinkId);
 define_id!(IngredientId);
+define_id!(ActionId);
+define_id!(EventId);
+define_id!(PlayerId);
+define_id!(RestockId);
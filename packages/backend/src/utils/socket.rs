@@ -1,8 +1,128 @@
+use crate::api::websocket::utils::{advance_version, ServerResponse};
 use crate::database::login::check_session;
+use crate::utils::ids::{ActionId, GameId};
 use crate::utils::state::SocketState;
-use crate::utils::types::UserType;
+use crate::utils::types::{GameData, UserType, VersionedGameData};
 use socketioxide::adapter::Adapter;
 use socketioxide::extract::SocketRef;
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// Allows an action at most once per interval for each key, e.g. each socket.
+pub struct RateLimiter {
+    interval: Duration,
+    last: Mutex<HashMap<String, Instant>>,
+}
+
+impl RateLimiter {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            last: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Records an attempt, returning false if the previous one of the key was too recent.
+    pub fn check(&self, key: &str) -> bool {
+        let now = Instant::now();
+        let mut last = self.last.lock().unwrap_or_else(|e| e.into_inner());
+        match last.get(key) {
+            Some(&previous) if now.duration_since(previous) < self.interval => false,
+            _ => {
+                last.insert(key.to_string(), now);
+                true
+            }
+        }
+    }
+
+    /// Forgets a key, e.g. when its socket disconnects.
+    pub fn forget(&self, key: &str) {
+        self.last
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(key);
+    }
+}
+
+/// The latest version of a game sent to the referees, with the latest action of the game when
+/// its data was read.
+struct GameVersion {
+    action: Option<ActionId>,
+    versioned: VersionedGameData,
+}
+
+/// The latest version of each game followed by referees, from which the next deltas are
+/// computed. Versions are ordered by the action they were read after: data read after an older
+/// action than the current version is dropped, as the version already includes that action.
+/// The map is only locked to compute deltas, never while they are emitted; referees that see
+/// a gap in the sequence ask for a resync.
+#[derive(Default)]
+pub struct GameVersions {
+    games: Mutex<HashMap<GameId, GameVersion>>,
+}
+
+impl GameVersions {
+    fn lock(&self) -> MutexGuard<'_, HashMap<GameId, GameVersion>> {
+        self.games.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Moves a game to data read after `action`, returning the deltas for the referees.
+    /// A game no referee follows anymore is forgot.
+    pub fn advance(
+        &self,
+        game_id: GameId,
+        action: Option<ActionId>,
+        data: &GameData,
+        followed: impl FnOnce() -> bool,
+    ) -> Vec<ServerResponse> {
+        let mut games = self.lock();
+        if !followed() {
+            games.remove(&game_id);
+            return Vec::new();
+        }
+        match games.get_mut(&game_id) {
+            Some(version) if action >= version.action => {
+                version.action = action;
+                advance_version(&mut version.versioned, data)
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// The version of a game for a referee subscribing to it, read after `action`, with the
+    /// deltas for the referees already following it.
+    pub fn snapshot(
+        &self,
+        game_id: GameId,
+        action: Option<ActionId>,
+        data: GameData,
+    ) -> (Vec<ServerResponse>, VersionedGameData) {
+        let mut games = self.lock();
+        let deltas = match games.get_mut(&game_id) {
+            Some(version) if action >= version.action => {
+                version.action = action;
+                advance_version(&mut version.versioned, &data)
+            }
+            Some(_) => Vec::new(),
+            None => {
+                games.insert(
+                    game_id,
+                    GameVersion {
+                        action,
+                        versioned: VersionedGameData {
+                            game_id,
+                            seq: 0,
+                            data,
+                        },
+                    },
+                );
+                Vec::new()
+            }
+        };
+        (deltas, games[&game_id].versioned.clone())
+    }
+}
 
 /// Check if the user has any of the allowed user types.
 pub async fn check_auth_any<A: Adapter>(
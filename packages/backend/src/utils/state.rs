@@ -1,4 +1,5 @@
 use crate::database::login::check_session;
+use crate::utils::socket::{GameVersions, RateLimiter};
 use crate::utils::types::{PgError, SessionInfo};
 use axum::body::Body;
 use axum::extract::{FromRequestParts, OptionalFromRequestParts, State};
@@ -13,6 +14,8 @@ use http::request::Parts;
 use http::{Method, Request};
 use serde::Serialize;
 use socketioxide::SocketIo;
+use std::sync::Arc;
+use std::time::Duration;
 use thiserror::Error;
 
 /// State for Axum routes - includes SocketIo for emitting from REST endpoints.
@@ -20,11 +23,13 @@ use thiserror::Error;
 pub struct AppState {
     pub db: Pool,
     pub io: SocketIo,
+    /// shared with [`SocketState`]
+    pub versions: Arc<GameVersions>,
 }
 
 impl AppState {
-    pub fn new(db: Pool, io: SocketIo) -> Self {
-        Self { db, io }
+    pub fn new(db: Pool, io: SocketIo, versions: Arc<GameVersions>) -> Self {
+        Self { db, io, versions }
     }
 }
 
@@ -33,14 +38,25 @@ impl AppState {
 #[derive(Clone)]
 pub struct SocketState {
     pub db: Pool,
+    /// limits how often an anonymous spectator may subscribe to a game
+    pub spectate_limiter: Arc<RateLimiter>,
+    /// latest versions of the games followed by referees
+    pub versions: Arc<GameVersions>,
 }
 
 impl SocketState {
-    pub fn new(db: Pool) -> Self {
-        Self { db }
+    pub fn new(db: Pool, versions: Arc<GameVersions>) -> Self {
+        Self {
+            db,
+            versions,
+            spectate_limiter: Arc::new(RateLimiter::new(SPECTATE_SUBSCRIBE_INTERVAL)),
+        }
     }
 }
 
+/// Shortest time between two subscribes of a spectator socket.
+const SPECTATE_SUBSCRIBE_INTERVAL: Duration = Duration::from_secs(1);
+
 #[derive(Serialize)]
 struct ErrorBody {
     error: String,
@@ -56,8 +72,8 @@ pub enum AppError {
     Conflict(String),
     #[error("not found: {0}")]
     NotFound(String),
-    // #[error("rate limited")]
-    // RateLimited,
+    #[error("rate limited")]
+    RateLimited,
     // #[error("internal error")]
     // Internal,
     #[error("unauthorized: {0}")]
@@ -73,7 +89,7 @@ impl IntoResponse for AppError {
             AppError::Database(m) => (StatusCode::INTERNAL_SERVER_ERROR, m),
             AppError::Conflict(m) => (StatusCode::CONFLICT, m),
             AppError::NotFound(m) => (StatusCode::NOT_FOUND, m),
-            // AppError::RateLimited => (StatusCode::TOO_MANY_REQUESTS, "too many requests".into()),
+            AppError::RateLimited => (StatusCode::TOO_MANY_REQUESTS, "too many requests".into()),
             // AppError::Internal => (
             //     StatusCode::INTERNAL_SERVER_ERROR,
             //     "internal server error".into(),
@@ -90,7 +106,7 @@ fn self_code(err: &AppError) -> &'static str {
         AppError::Database(_) => "Database",
         AppError::Conflict(_) => "Conflict",
         AppError::NotFound(_) => "NotFound",
-        // AppError::RateLimited => "RateLimited",
+        AppError::RateLimited => "RateLimited",
         // AppError::Internal => "Internal",
         AppError::Unauthorized(_) => "Unauthorized",
     }
@@ -1,6 +1,9 @@
 use std::cmp::min;
 
-use crate::utils::ids::{BoardId, DrinkId, GameId, IngredientId, PlaceId, TeamId, TurnId, UserId};
+use crate::utils::ids::{
+    ActionId, BoardId, DrinkId, GameId, IngredientId, PlaceId, PlayerId, RestockId, TeamId, TurnId,
+    UserId,
+};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use tokio_postgres::types::{FromSql, ToSql};
@@ -23,6 +26,12 @@ pub enum PlaceType {
     Guild,
 }
 
+/// Connection data of the /team websocket namespace.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct TeamSocketAuth {
+    pub team_hash: String,
+}
+
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct SocketAuth {
     pub token: String,
@@ -138,6 +147,8 @@ pub struct Team {
     pub team_name: String,
     pub team_hash: String,
     pub moral_victory_eligible: bool,
+    /// whether the drinks of the team are swapped for their non-alcoholic equivalents
+    pub sober: bool,
 }
 
 /// Request body for creating or updating a team's name.
@@ -146,6 +157,28 @@ pub struct TeamNameUpdate {
     pub team_name: String,
 }
 
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct Player {
+    pub player_id: PlayerId,
+    pub team_id: TeamId,
+    pub player_name: String,
+    /// whether the player drinks only non-alcoholic drinks
+    pub sober: bool,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct Players {
+    pub players: Vec<Player>,
+}
+
+/// Request body for adding or updating a player.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct PlayerUpdate {
+    pub player_name: String,
+    #[serde(default)]
+    pub sober: bool,
+}
+
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct TurnDrink {
     pub drink: Drink,
@@ -154,6 +187,33 @@ pub struct TurnDrink {
     pub on_table: i32,
     /// whether this drink amount is expected to be modified by the assistant referee; not used after confirming the turn
     pub optional: bool,
+    /// the drink this one replaced, for a sober team or over a drinking limit
+    pub substituted_for: Option<DrinkId>,
+    /// servings attributed to players; set with PUT /turns/{id}/players and cleared whenever
+    /// the drinks of the turn change
+    #[serde(default, skip_deserializing)]
+    pub players: Vec<PlayerServing>,
+}
+
+/// Servings of a turn drink that a player drank.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct PlayerServing {
+    pub player_id: PlayerId,
+    pub n: i32,
+}
+
+/// Servings of a drink of a turn that a player drank, as attributed by the referee.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct PlayerDrink {
+    pub drink_id: DrinkId,
+    pub player_id: PlayerId,
+    pub n: i32,
+}
+
+/// Request body for PUT /turns/{turn_id}/players; replaces the attribution of the turn.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct AttributeDrinksBody {
+    pub drinks: Vec<PlayerDrink>,
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
@@ -179,15 +239,35 @@ pub struct GameTeam {
     pub turns: Vec<Turn>,
     pub location: Option<BoardPlace>,
     pub double_tampere: bool,
+    #[serde(default)]
+    pub alcohol: AlcoholIntake,
+    #[serde(default)]
+    pub players: Vec<GamePlayer>,
 }
 
-/// Lightweight version of GameTeam with only the latest turn
 #[derive(Clone, Serialize, Deserialize, Debug)]
-pub struct TeamLatestTurn {
-    pub team: Team,
-    pub latest_turn: Option<Turn>,
-    pub location: Option<BoardPlace>,
-    pub double_tampere: bool,
+pub struct GamePlayer {
+    pub player: Player,
+    /// alcohol of the servings attributed to the player
+    pub alcohol: AlcoholIntake,
+}
+
+/// Pure alcohol served to a team in confirmed turns. A standard drink is 12 g of ethanol.
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+pub struct AlcoholIntake {
+    pub ethanol_grams: f64,
+    pub standard_drinks: f64,
+    /// running totals after each confirmed turn that served alcohol, in confirmation order
+    pub timeline: Vec<AlcoholPoint>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct AlcoholPoint {
+    pub turn_id: TurnId,
+    /// when the turn was confirmed
+    pub at: DateTime<Utc>,
+    pub ethanol_grams: f64,
+    pub standard_drinks: f64,
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
@@ -225,6 +305,8 @@ pub struct Turn {
     pub penalty: bool,
     /// whether double tampere multiplier applies (set on confirm)
     pub double_tampere: Option<bool>,
+    /// whether the turn was confirmed over a drinking limit of the game
+    pub over_limit: bool,
     pub drinks: TurnDrinks,
     /// the board place this turn ended on (if location is set)
     pub place: Option<BoardPlace>,
@@ -258,6 +340,71 @@ pub struct ChangeDiceBody {
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct ConfirmTurnBody {
     pub drinks: TurnDrinks,
+    /// confirm even if the turn exceeds a drinking limit that requires a referee override
+    #[serde(default)]
+    pub override_limits: bool,
+}
+
+/// What happens when confirming a turn would exceed a drinking limit of the game.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, ToSql, FromSql, PartialEq, Eq)]
+#[postgres(name = "limitaction")]
+pub enum LimitAction {
+    /// the turn is confirmed and flagged as over the limit
+    #[postgres(name = "Flag")]
+    Flag,
+    /// the alcoholic drinks of the turn are replaced with their non-alcoholic equivalents
+    #[postgres(name = "Substitute")]
+    Substitute,
+    /// the turn is only confirmed with `override_limits`
+    #[postgres(name = "RequireOverride")]
+    RequireOverride,
+}
+
+/// Drinking limits of a game in standard drinks per team, for GET/PUT /games/{id}/limits.
+/// Limits that are null are not enforced.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct DrinkingLimits {
+    /// standard drinks within any 60 minutes
+    pub per_hour: Option<f64>,
+    /// standard drinks over the whole game
+    pub per_game: Option<f64>,
+    /// standard drinks in a single turn
+    pub per_turn: Option<f64>,
+    pub action: LimitAction,
+    /// non-alcoholic drink served instead of alcoholic drinks without a non-alcoholic
+    /// equivalent when `action` is Substitute
+    pub substitute_drink: Option<DrinkId>,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum LimitPeriod {
+    /// any 60 minutes
+    Hour,
+    Game,
+    Turn,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct LimitBreach {
+    pub period: LimitPeriod,
+    pub limit: f64,
+    /// standard drinks the limit counts including the turn
+    pub standard_drinks: f64,
+}
+
+/// Sent as `limit-alert` to the game room of the referee namespace when a turn is confirmed
+/// over a drinking limit, or its confirmation is refused for one.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct LimitAlert {
+    pub game_id: GameId,
+    pub team_id: TeamId,
+    pub turn_id: TurnId,
+    /// what was done about it; RequireOverride means a referee overrode the limit, unless
+    /// `blocked`
+    pub action: LimitAction,
+    /// whether the confirmation was refused until a referee overrides the limit
+    pub blocked: bool,
+    pub breaches: Vec<LimitBreach>,
 }
 
 /// Status of drink preparation for a turn
@@ -275,6 +422,13 @@ pub struct SetDrinkPrepStatusBody {
     pub status: DrinkPrepStatus,
 }
 
+/// Request body for PUT /games/{game_id}/drink-queue
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct BatchPrepStatusBody {
+    pub turn_ids: Vec<TurnId>,
+    pub status: DrinkPrepStatus,
+}
+
 /// Request body for POST /teams/{team_id}/teleport
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct TeleportTeamBody {
@@ -302,10 +456,267 @@ pub struct Games {
     pub games: Vec<Game>,
 }
 
+/// Response of GET /games/{id}/stats. Only confirmed turns are counted.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct GameStats {
+    pub game_id: GameId,
+    /// teams ordered by rank
+    pub teams: Vec<TeamStats>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct TeamStats {
+    pub team_id: TeamId,
+    pub team_name: String,
+    /// position in the current ranking; teams equally far from the end share a rank
+    pub rank: i32,
+    /// current place of the team, if it has one
+    pub place_number: Option<i32>,
+    /// fewest forward steps from the current place to an end place
+    pub steps_to_end: Option<i32>,
+    /// thrown turns, penalties not included
+    pub turns: i32,
+    pub penalties: i32,
+    pub drinks: Vec<DrinkCount>,
+    pub total_drinks: i32,
+    pub ethanol_grams: f64,
+    pub standard_drinks: f64,
+    /// standard drinks per hour since the start of the game
+    pub standard_drinks_per_hour: f64,
+    /// average seconds from confirming a turn until IE started mixing its drinks
+    pub avg_queue_secs: Option<f64>,
+    /// average seconds IE spent mixing the drinks of a turn
+    pub avg_mixing_secs: Option<f64>,
+    /// average seconds from mixed drinks until they were delivered
+    pub avg_delivery_secs: Option<f64>,
+    pub players: Vec<PlayerStats>,
+}
+
+/// Drinks attributed to a player in confirmed turns.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct PlayerStats {
+    pub player_id: PlayerId,
+    pub player_name: String,
+    pub drinks: Vec<DrinkCount>,
+    pub total_drinks: i32,
+    pub ethanol_grams: f64,
+    pub standard_drinks: f64,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct DrinkCount {
+    pub drink: Drink,
+    pub n: i32,
+}
+
+/// A team's own view of its game, served by GET /public/teams/{team_hash} and the /team
+/// websocket namespace. Other teams and referee internals are left out.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct TeamView {
+    pub game_id: GameId,
+    pub game_name: String,
+    pub started: bool,
+    pub finished: bool,
+    pub team_id: TeamId,
+    pub team_name: String,
+    /// place of the latest confirmed turn
+    pub location: Option<BoardPlace>,
+    pub double_tampere: bool,
+    /// drinks of confirmed turns that have not been delivered yet
+    pub pending: Vec<PendingDrinks>,
+    /// confirmed turns, oldest first
+    pub turns: Vec<TeamTurn>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct PendingDrinks {
+    pub turn_id: TurnId,
+    pub status: DrinkPrepStatus,
+    pub drinks: Vec<DrinkCount>,
+}
+
+/// What spectators see of a game through the /spectate websocket namespace: the positions
+/// and confirmed turns of the teams, without team hashes or referee internals.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct SpectatorGame {
+    pub game: Game,
+    pub teams: Vec<SpectatorTeam>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct SpectatorTeam {
+    pub team_id: TeamId,
+    pub team_name: String,
+    /// place of the latest confirmed turn
+    pub location: Option<BoardPlace>,
+    pub double_tampere: bool,
+    /// confirmed turns, oldest first
+    pub turns: Vec<TeamTurn>,
+}
+
+/// A confirmed turn as teams and spectators see it.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct TeamTurn {
+    pub turn_id: TurnId,
+    pub penalty: bool,
+    pub start_time: DateTime<Utc>,
+    pub confirmed_at: Option<DateTime<Utc>>,
+    pub delivered_at: Option<DateTime<Utc>>,
+    pub end_time: Option<DateTime<Utc>>,
+    pub dice1: Option<i32>,
+    pub dice2: Option<i32>,
+    pub place_number: Option<i32>,
+    pub place_name: Option<String>,
+    pub drinks: Vec<DrinkCount>,
+}
+
+/// The drink queue of a game, served by GET /games/{game_id}/drink-queue and sent to the IE
+/// channel of the game as drink-queue: the confirmed turns whose drinks have not been
+/// delivered yet, oldest confirmation first.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct DrinkQueue {
+    pub game_id: GameId,
+    pub turns: Vec<QueuedTurn>,
+    /// ingredients needed for the queued drinks that are not mixed yet
+    pub ingredient_totals: Vec<IngredientQty>,
+    /// teams waiting for drinks, longest wait first
+    pub teams: Vec<TeamWait>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct TeamWait {
+    pub team_id: TeamId,
+    pub team_name: String,
+    /// queued turns of the team
+    pub turns: i32,
+    /// confirmation of the oldest queued turn of the team
+    pub waiting_since: DateTime<Utc>,
+    pub waiting_seconds: i64,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct QueuedTurn {
+    pub turn_id: TurnId,
+    pub team_id: TeamId,
+    pub team_name: String,
+    pub status: DrinkPrepStatus,
+    pub confirmed_at: DateTime<Utc>,
+    pub mixing_at: Option<DateTime<Utc>>,
+    pub mixed_at: Option<DateTime<Utc>>,
+    pub drinks: Vec<QueuedDrink>,
+}
+
+/// A drink to serve, with the ingredients of one serving.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct QueuedDrink {
+    pub drink: Drink,
+    pub n: i32,
+    pub ingredients: Vec<IngredientQty>,
+}
+
+/// What the secretary channel of a game receives as turn-log: every turn of the game,
+/// newest first.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct TurnLog {
+    pub game_id: GameId,
+    pub turns: Vec<TurnLogEntry>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct TurnLogEntry {
+    pub turn_id: TurnId,
+    pub team_id: TeamId,
+    pub team_name: String,
+    pub penalty: bool,
+    pub start_time: DateTime<Utc>,
+    pub thrown_at: Option<DateTime<Utc>>,
+    pub confirmed_at: Option<DateTime<Utc>>,
+    pub delivered_at: Option<DateTime<Utc>>,
+    pub end_time: Option<DateTime<Utc>>,
+    pub dice1: Option<i32>,
+    pub dice2: Option<i32>,
+    pub dice3: Option<i32>,
+    pub dice4: Option<i32>,
+    pub place_number: Option<i32>,
+    pub place_name: Option<String>,
+    pub drinks: Vec<DrinkCount>,
+}
+
+/// The data of a game at a sequence number, sent to a referee socket as game-snapshot when it
+/// subscribes or asks for a resync. Every later delta of the game has the next number.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct VersionedGameData {
+    pub game_id: GameId,
+    pub seq: u64,
+    pub data: GameData,
+}
+
+/// Sent as game-updated when the game itself changed.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct GameDelta {
+    pub game_id: GameId,
+    pub seq: u64,
+    pub game: Game,
+}
+
+/// Sent as team-updated when a team was added, changed or removed.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct TeamDelta {
+    pub game_id: GameId,
+    pub seq: u64,
+    pub team_id: TeamId,
+    /// the team with its turns left empty, null if the team was removed
+    pub team: Option<GameTeam>,
+}
+
+/// Sent as turn-updated when a turn was added, changed or removed.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct TurnDelta {
+    pub game_id: GameId,
+    pub seq: u64,
+    pub team_id: TeamId,
+    pub turn_id: TurnId,
+    /// null if the turn was removed
+    pub turn: Option<Turn>,
+}
+
+/// An entry in the referee action log of a game.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct GameAction {
+    pub action_id: ActionId,
+    pub game_id: GameId,
+    /// user who performed the action
+    pub uid: Option<UserId>,
+    /// e.g. "start_turn", "confirm_turn", "undo", "redo"
+    pub action: String,
+    /// the action this entry reverts (set for undo and redo)
+    pub reverts: Option<ActionId>,
+    /// teams whose state the action changed
+    pub team_ids: Vec<TeamId>,
+    pub created_at: DateTime<Utc>,
+    /// whether a later undo (or redo) has reverted this action
+    pub undone: bool,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct GameActions {
+    pub actions: Vec<GameAction>,
+}
+
+/// Request body for POST /games/{game_id}/undo and /games/{game_id}/redo
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct UndoBody {
+    /// how many actions to undo or redo, 1 if not given
+    pub count: Option<i32>,
+}
+
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct Board {
     pub id: BoardId,
     pub name: String,
+    /// set once a game has started on the board; frozen boards cannot be edited, only cloned
+    #[serde(default)]
+    pub frozen: bool,
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
@@ -324,6 +735,130 @@ impl BoardPlaces {
     }
 }
 
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub enum DiagnosticSeverity {
+    /// The board cannot be played correctly
+    Error,
+    /// The board works, but probably not as intended
+    Warning,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub enum DiagnosticCode {
+    NoStart,
+    MultipleStarts,
+    NoEnd,
+    EndUnreachable,
+    DeadEnd,
+    Unreachable,
+    BackwardsTooShort,
+    MissingTarget,
+    OnLandLoop,
+    ChainedOnLand,
+    MultipleOnLand,
+    InvalidSpecial,
+    UnknownDrink,
+}
+
+/// A single finding of the board validator.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct BoardDiagnostic {
+    pub severity: DiagnosticSeverity,
+    pub code: DiagnosticCode,
+    /// the place the finding is about, if any
+    pub place_number: Option<i32>,
+    pub message: String,
+}
+
+/// Response of GET /boards/{id}/validate
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct BoardValidation {
+    pub board_id: BoardId,
+    /// true when there are no errors; warnings are allowed
+    pub valid: bool,
+    pub diagnostics: Vec<BoardDiagnostic>,
+}
+
+/// Response of GET /boards/{id}/analysis, for a single team starting on the start place.
+/// Values that would be infinite (a team can get stuck forever) are null.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct BoardAnalysis {
+    pub board_id: BoardId,
+    /// expected number of turns from the start place to an end place
+    pub expected_turns: Option<f64>,
+    /// expected drinks from the start place to an end place
+    pub expected_drinks: Option<f64>,
+    pub squares: Vec<SquareAnalysis>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct SquareAnalysis {
+    pub place_number: i32,
+    pub place_name: String,
+    /// probability that a team starting on the start place lands here at least once
+    pub landing_probability: Option<f64>,
+    /// expected number of times a team starting on the start place lands here
+    pub expected_landings: Option<f64>,
+    /// expected drinks of a turn thrown from this place
+    pub expected_drinks_per_turn: f64,
+    /// expected number of turns from this place to an end place
+    pub expected_turns_to_finish: Option<f64>,
+    /// expected drinks from this place to an end place
+    pub expected_drinks_to_finish: Option<f64>,
+}
+
+/// A complete board in the export format of GET /boards/{id}/export, accepted by
+/// POST /boards/import.
+///
+/// `board` is the board as returned by GET /boards/places/{id}, and `drinks` holds every drink
+/// its places reference with their ingredients. All IDs are those of the exporting environment
+/// and are remapped on import; places are always created anew, drinks and ingredients are
+/// matched by name according to [`ImportConflict`].
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct BoardExport {
+    /// format version, bumped on incompatible changes; currently 1
+    pub version: i32,
+    pub board: BoardPlaces,
+    pub drinks: Vec<DrinkIngredients>,
+}
+
+/// What POST /boards/import does with drinks and ingredients whose name is already taken
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq, Default)]
+pub enum ImportConflict {
+    /// Use the existing one if it is identical, fail if it differs
+    #[default]
+    Reuse,
+    /// Always create new ones, even if the name is taken
+    Copy,
+    /// Fail if any name is taken
+    Fail,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct BoardImportQuery {
+    pub on_conflict: Option<ImportConflict>,
+}
+
+/// Request body for POST /boards/{id}/clone
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct CloneBoardBody {
+    /// name of the new board, e.g. "Lauta 2026"
+    pub name: String,
+}
+
+/// Response of POST /boards/import
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct BoardImportResult {
+    /// the newly created board
+    pub board: Board,
+    pub created_drinks: i32,
+    pub reused_drinks: i32,
+    pub created_ingredients: i32,
+    pub reused_ingredients: i32,
+    /// validation warnings of the imported board; boards with errors are not imported
+    pub diagnostics: Vec<BoardDiagnostic>,
+}
+
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct Place {
     pub place_id: PlaceId,
@@ -331,6 +866,9 @@ pub struct Place {
     pub rule: String,
     pub place_type: PlaceType,
     pub special: Option<String>,
+    /// number of extra dice (dice3, dice4) the special rule needs; derived from `special`
+    #[serde(default)]
+    pub required_dice: u8,
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
@@ -338,6 +876,24 @@ pub struct Places {
     pub places: Vec<Place>,
 }
 
+/// Request body for PATCH /boards/places/{board_id}/{place_number}; missing fields are kept
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct BoardPlaceUpdate {
+    /// new place_number; connections and drinks of the place move along
+    pub place_number: Option<i32>,
+    pub area: Option<String>,
+    pub start: Option<bool>,
+    pub end: Option<bool>,
+}
+
+/// Response of the endpoints that edit or delete places and board places
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct BoardEditResult {
+    pub rows: u64,
+    /// games or other boards the change affects
+    pub warnings: Vec<String>,
+}
+
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct BoardPlace {
     pub board_id: BoardId,
@@ -358,13 +914,13 @@ pub struct PlaceDrinks {
 }
 
 impl PlaceDrinks {
-    pub fn to_turn_drinks(&self, visited: i32, multiplier: i32) -> TurnDrinks {
+    pub fn to_turn_drinks(&self, visited: i32, multiplier: i32, sober: bool) -> TurnDrinks {
         TurnDrinks {
             drinks: self
                 .drinks
                 .iter()
                 .filter(|pd| pd.refill || visited == 0)
-                .map(|pd| pd.to_turn_drink(multiplier))
+                .map(|pd| pd.to_turn_drink(multiplier, sober))
                 .collect(),
         }
     }
@@ -381,22 +937,37 @@ pub struct PlaceDrink {
     /// whether this drink is already on the table and doesn't require IE involvement
     pub on_table: bool,
     pub n: i32,
+    /// the non-alcoholic equivalent of `drink`, if it has one
+    pub non_alcoholic: Option<Drink>,
 }
 
 impl PlaceDrink {
     /// Converts a place drink template to a turn drink instance.
     /// Optional drinks get n=0 so assistant referees can adjust them manually.
-    pub fn to_turn_drink(&self, multiplier: i32) -> TurnDrink {
+    /// Sober teams get the non-alcoholic equivalent, which is never on the table.
+    pub fn to_turn_drink(&self, multiplier: i32, sober: bool) -> TurnDrink {
         let n = if self.optional {
             0
         } else {
-            self.n * multiplier
+            self.n.saturating_mul(multiplier)
         };
-        TurnDrink {
-            drink: self.drink.clone(),
-            n,
-            on_table: if self.on_table { min(n, self.n) } else { 0 },
-            optional: self.optional,
+        match self.non_alcoholic.as_ref().filter(|_| sober) {
+            Some(non_alcoholic) => TurnDrink {
+                drink: non_alcoholic.clone(),
+                n,
+                on_table: 0,
+                optional: self.optional,
+                substituted_for: Some(self.drink.id),
+                players: Vec::new(),
+            },
+            None => TurnDrink {
+                drink: self.drink.clone(),
+                n,
+                on_table: if self.on_table { min(n, self.n) } else { 0 },
+                optional: self.optional,
+                substituted_for: None,
+                players: Vec::new(),
+            },
         }
     }
 }
@@ -423,6 +994,12 @@ pub struct Connections {
     pub backwards: Vec<Connection>,
 }
 
+/// All connections of a board, as read and written by /boards/{id}/connections
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct BoardConnections {
+    pub connections: Vec<Connection>,
+}
+
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct Ingredient {
     pub id: IngredientId,
@@ -442,6 +1019,8 @@ pub struct Drink {
     pub name: String,
     pub favorite: bool,
     pub no_mix_required: bool,
+    /// non-alcoholic equivalent served instead of this drink to sober teams
+    pub non_alcoholic: Option<DrinkId>,
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
@@ -469,6 +1048,70 @@ pub struct DrinksIngredients {
     pub drink_ingredients: Vec<DrinkIngredients>,
 }
 
+/// Stock of an ingredient in a game, in the units of drink recipes.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct IngredientStock {
+    pub ingredient: Ingredient,
+    pub opening: f64,
+    pub restocked: f64,
+    /// used by the drinks of turns that have been mixed, excluding drinks taken from the table
+    pub used: f64,
+    pub remaining: f64,
+    /// level at which the stock is low; without one, the stock is low once it runs out
+    pub low_stock: Option<f64>,
+    pub low: bool,
+}
+
+/// The ingredient inventory of a game, served by GET /games/{game_id}/inventory.
+///
+/// Usage is recorded with the recipes of the drinks when they were mixed, so editing a recipe
+/// only changes what drinks mixed later use.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct GameInventory {
+    pub game_id: GameId,
+    pub ingredients: Vec<IngredientStock>,
+    /// newest first
+    pub restocks: Vec<Restock>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct Restock {
+    pub restock_id: RestockId,
+    pub ingredient_id: IngredientId,
+    pub quantity: f64,
+    /// user who entered the restock
+    pub uid: Option<UserId>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct OpeningStock {
+    pub ingredient_id: IngredientId,
+    pub opening: f64,
+    pub low_stock: Option<f64>,
+}
+
+/// Request body for PUT /games/{game_id}/inventory; sets the opening stock of the given
+/// ingredients and leaves the others as they are.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct OpeningStockBody {
+    pub stock: Vec<OpeningStock>,
+}
+
+/// Request body for POST /games/{game_id}/inventory/restock
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct RestockBody {
+    pub ingredient_id: IngredientId,
+    pub quantity: f64,
+}
+
+/// Sent to the IE channel of a game as low-stock when mixing drinks leaves ingredients low.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct LowStockAlert {
+    pub game_id: GameId,
+    pub ingredients: Vec<IngredientStock>,
+}
+
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct ResultIntJson {
     pub int: i32,
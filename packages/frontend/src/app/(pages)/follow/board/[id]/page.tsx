@@ -25,6 +25,7 @@ export default function Page({
       rule: "",
       place_type: "Normal",
       special: null,
+      required_dice: 0,
     },
     connections: { forwards: [], backwards: [] },
     drinks: {
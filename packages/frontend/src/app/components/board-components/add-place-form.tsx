@@ -16,6 +16,7 @@ export default function AddPlaceForm({
     rule: "",
     place_type: "Normal",
     special: null,
+    required_dice: 0,
   };
   const router = useRouter();
 
@@ -103,7 +104,7 @@ export default function AddPlaceForm({
         type="text"
         id="placeSpecial"
         value={place.special ?? ""}
-        placeholder="MIN(D2), N+1, -D1, IE"
+        placeholder="MIN(D2), -D1, IE, N+1, MIN(D3,D4), IF(DOUBLE, 3, 1)"
         className="border border-primary-500 rounded-lg p-2 w-full"
         onChange={(e: React.ChangeEvent<HTMLInputElement>) => {
           updatePlace({
@@ -418,8 +418,8 @@ export function needDice(turn: Turn | undefined): {
   dice4: boolean;
 } {
   return {
-    dice3: !!turn?.dice3 || /\bD[12]\b/.test(turn?.place?.place.special ?? ""),
-    dice4: !!turn?.dice4 || /\bD2\b/.test(turn?.place?.place.special ?? ""),
+    dice3: !!turn?.dice3 || (turn?.place?.place.required_dice ?? 0) >= 1,
+    dice4: !!turn?.dice4 || (turn?.place?.place.required_dice ?? 0) >= 2,
   };
 }
 
@@ -1,7 +1,7 @@
 "use client";
 
 import { getBoardPlaces } from "@/utils/fetchers";
-import { useEffect, useState } from "react";
+import { useEffect, useRef, useState } from "react";
 import { Socket } from "socket.io-client";
 
 export interface UseGameDataResult {
@@ -11,11 +11,56 @@ export interface UseGameDataResult {
   setGameData: React.Dispatch<React.SetStateAction<GameData | undefined>>;
 }
 
+type Delta = GameDelta | TeamDelta | TurnDelta;
+
+function applyTeamDelta(data: GameData, delta: TeamDelta): GameData {
+  const { team_id, team } = delta;
+  if (!team) {
+    return {
+      ...data,
+      teams: data.teams.filter((t) => t.team.team_id !== team_id),
+    };
+  }
+  const existing = data.teams.find((t) => t.team.team_id === team_id);
+  if (!existing) {
+    return { ...data, teams: [...data.teams, team] };
+  }
+  // Turns are sent separately as turn deltas
+  return {
+    ...data,
+    teams: data.teams.map((t) =>
+      t.team.team_id === team_id ? { ...team, turns: t.turns } : t,
+    ),
+  };
+}
+
+function applyTurnDelta(data: GameData, delta: TurnDelta): GameData {
+  const { team_id, turn_id, turn } = delta;
+  return {
+    ...data,
+    teams: data.teams.map((t) => {
+      if (t.team.team_id !== team_id) return t;
+      if (!turn) {
+        return { ...t, turns: t.turns.filter((tr) => tr.turn_id !== turn_id) };
+      }
+      const exists = t.turns.some((tr) => tr.turn_id === turn_id);
+      return {
+        ...t,
+        turns: exists
+          ? t.turns.map((tr) => (tr.turn_id === turn_id ? turn : tr))
+          : [...t.turns, turn],
+      };
+    }),
+  };
+}
+
 /**
  * Custom hook to subscribe to game data updates via websocket.
  *
- * On mount, subscribes to the game room. The server sends initial game data
- * immediately upon subscription, and broadcasts updates when actions occur.
+ * On mount, subscribes to the game room. The server sends a versioned snapshot
+ * of the game immediately upon subscription, followed by numbered deltas
+ * (game-updated, team-updated, turn-updated) when actions occur. If a delta is
+ * missed, a resync is requested and a new snapshot replaces the data.
  *
  * @param socket - The socket.io socket instance
  * @param gameId - The game ID to subscribe to
@@ -28,42 +73,74 @@ export function useGameData(
   const [gameData, setGameData] = useState<GameData | undefined>(undefined);
   const [error, setError] = useState<string | null>(null);
   const [isLoading, setIsLoading] = useState<boolean>(true);
+  // Sequence number of the last snapshot or delta applied, null before the first snapshot
+  const seq = useRef<number | null>(null);
 
   useEffect(() => {
     if (!socket) {
       setIsLoading(true);
       return;
     }
+    seq.current = null;
 
-    const onGameUpdate = (data: GameData) => {
-      if (!data?.game) {
+    const onSnapshot = (snapshot: VersionedGameData) => {
+      if (!snapshot?.data?.game) {
         setError("Invalid game data received.");
         return;
       }
 
       // Validate that the received game ID matches the subscribed one
-      if (data.game.id !== gameId) {
+      if (snapshot.game_id !== gameId) {
         return;
       }
 
-      console.log("Received game update:", data);
-
-      setGameData(data);
+      seq.current = snapshot.seq;
+      setGameData(snapshot.data);
       setIsLoading(false);
       setError(null);
     };
 
+    const onDelta =
+      <D extends Delta>(apply: (data: GameData, delta: D) => GameData) =>
+      (delta: D) => {
+        if (delta.game_id !== gameId || seq.current === null) {
+          return;
+        }
+        // Already included in the snapshot
+        if (delta.seq <= seq.current) {
+          return;
+        }
+        if (delta.seq !== seq.current + 1) {
+          seq.current = null;
+          socket.emit("resync", { game_id: gameId });
+          return;
+        }
+        seq.current = delta.seq;
+        setGameData((data) => data && apply(data, delta));
+      };
+
+    const onGameUpdated = onDelta<GameDelta>((data, delta) => ({
+      ...data,
+      game: delta.game,
+    }));
+    const onTeamUpdated = onDelta(applyTeamDelta);
+    const onTurnUpdated = onDelta(applyTurnDelta);
+
     const onError = (errorMsg: string) => {
       setError(errorMsg);
       setIsLoading(false);
     };
 
     const subscribe = () => {
+      seq.current = null;
       socket.emit("subscribe", { game_id: gameId });
     };
 
     // Listen for game updates and errors
-    socket.on("game-update", onGameUpdate);
+    socket.on("game-snapshot", onSnapshot);
+    socket.on("game-updated", onGameUpdated);
+    socket.on("team-updated", onTeamUpdated);
+    socket.on("turn-updated", onTurnUpdated);
     socket.on("response-error", onError);
 
     // Subscribe when connected, and on reconnection
@@ -74,7 +151,10 @@ export function useGameData(
 
     return () => {
       socket.off("connect", subscribe);
-      socket.off("game-update", onGameUpdate);
+      socket.off("game-snapshot", onSnapshot);
+      socket.off("game-updated", onGameUpdated);
+      socket.off("team-updated", onTeamUpdated);
+      socket.off("turn-updated", onTurnUpdated);
       socket.off("response-error", onError);
     };
   }, [socket, gameId]);
@@ -0,0 +1,108 @@
+"use client";
+
+import { useEffect, useState } from "react";
+import { Socket } from "socket.io-client";
+
+export interface UseRoleChannelResult<T> {
+  payload: T | undefined;
+  error: string | null;
+  isLoading: boolean;
+}
+
+/**
+ * Custom hook to follow the channel of a role in a game via websocket.
+ *
+ * The server sends the current payload of the channel upon subscription, and
+ * a new one whenever the game changes.
+ *
+ * @param socket - The socket.io socket instance
+ * @param gameId - The game ID to follow
+ * @param role - The role whose channel to follow
+ * @param event - The event carrying the payload of the channel
+ * @returns Latest payload, error state and loading state
+ */
+function useRoleChannel<T extends { game_id: number }>(
+  socket: Socket | null,
+  gameId: number,
+  role: UserType,
+  event: string,
+): UseRoleChannelResult<T> {
+  const [payload, setPayload] = useState<T | undefined>(undefined);
+  const [error, setError] = useState<string | null>(null);
+  const [isLoading, setIsLoading] = useState<boolean>(true);
+
+  useEffect(() => {
+    if (!socket) {
+      setIsLoading(true);
+      return;
+    }
+
+    const onPayload = (data: T) => {
+      if (data?.game_id !== gameId) {
+        return;
+      }
+      setPayload(data);
+      setIsLoading(false);
+      setError(null);
+    };
+
+    const onError = (errorMsg: string) => {
+      setError(errorMsg);
+      setIsLoading(false);
+    };
+
+    const subscribe = () => {
+      socket.emit("subscribe-role", { game_id: gameId, role });
+    };
+
+    socket.on(event, onPayload);
+    socket.on("response-error", onError);
+
+    // Subscribe when connected, and on reconnection
+    socket.on("connect", subscribe);
+    if (socket.connected) {
+      subscribe();
+    }
+
+    return () => {
+      socket.off("connect", subscribe);
+      socket.off(event, onPayload);
+      socket.off("response-error", onError);
+    };
+  }, [socket, gameId, role, event]);
+
+  return { payload, error, isLoading };
+}
+
+/** The drink queue of a game, as shown on the IE screen. */
+export function useDrinkQueue(socket: Socket | null, gameId: number) {
+  return useRoleChannel<DrinkQueue>(socket, gameId, "Ie", "drink-queue");
+}
+
+/** The turn log of a game, as shown on the secretary screen. */
+export function useTurnLog(socket: Socket | null, gameId: number) {
+  return useRoleChannel<TurnLog>(socket, gameId, "Secretary", "turn-log");
+}
+
+/**
+ * Low stock alerts of a game, sent to the IE channel when mixing drinks leaves
+ * ingredients low. Only received while following the drink queue.
+ */
+export function useLowStockAlert(socket: Socket | null, gameId: number) {
+  const [alert, setAlert] = useState<LowStockAlert | undefined>(undefined);
+
+  useEffect(() => {
+    if (!socket) return;
+
+    const onLowStock = (data: LowStockAlert) => {
+      if (data?.game_id === gameId) setAlert(data);
+    };
+
+    socket.on("low-stock", onLowStock);
+    return () => {
+      socket.off("low-stock", onLowStock);
+    };
+  }, [socket, gameId]);
+
+  return { alert, dismiss: () => setAlert(undefined) };
+}
@@ -6,8 +6,18 @@ declare global {
 
   type UserType = "Admin" | "Ie" | "Referee" | "Secretary";
 
+  type LimitAction = "Flag" | "Substitute" | "RequireOverride";
+
+  type LimitPeriod = "Hour" | "Game" | "Turn";
+
   type DrinkPrepStatus = "Queued" | "Mixing" | "Mixed" | "Delivered";
 
+  type DiagnosticSeverity = "Error" | "Warning";
+
+  type DiagnosticCode = "NoStart" | "MultipleStarts" | "NoEnd" | "EndUnreachable" | "DeadEnd" | "Unreachable" | "BackwardsTooShort" | "MissingTarget" | "OnLandLoop" | "ChainedOnLand" | "MultipleOnLand" | "InvalidSpecial" | "UnknownDrink";
+
+  type ImportConflict = "Reuse" | "Copy" | "Fail";
+
   type WithNameAndId = { id: string | number; name: string };
 
   interface HeaderItem {
@@ -15,6 +25,10 @@ declare global {
     href: string;
   }
 
+  interface TeamSocketAuth {
+    team_hash: string;
+  }
+
   interface SocketAuth {
     token: string;
   }
@@ -69,17 +83,51 @@ declare global {
     team_name: string;
     team_hash: string;
     moral_victory_eligible: boolean;
+    sober: boolean;
   }
 
   interface TeamNameUpdate {
     team_name: string;
   }
 
+  interface Player {
+    player_id: number;
+    team_id: number;
+    player_name: string;
+    sober: boolean;
+  }
+
+  interface Players {
+    players: Player[];
+  }
+
+  interface PlayerUpdate {
+    player_name: string;
+    sober: boolean;
+  }
+
   interface TurnDrink {
     drink: Drink;
     n: number;
     on_table: number;
     optional: boolean;
+    substituted_for: number | null;
+    players: PlayerServing[];
+  }
+
+  interface PlayerServing {
+    player_id: number;
+    n: number;
+  }
+
+  interface PlayerDrink {
+    drink_id: number;
+    player_id: number;
+    n: number;
+  }
+
+  interface AttributeDrinksBody {
+    drinks: PlayerDrink[];
   }
 
   interface TurnDrinks {
@@ -101,13 +149,26 @@ declare global {
     turns: Turn[];
     location: BoardPlace | null;
     double_tampere: boolean;
+    alcohol: AlcoholIntake;
+    players: GamePlayer[];
   }
 
-  interface TeamLatestTurn {
-    team: Team;
-    latest_turn: Turn | null;
-    location: BoardPlace | null;
-    double_tampere: boolean;
+  interface GamePlayer {
+    player: Player;
+    alcohol: AlcoholIntake;
+  }
+
+  interface AlcoholIntake {
+    ethanol_grams: number;
+    standard_drinks: number;
+    timeline: AlcoholPoint[];
+  }
+
+  interface AlcoholPoint {
+    turn_id: number;
+    at: string;
+    ethanol_grams: number;
+    standard_drinks: number;
   }
 
   interface Turn {
@@ -129,6 +190,7 @@ declare global {
     via_number: number | null;
     penalty: boolean;
     double_tampere: boolean | null;
+    over_limit: boolean;
     drinks: TurnDrinks;
     place: BoardPlace | null;
     via: BoardPlace | null;
@@ -151,12 +213,41 @@ declare global {
 
   interface ConfirmTurnBody {
     drinks: TurnDrinks;
+    override_limits: boolean;
+  }
+
+  interface DrinkingLimits {
+    per_hour: number | null;
+    per_game: number | null;
+    per_turn: number | null;
+    action: LimitAction;
+    substitute_drink: number | null;
+  }
+
+  interface LimitBreach {
+    period: LimitPeriod;
+    limit: number;
+    standard_drinks: number;
+  }
+
+  interface LimitAlert {
+    game_id: number;
+    team_id: number;
+    turn_id: number;
+    action: LimitAction;
+    blocked: boolean;
+    breaches: LimitBreach[];
   }
 
   interface SetDrinkPrepStatusBody {
     status: DrinkPrepStatus;
   }
 
+  interface BatchPrepStatusBody {
+    turn_ids: number[];
+    status: DrinkPrepStatus;
+  }
+
   interface TeleportTeamBody {
     location: number;
   }
@@ -179,9 +270,196 @@ declare global {
     games: Game[];
   }
 
+  interface GameStats {
+    game_id: number;
+    teams: TeamStats[];
+  }
+
+  interface TeamStats {
+    team_id: number;
+    team_name: string;
+    rank: number;
+    place_number: number | null;
+    steps_to_end: number | null;
+    turns: number;
+    penalties: number;
+    drinks: DrinkCount[];
+    total_drinks: number;
+    ethanol_grams: number;
+    standard_drinks: number;
+    standard_drinks_per_hour: number;
+    avg_queue_secs: number | null;
+    avg_mixing_secs: number | null;
+    avg_delivery_secs: number | null;
+    players: PlayerStats[];
+  }
+
+  interface PlayerStats {
+    player_id: number;
+    player_name: string;
+    drinks: DrinkCount[];
+    total_drinks: number;
+    ethanol_grams: number;
+    standard_drinks: number;
+  }
+
+  interface DrinkCount {
+    drink: Drink;
+    n: number;
+  }
+
+  interface TeamView {
+    game_id: number;
+    game_name: string;
+    started: boolean;
+    finished: boolean;
+    team_id: number;
+    team_name: string;
+    location: BoardPlace | null;
+    double_tampere: boolean;
+    pending: PendingDrinks[];
+    turns: TeamTurn[];
+  }
+
+  interface PendingDrinks {
+    turn_id: number;
+    status: DrinkPrepStatus;
+    drinks: DrinkCount[];
+  }
+
+  interface SpectatorGame {
+    game: Game;
+    teams: SpectatorTeam[];
+  }
+
+  interface SpectatorTeam {
+    team_id: number;
+    team_name: string;
+    location: BoardPlace | null;
+    double_tampere: boolean;
+    turns: TeamTurn[];
+  }
+
+  interface TeamTurn {
+    turn_id: number;
+    penalty: boolean;
+    start_time: string;
+    confirmed_at: string | null;
+    delivered_at: string | null;
+    end_time: string | null;
+    dice1: number | null;
+    dice2: number | null;
+    place_number: number | null;
+    place_name: string | null;
+    drinks: DrinkCount[];
+  }
+
+  interface DrinkQueue {
+    game_id: number;
+    turns: QueuedTurn[];
+    ingredient_totals: IngredientQty[];
+    teams: TeamWait[];
+  }
+
+  interface TeamWait {
+    team_id: number;
+    team_name: string;
+    turns: number;
+    waiting_since: string;
+    waiting_seconds: number;
+  }
+
+  interface QueuedTurn {
+    turn_id: number;
+    team_id: number;
+    team_name: string;
+    status: DrinkPrepStatus;
+    confirmed_at: string;
+    mixing_at: string | null;
+    mixed_at: string | null;
+    drinks: QueuedDrink[];
+  }
+
+  interface QueuedDrink {
+    drink: Drink;
+    n: number;
+    ingredients: IngredientQty[];
+  }
+
+  interface TurnLog {
+    game_id: number;
+    turns: TurnLogEntry[];
+  }
+
+  interface TurnLogEntry {
+    turn_id: number;
+    team_id: number;
+    team_name: string;
+    penalty: boolean;
+    start_time: string;
+    thrown_at: string | null;
+    confirmed_at: string | null;
+    delivered_at: string | null;
+    end_time: string | null;
+    dice1: number | null;
+    dice2: number | null;
+    dice3: number | null;
+    dice4: number | null;
+    place_number: number | null;
+    place_name: string | null;
+    drinks: DrinkCount[];
+  }
+
+  interface VersionedGameData {
+    game_id: number;
+    seq: number;
+    data: GameData;
+  }
+
+  interface GameDelta {
+    game_id: number;
+    seq: number;
+    game: Game;
+  }
+
+  interface TeamDelta {
+    game_id: number;
+    seq: number;
+    team_id: number;
+    team: GameTeam | null;
+  }
+
+  interface TurnDelta {
+    game_id: number;
+    seq: number;
+    team_id: number;
+    turn_id: number;
+    turn: Turn | null;
+  }
+
+  interface GameAction {
+    action_id: number;
+    game_id: number;
+    uid: number | null;
+    action: string;
+    reverts: number | null;
+    team_ids: number[];
+    created_at: string;
+    undone: boolean;
+  }
+
+  interface GameActions {
+    actions: GameAction[];
+  }
+
+  interface UndoBody {
+    count: number | null;
+  }
+
   interface Board {
     id: number;
     name: string;
+    frozen: boolean;
   }
 
   interface Boards {
@@ -193,18 +471,84 @@ declare global {
     places: BoardPlace[];
   }
 
+  interface BoardDiagnostic {
+    severity: DiagnosticSeverity;
+    code: DiagnosticCode;
+    place_number: number | null;
+    message: string;
+  }
+
+  interface BoardValidation {
+    board_id: number;
+    valid: boolean;
+    diagnostics: BoardDiagnostic[];
+  }
+
+  interface BoardAnalysis {
+    board_id: number;
+    expected_turns: number | null;
+    expected_drinks: number | null;
+    squares: SquareAnalysis[];
+  }
+
+  interface SquareAnalysis {
+    place_number: number;
+    place_name: string;
+    landing_probability: number | null;
+    expected_landings: number | null;
+    expected_drinks_per_turn: number;
+    expected_turns_to_finish: number | null;
+    expected_drinks_to_finish: number | null;
+  }
+
+  interface BoardExport {
+    version: number;
+    board: BoardPlaces;
+    drinks: DrinkIngredients[];
+  }
+
+  interface BoardImportQuery {
+    on_conflict: ImportConflict | null;
+  }
+
+  interface CloneBoardBody {
+    name: string;
+  }
+
+  interface BoardImportResult {
+    board: Board;
+    created_drinks: number;
+    reused_drinks: number;
+    created_ingredients: number;
+    reused_ingredients: number;
+    diagnostics: BoardDiagnostic[];
+  }
+
   interface Place {
     place_id: number;
     place_name: string;
     rule: string;
     place_type: PlaceType;
     special: string | null;
+    required_dice: number;
   }
 
   interface Places {
     places: Place[];
   }
 
+  interface BoardPlaceUpdate {
+    place_number: number | null;
+    area: string | null;
+    start: boolean | null;
+    end: boolean | null;
+  }
+
+  interface BoardEditResult {
+    rows: number;
+    warnings: string[];
+  }
+
   interface BoardPlace {
     board_id: number;
     place: Place;
@@ -230,6 +574,7 @@ declare global {
     optional: boolean;
     on_table: boolean;
     n: number;
+    non_alcoholic: Drink | null;
   }
 
   interface Connection {
@@ -245,6 +590,10 @@ declare global {
     backwards: Connection[];
   }
 
+  interface BoardConnections {
+    connections: Connection[];
+  }
+
   interface Ingredient {
     id: number;
     name: string;
@@ -261,6 +610,7 @@ declare global {
     name: string;
     favorite: boolean;
     no_mix_required: boolean;
+    non_alcoholic: number | null;
   }
 
   interface IngredientQty {
@@ -284,6 +634,50 @@ declare global {
     drink_ingredients: DrinkIngredients[];
   }
 
+  interface IngredientStock {
+    ingredient: Ingredient;
+    opening: number;
+    restocked: number;
+    used: number;
+    remaining: number;
+    low_stock: number | null;
+    low: boolean;
+  }
+
+  interface GameInventory {
+    game_id: number;
+    ingredients: IngredientStock[];
+    restocks: Restock[];
+  }
+
+  interface Restock {
+    restock_id: number;
+    ingredient_id: number;
+    quantity: number;
+    uid: number | null;
+    created_at: string;
+  }
+
+  interface OpeningStock {
+    ingredient_id: number;
+    opening: number;
+    low_stock: number | null;
+  }
+
+  interface OpeningStockBody {
+    stock: OpeningStock[];
+  }
+
+  interface RestockBody {
+    ingredient_id: number;
+    quantity: number;
+  }
+
+  interface LowStockAlert {
+    game_id: number;
+    ingredients: IngredientStock[];
+  }
+
   interface ResultIntJson {
     int: number;
   }
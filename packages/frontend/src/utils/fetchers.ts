@@ -496,6 +496,60 @@ export async function setDrinkPrepStatus(
   );
 }
 
+export async function getDrinkQueue(gameId: number): Promise<DrinkQueue> {
+  return apiFetch<DrinkQueue>(`${API_URL}/games/${gameId}/drink-queue`);
+}
+
+/** Moves several turns of a game to the same drink preparation status at once. */
+export async function setQueuePrepStatus(
+  gameId: number,
+  turnIds: number[],
+  status: DrinkPrepStatus,
+): Promise<void> {
+  return apiFetchVoid(
+    `${API_URL}/games/${gameId}/drink-queue`,
+    {
+      method: "PUT",
+      body: JSON.stringify({ turn_ids: turnIds, status }),
+    },
+    true,
+  );
+}
+
+export async function getInventory(gameId: number): Promise<GameInventory> {
+  return apiFetch<GameInventory>(`${API_URL}/games/${gameId}/inventory`);
+}
+
+/** Sets the opening stock of ingredients of a game. */
+export async function setOpeningStock(
+  gameId: number,
+  stock: OpeningStock[],
+): Promise<GameInventory> {
+  return apiFetch<GameInventory>(
+    `${API_URL}/games/${gameId}/inventory`,
+    {
+      method: "PUT",
+      body: JSON.stringify({ stock }),
+    },
+    true,
+  );
+}
+
+export async function restockIngredient(
+  gameId: number,
+  ingredientId: number,
+  quantity: number,
+): Promise<GameInventory> {
+  return apiFetch<GameInventory>(
+    `${API_URL}/games/${gameId}/inventory/restock`,
+    {
+      method: "POST",
+      body: JSON.stringify({ ingredient_id: ingredientId, quantity }),
+    },
+    true,
+  );
+}
+
 /** Updates drinks on an already-confirmed turn (used by IE for "IE" special). */
 export async function editTurnDrinks(
   turnId: number,
@@ -99,8 +99,25 @@ PlaceType = Literal["Normal", "Food", "Sauna", "Special", "Guild"]
 
 UserType = Literal["Admin", "Ie", "Referee", "Secretary"]
 
+LimitAction = Literal["Flag", "Substitute", "RequireOverride"]
+
+LimitPeriod = Literal["Hour", "Game", "Turn"]
+
 DrinkPrepStatus = Literal["Queued", "Mixing", "Mixed", "Delivered"]
 
+DiagnosticSeverity = Literal["Error", "Warning"]
+
+DiagnosticCode = Literal["NoStart", "MultipleStarts", "NoEnd", "EndUnreachable", "DeadEnd", "Unreachable", "BackwardsTooShort", "MissingTarget", "OnLandLoop", "ChainedOnLand", "MultipleOnLand", "InvalidSpecial", "UnknownDrink"]
+
+ImportConflict = Literal["Reuse", "Copy", "Fail"]
+
+
+
+@dataclass
+class TeamSocketAuth:
+    team_hash: str
+    @classmethod
+    def from_dict(cls, data: Any): return _from_dict(cls, data)
 
 
 @dataclass
@@ -185,6 +202,7 @@ class Team:
     team_name: str
     team_hash: str
     moral_victory_eligible: bool
+    sober: bool
     @classmethod
     def from_dict(cls, data: Any): return _from_dict(cls, data)
 
@@ -196,12 +214,63 @@ class TeamNameUpdate:
     def from_dict(cls, data: Any): return _from_dict(cls, data)
 
 
+@dataclass
+class Player:
+    player_id: int
+    team_id: int
+    player_name: str
+    sober: bool
+    @classmethod
+    def from_dict(cls, data: Any): return _from_dict(cls, data)
+
+
+@dataclass
+class Players:
+    players: list['Player'] = field(default_factory=list['Player'])
+    @classmethod
+    def from_dict(cls, data: Any): return _from_dict(cls, data)
+
+
+@dataclass
+class PlayerUpdate:
+    player_name: str
+    sober: bool
+    @classmethod
+    def from_dict(cls, data: Any): return _from_dict(cls, data)
+
+
 @dataclass
 class TurnDrink:
     drink: 'Drink'
     n: int
     on_table: int
     optional: bool
+    substituted_for: Optional[int] = None
+    players: list['PlayerServing'] = field(default_factory=list['PlayerServing'])
+    @classmethod
+    def from_dict(cls, data: Any): return _from_dict(cls, data)
+
+
+@dataclass
+class PlayerServing:
+    player_id: int
+    n: int
+    @classmethod
+    def from_dict(cls, data: Any): return _from_dict(cls, data)
+
+
+@dataclass
+class PlayerDrink:
+    drink_id: int
+    player_id: int
+    n: int
+    @classmethod
+    def from_dict(cls, data: Any): return _from_dict(cls, data)
+
+
+@dataclass
+class AttributeDrinksBody:
+    drinks: list['PlayerDrink'] = field(default_factory=list['PlayerDrink'])
     @classmethod
     def from_dict(cls, data: Any): return _from_dict(cls, data)
 
@@ -233,18 +302,37 @@ class GameData:
 class GameTeam:
     team: 'Team'
     double_tampere: bool
+    alcohol: 'AlcoholIntake'
     turns: list['Turn'] = field(default_factory=list['Turn'])
     location: Optional['BoardPlace'] = None
+    players: list['GamePlayer'] = field(default_factory=list['GamePlayer'])
     @classmethod
     def from_dict(cls, data: Any): return _from_dict(cls, data)
 
 
 @dataclass
-class TeamLatestTurn:
-    team: 'Team'
-    double_tampere: bool
-    latest_turn: Optional['Turn'] = None
-    location: Optional['BoardPlace'] = None
+class GamePlayer:
+    player: 'Player'
+    alcohol: 'AlcoholIntake'
+    @classmethod
+    def from_dict(cls, data: Any): return _from_dict(cls, data)
+
+
+@dataclass
+class AlcoholIntake:
+    ethanol_grams: float
+    standard_drinks: float
+    timeline: list['AlcoholPoint'] = field(default_factory=list['AlcoholPoint'])
+    @classmethod
+    def from_dict(cls, data: Any): return _from_dict(cls, data)
+
+
+@dataclass
+class AlcoholPoint:
+    turn_id: int
+    at: str
+    ethanol_grams: float
+    standard_drinks: float
     @classmethod
     def from_dict(cls, data: Any): return _from_dict(cls, data)
 
@@ -256,6 +344,7 @@ class Turn:
     game_id: int
     start_time: str
     penalty: bool
+    over_limit: bool
     drinks: 'TurnDrinks'
     thrown_at: Optional[str] = None
     confirmed_at: Optional[str] = None
@@ -300,6 +389,39 @@ class ChangeDiceBody:
 @dataclass
 class ConfirmTurnBody:
     drinks: 'TurnDrinks'
+    override_limits: bool
+    @classmethod
+    def from_dict(cls, data: Any): return _from_dict(cls, data)
+
+
+@dataclass
+class DrinkingLimits:
+    action: LimitAction
+    per_hour: Optional[float] = None
+    per_game: Optional[float] = None
+    per_turn: Optional[float] = None
+    substitute_drink: Optional[int] = None
+    @classmethod
+    def from_dict(cls, data: Any): return _from_dict(cls, data)
+
+
+@dataclass
+class LimitBreach:
+    period: LimitPeriod
+    limit: float
+    standard_drinks: float
+    @classmethod
+    def from_dict(cls, data: Any): return _from_dict(cls, data)
+
+
+@dataclass
+class LimitAlert:
+    game_id: int
+    team_id: int
+    turn_id: int
+    action: LimitAction
+    blocked: bool
+    breaches: list['LimitBreach'] = field(default_factory=list['LimitBreach'])
     @classmethod
     def from_dict(cls, data: Any): return _from_dict(cls, data)
 
@@ -311,6 +433,14 @@ class SetDrinkPrepStatusBody:
     def from_dict(cls, data: Any): return _from_dict(cls, data)
 
 
+@dataclass
+class BatchPrepStatusBody:
+    status: DrinkPrepStatus
+    turn_ids: list[int] = field(default_factory=list[int])
+    @classmethod
+    def from_dict(cls, data: Any): return _from_dict(cls, data)
+
+
 @dataclass
 class TeleportTeamBody:
     location: int
@@ -345,10 +475,263 @@ class Games:
     def from_dict(cls, data: Any): return _from_dict(cls, data)
 
 
+@dataclass
+class GameStats:
+    game_id: int
+    teams: list['TeamStats'] = field(default_factory=list['TeamStats'])
+    @classmethod
+    def from_dict(cls, data: Any): return _from_dict(cls, data)
+
+
+@dataclass
+class TeamStats:
+    team_id: int
+    team_name: str
+    rank: int
+    turns: int
+    penalties: int
+    total_drinks: int
+    ethanol_grams: float
+    standard_drinks: float
+    standard_drinks_per_hour: float
+    place_number: Optional[int] = None
+    steps_to_end: Optional[int] = None
+    drinks: list['DrinkCount'] = field(default_factory=list['DrinkCount'])
+    avg_queue_secs: Optional[float] = None
+    avg_mixing_secs: Optional[float] = None
+    avg_delivery_secs: Optional[float] = None
+    players: list['PlayerStats'] = field(default_factory=list['PlayerStats'])
+    @classmethod
+    def from_dict(cls, data: Any): return _from_dict(cls, data)
+
+
+@dataclass
+class PlayerStats:
+    player_id: int
+    player_name: str
+    total_drinks: int
+    ethanol_grams: float
+    standard_drinks: float
+    drinks: list['DrinkCount'] = field(default_factory=list['DrinkCount'])
+    @classmethod
+    def from_dict(cls, data: Any): return _from_dict(cls, data)
+
+
+@dataclass
+class DrinkCount:
+    drink: 'Drink'
+    n: int
+    @classmethod
+    def from_dict(cls, data: Any): return _from_dict(cls, data)
+
+
+@dataclass
+class TeamView:
+    game_id: int
+    game_name: str
+    started: bool
+    finished: bool
+    team_id: int
+    team_name: str
+    double_tampere: bool
+    location: Optional['BoardPlace'] = None
+    pending: list['PendingDrinks'] = field(default_factory=list['PendingDrinks'])
+    turns: list['TeamTurn'] = field(default_factory=list['TeamTurn'])
+    @classmethod
+    def from_dict(cls, data: Any): return _from_dict(cls, data)
+
+
+@dataclass
+class PendingDrinks:
+    turn_id: int
+    status: DrinkPrepStatus
+    drinks: list['DrinkCount'] = field(default_factory=list['DrinkCount'])
+    @classmethod
+    def from_dict(cls, data: Any): return _from_dict(cls, data)
+
+
+@dataclass
+class SpectatorGame:
+    game: 'Game'
+    teams: list['SpectatorTeam'] = field(default_factory=list['SpectatorTeam'])
+    @classmethod
+    def from_dict(cls, data: Any): return _from_dict(cls, data)
+
+
+@dataclass
+class SpectatorTeam:
+    team_id: int
+    team_name: str
+    double_tampere: bool
+    location: Optional['BoardPlace'] = None
+    turns: list['TeamTurn'] = field(default_factory=list['TeamTurn'])
+    @classmethod
+    def from_dict(cls, data: Any): return _from_dict(cls, data)
+
+
+@dataclass
+class TeamTurn:
+    turn_id: int
+    penalty: bool
+    start_time: str
+    confirmed_at: Optional[str] = None
+    delivered_at: Optional[str] = None
+    end_time: Optional[str] = None
+    dice1: Optional[int] = None
+    dice2: Optional[int] = None
+    place_number: Optional[int] = None
+    place_name: Optional[str] = None
+    drinks: list['DrinkCount'] = field(default_factory=list['DrinkCount'])
+    @classmethod
+    def from_dict(cls, data: Any): return _from_dict(cls, data)
+
+
+@dataclass
+class DrinkQueue:
+    game_id: int
+    turns: list['QueuedTurn'] = field(default_factory=list['QueuedTurn'])
+    ingredient_totals: list['IngredientQty'] = field(default_factory=list['IngredientQty'])
+    teams: list['TeamWait'] = field(default_factory=list['TeamWait'])
+    @classmethod
+    def from_dict(cls, data: Any): return _from_dict(cls, data)
+
+
+@dataclass
+class TeamWait:
+    team_id: int
+    team_name: str
+    turns: int
+    waiting_since: str
+    waiting_seconds: int
+    @classmethod
+    def from_dict(cls, data: Any): return _from_dict(cls, data)
+
+
+@dataclass
+class QueuedTurn:
+    turn_id: int
+    team_id: int
+    team_name: str
+    status: DrinkPrepStatus
+    confirmed_at: str
+    mixing_at: Optional[str] = None
+    mixed_at: Optional[str] = None
+    drinks: list['QueuedDrink'] = field(default_factory=list['QueuedDrink'])
+    @classmethod
+    def from_dict(cls, data: Any): return _from_dict(cls, data)
+
+
+@dataclass
+class QueuedDrink:
+    drink: 'Drink'
+    n: int
+    ingredients: list['IngredientQty'] = field(default_factory=list['IngredientQty'])
+    @classmethod
+    def from_dict(cls, data: Any): return _from_dict(cls, data)
+
+
+@dataclass
+class TurnLog:
+    game_id: int
+    turns: list['TurnLogEntry'] = field(default_factory=list['TurnLogEntry'])
+    @classmethod
+    def from_dict(cls, data: Any): return _from_dict(cls, data)
+
+
+@dataclass
+class TurnLogEntry:
+    turn_id: int
+    team_id: int
+    team_name: str
+    penalty: bool
+    start_time: str
+    thrown_at: Optional[str] = None
+    confirmed_at: Optional[str] = None
+    delivered_at: Optional[str] = None
+    end_time: Optional[str] = None
+    dice1: Optional[int] = None
+    dice2: Optional[int] = None
+    dice3: Optional[int] = None
+    dice4: Optional[int] = None
+    place_number: Optional[int] = None
+    place_name: Optional[str] = None
+    drinks: list['DrinkCount'] = field(default_factory=list['DrinkCount'])
+    @classmethod
+    def from_dict(cls, data: Any): return _from_dict(cls, data)
+
+
+@dataclass
+class VersionedGameData:
+    game_id: int
+    seq: int
+    data: 'GameData'
+    @classmethod
+    def from_dict(cls, data: Any): return _from_dict(cls, data)
+
+
+@dataclass
+class GameDelta:
+    game_id: int
+    seq: int
+    game: 'Game'
+    @classmethod
+    def from_dict(cls, data: Any): return _from_dict(cls, data)
+
+
+@dataclass
+class TeamDelta:
+    game_id: int
+    seq: int
+    team_id: int
+    team: Optional['GameTeam'] = None
+    @classmethod
+    def from_dict(cls, data: Any): return _from_dict(cls, data)
+
+
+@dataclass
+class TurnDelta:
+    game_id: int
+    seq: int
+    team_id: int
+    turn_id: int
+    turn: Optional['Turn'] = None
+    @classmethod
+    def from_dict(cls, data: Any): return _from_dict(cls, data)
+
+
+@dataclass
+class GameAction:
+    action_id: int
+    game_id: int
+    action: str
+    created_at: str
+    undone: bool
+    uid: Optional[int] = None
+    reverts: Optional[int] = None
+    team_ids: list[int] = field(default_factory=list[int])
+    @classmethod
+    def from_dict(cls, data: Any): return _from_dict(cls, data)
+
+
+@dataclass
+class GameActions:
+    actions: list['GameAction'] = field(default_factory=list['GameAction'])
+    @classmethod
+    def from_dict(cls, data: Any): return _from_dict(cls, data)
+
+
+@dataclass
+class UndoBody:
+    count: Optional[int] = None
+    @classmethod
+    def from_dict(cls, data: Any): return _from_dict(cls, data)
+
+
 @dataclass
 class Board:
     id: int
     name: str
+    frozen: bool
     @classmethod
     def from_dict(cls, data: Any): return _from_dict(cls, data)
 
@@ -368,12 +751,90 @@ class BoardPlaces:
     def from_dict(cls, data: Any): return _from_dict(cls, data)
 
 
+@dataclass
+class BoardDiagnostic:
+    severity: DiagnosticSeverity
+    code: DiagnosticCode
+    message: str
+    place_number: Optional[int] = None
+    @classmethod
+    def from_dict(cls, data: Any): return _from_dict(cls, data)
+
+
+@dataclass
+class BoardValidation:
+    board_id: int
+    valid: bool
+    diagnostics: list['BoardDiagnostic'] = field(default_factory=list['BoardDiagnostic'])
+    @classmethod
+    def from_dict(cls, data: Any): return _from_dict(cls, data)
+
+
+@dataclass
+class BoardAnalysis:
+    board_id: int
+    expected_turns: Optional[float] = None
+    expected_drinks: Optional[float] = None
+    squares: list['SquareAnalysis'] = field(default_factory=list['SquareAnalysis'])
+    @classmethod
+    def from_dict(cls, data: Any): return _from_dict(cls, data)
+
+
+@dataclass
+class SquareAnalysis:
+    place_number: int
+    place_name: str
+    expected_drinks_per_turn: float
+    landing_probability: Optional[float] = None
+    expected_landings: Optional[float] = None
+    expected_turns_to_finish: Optional[float] = None
+    expected_drinks_to_finish: Optional[float] = None
+    @classmethod
+    def from_dict(cls, data: Any): return _from_dict(cls, data)
+
+
+@dataclass
+class BoardExport:
+    version: int
+    board: 'BoardPlaces'
+    drinks: list['DrinkIngredients'] = field(default_factory=list['DrinkIngredients'])
+    @classmethod
+    def from_dict(cls, data: Any): return _from_dict(cls, data)
+
+
+@dataclass
+class BoardImportQuery:
+    on_conflict: Optional[ImportConflict] = None
+    @classmethod
+    def from_dict(cls, data: Any): return _from_dict(cls, data)
+
+
+@dataclass
+class CloneBoardBody:
+    name: str
+    @classmethod
+    def from_dict(cls, data: Any): return _from_dict(cls, data)
+
+
+@dataclass
+class BoardImportResult:
+    board: 'Board'
+    created_drinks: int
+    reused_drinks: int
+    created_ingredients: int
+    reused_ingredients: int
+    diagnostics: list['BoardDiagnostic'] = field(default_factory=list['BoardDiagnostic'])
+    @classmethod
+    def from_dict(cls, data: Any): return _from_dict(cls, data)
+
+
 @dataclass
 class Place:
     place_id: int
     place_name: str
     rule: str
     place_type: PlaceType
+    required_dice: int
     special: Optional[str] = None
     @classmethod
     def from_dict(cls, data: Any): return _from_dict(cls, data)
@@ -386,6 +847,24 @@ class Places:
     def from_dict(cls, data: Any): return _from_dict(cls, data)
 
 
+@dataclass
+class BoardPlaceUpdate:
+    place_number: Optional[int] = None
+    area: Optional[str] = None
+    start: Optional[bool] = None
+    end: Optional[bool] = None
+    @classmethod
+    def from_dict(cls, data: Any): return _from_dict(cls, data)
+
+
+@dataclass
+class BoardEditResult:
+    rows: int
+    warnings: list[str] = field(default_factory=list[str])
+    @classmethod
+    def from_dict(cls, data: Any): return _from_dict(cls, data)
+
+
 @dataclass
 class BoardPlace:
     board_id: int
@@ -418,6 +897,7 @@ class PlaceDrink:
     optional: bool
     on_table: bool
     n: int
+    non_alcoholic: Optional['Drink'] = None
     @classmethod
     def from_dict(cls, data: Any): return _from_dict(cls, data)
 
@@ -441,6 +921,13 @@ class Connections:
     def from_dict(cls, data: Any): return _from_dict(cls, data)
 
 
+@dataclass
+class BoardConnections:
+    connections: list['Connection'] = field(default_factory=list['Connection'])
+    @classmethod
+    def from_dict(cls, data: Any): return _from_dict(cls, data)
+
+
 @dataclass
 class Ingredient:
     id: int
@@ -464,6 +951,7 @@ class Drink:
     name: str
     favorite: bool
     no_mix_required: bool
+    non_alcoholic: Optional[int] = None
     @classmethod
     def from_dict(cls, data: Any): return _from_dict(cls, data)
 
@@ -501,6 +989,71 @@ class DrinksIngredients:
     def from_dict(cls, data: Any): return _from_dict(cls, data)
 
 
+@dataclass
+class IngredientStock:
+    ingredient: 'Ingredient'
+    opening: float
+    restocked: float
+    used: float
+    remaining: float
+    low: bool
+    low_stock: Optional[float] = None
+    @classmethod
+    def from_dict(cls, data: Any): return _from_dict(cls, data)
+
+
+@dataclass
+class GameInventory:
+    game_id: int
+    ingredients: list['IngredientStock'] = field(default_factory=list['IngredientStock'])
+    restocks: list['Restock'] = field(default_factory=list['Restock'])
+    @classmethod
+    def from_dict(cls, data: Any): return _from_dict(cls, data)
+
+
+@dataclass
+class Restock:
+    restock_id: int
+    ingredient_id: int
+    quantity: float
+    created_at: str
+    uid: Optional[int] = None
+    @classmethod
+    def from_dict(cls, data: Any): return _from_dict(cls, data)
+
+
+@dataclass
+class OpeningStock:
+    ingredient_id: int
+    opening: float
+    low_stock: Optional[float] = None
+    @classmethod
+    def from_dict(cls, data: Any): return _from_dict(cls, data)
+
+
+@dataclass
+class OpeningStockBody:
+    stock: list['OpeningStock'] = field(default_factory=list['OpeningStock'])
+    @classmethod
+    def from_dict(cls, data: Any): return _from_dict(cls, data)
+
+
+@dataclass
+class RestockBody:
+    ingredient_id: int
+    quantity: float
+    @classmethod
+    def from_dict(cls, data: Any): return _from_dict(cls, data)
+
+
+@dataclass
+class LowStockAlert:
+    game_id: int
+    ingredients: list['IngredientStock'] = field(default_factory=list['IngredientStock'])
+    @classmethod
+    def from_dict(cls, data: Any): return _from_dict(cls, data)
+
+
 @dataclass
 class ResultIntJson:
     int: int